@@ -0,0 +1,35 @@
+// Compiles the prelude into bytecode, so that vm-calc does not have to parse it each time it runs.
+// The compiler is shared with vm-calc itself, where it is linted, and only a part of it is used here
+#![allow(dead_code, clippy::all)]
+
+#[path = "src/ast.rs"] mod ast;
+#[path = "src/bytecode.rs"] mod bytecode;
+#[path = "src/errors.rs"] mod errors;
+#[path = "src/format.rs"] mod format;
+#[path = "src/instruction.rs"] mod instruction;
+#[path = "src/lexer.rs"] mod lexer;
+#[path = "src/parser.rs"] mod parser;
+#[path = "src/signatures.rs"] mod signatures;
+#[path = "src/symbolic.rs"] mod symbolic;
+#[path = "src/tokens.rs"] mod tokens;
+#[path = "src/utils.rs"] mod utils;
+
+use std::path::Path;
+
+use instruction::Instruction;
+
+fn main() {
+    // The bytecode changes along with the compiler
+    println!("cargo:rerun-if-changed=src");
+
+    let source = std::fs::read_to_string("src/prelude.txt").expect("Failed to read the prelude!");
+    let lexer = lexer::Lexer::new(&source).expect("Failed to initialize the lexer!");
+    let instructions = bytecode::Bytecode::new(parser::Parser::new(lexer)).generate_bytecode();
+    if instructions.first() == Some(&Instruction::CompileError) {
+        panic!("The prelude could not be compiled!");
+    }
+
+    let bytecode = bincode::serialize(&instructions).expect("Failed to serialize the bytecode of the prelude!");
+    let path = Path::new(&std::env::var("OUT_DIR").unwrap()).join("prelude.bin");
+    std::fs::write(path, bytecode).expect("Failed to write the bytecode of the prelude!");
+}
\ No newline at end of file
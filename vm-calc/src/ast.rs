@@ -2,7 +2,7 @@ use std::{fmt::Display, rc::Rc};
 
 use serde::{Deserialize, Serialize};
 
-use crate::{tokens::TokenType, utils::Span};
+use crate::{format::FormatSpec, instruction::Value, tokens::TokenType, utils::Span};
 
 // This is pretty redundant, and could probably be phased out for just using `TokenType`
 /// An operator
@@ -34,6 +34,14 @@ pub enum Operator {
     BitLeftShiftEqual,
     BitRightShiftEqual,
 
+    // Comparisons, which result in 1 when they are true and 0 otherwise
+    IsEqual,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+
     Equal,
 }
 
@@ -63,11 +71,37 @@ impl Display for Operator {
             Self::BitXorEqual => "^=",
             Self::BitLeftShiftEqual => "<<=",
             Self::BitRightShiftEqual => ">>=",
+            Self::IsEqual => "==",
+            Self::NotEqual => "!=",
+            Self::Less => "<",
+            Self::LessEqual => "<=",
+            Self::Greater => ">",
+            Self::GreaterEqual => ">=",
         };
         write!(f, "{res}")
     }
 }
 
+impl Operator {
+    /// The binary operation performed by an assignment operator like `+=`
+    pub fn without_assignment(self) -> Self {
+        match self {
+            Self::PlusEqual => Self::Plus,
+            Self::MinusEqual => Self::Minus,
+            Self::DivideEqual => Self::Divide,
+            Self::MultiplyEqual => Self::Multiply,
+            Self::ModuloEqual => Self::Modulo,
+            Self::ExponentEqual => Self::Exponent,
+            Self::BitAndEqual => Self::BitAnd,
+            Self::BitOrEqual => Self::BitOr,
+            Self::BitXorEqual => Self::BitXor,
+            Self::BitLeftShiftEqual => Self::BitLeftShift,
+            Self::BitRightShiftEqual => Self::BitRightShift,
+            operator => operator,
+        }
+    }
+}
+
 impl From<TokenType> for Operator {    
     fn from(value: TokenType) -> Self {
         match value {
@@ -89,6 +123,12 @@ impl From<TokenType> for Operator {
             TokenType::BitOr => Self::BitOr,
             TokenType::BitLeftShift => Self::BitLeftShift,
             TokenType::BitRightShift => Self::BitRightShift,
+            TokenType::IsEqual => Self::IsEqual,
+            TokenType::NotEqual => Self::NotEqual,
+            TokenType::Less => Self::Less,
+            TokenType::LessEqual => Self::LessEqual,
+            TokenType::Greater => Self::Greater,
+            TokenType::GreaterEqual => Self::GreaterEqual,
 
             _ => panic!("A bug has occured when trying to convert `{value:?}` to `Operator`"),
         }
@@ -115,6 +155,83 @@ impl Display for Tree<'_> {
     }
 }
 
+/// The bounds of a slice like `[start:end:step]`, any of which can be left out
+#[derive(Debug, Clone, PartialEq)]
+pub struct SliceBounds<'a> {
+    pub(crate) start: Option<Rc<Tree<'a>>>,
+    pub(crate) end: Option<Rc<Tree<'a>>>,
+    pub(crate) step: Option<Rc<Tree<'a>>>,
+}
+
+impl Display for SliceBounds<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let bound = |bound: &Option<Rc<Tree>>| bound.as_ref().map(|bound| format!("{bound}")).unwrap_or_default();
+        write!(f, "{}:{}", bound(&self.start), bound(&self.end))?;
+        if self.step.is_some() {
+            write!(f, ":{}", bound(&self.step))?;
+        }
+        Ok(())
+    }
+}
+
+/// A pattern of a match arm, which a value is checked against
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Pattern<'a> {
+    /// `_`, which matches any value
+    Wildcard,
+    /// A name, which matches any value and binds it to the name
+    Binding { name: &'a str },
+    /// A number, a string or `Null`, which matches a value that is equal to it
+    Literal { value: Value },
+    /// `[a, b]`, which matches an array of the same length whose values match the patterns
+    Array {
+        #[serde(borrow)]
+        patterns: Vec<Pattern<'a>>,
+    },
+    /// `n: Number`, which matches a value of the type that also matches the pattern
+    Type {
+        #[serde(borrow)]
+        pattern: Box<Pattern<'a>>,
+        type_name: &'a str,
+    },
+}
+
+impl Pattern<'_> {
+    /// The types that can be used in a type pattern, which are the ones given by `typeof` without the braces
+    pub const TYPES: [&'static str; 7] = ["Null", "Number", "String", "Function", "PartialFunction", "Array", "Map"];
+
+    /// Whether the pattern only has names, `_` and arrays of them, which are the patterns that can be assigned to
+    pub fn is_destructuring(&self) -> bool {
+        match self {
+            Self::Wildcard | Self::Binding { .. } => true,
+            Self::Array { patterns } => patterns.iter().all(Self::is_destructuring),
+            Self::Literal { .. } | Self::Type { .. } => false,
+        }
+    }
+}
+
+impl Display for Pattern<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Wildcard => write!(f, "_"),
+            Self::Binding { name } => write!(f, "{name}"),
+            Self::Literal { value: Value::String(string) } => write!(f, "{string:?}"),
+            Self::Literal { value: Value::Null } => write!(f, "Null"),
+            Self::Literal { value } => write!(f, "{value}"),
+            Self::Array { patterns } => write!(f, "[{}]", patterns.iter().map(|pattern| pattern.to_string()).collect::<Vec<_>>().join(", ")),
+            Self::Type { pattern, type_name } => write!(f, "{pattern}: {type_name}"),
+        }
+    }
+}
+
+/// An arm of a match, `pattern if guard => body`
+#[derive(Debug, PartialEq)]
+pub struct MatchArm<'a> {
+    pub(crate) pattern: Pattern<'a>,
+    pub(crate) guard: Option<Rc<Tree<'a>>>,
+    pub(crate) body: Rc<Tree<'a>>,
+}
+
 #[derive(Debug, PartialEq)]
 pub enum AST<'a> {
     /// Binary operations applied to numbers, and strings where applicable
@@ -147,6 +264,12 @@ pub enum AST<'a> {
         value: Rc<Tree<'a>>,
     },
 
+    /// Declaring the names of a pattern like `let [a, [b, c]] = value`, and assigning them the parts of the value
+    DeclarePattern {
+        pattern: Pattern<'a>,
+        value: Rc<Tree<'a>>,
+    },
+
     /// Declaring a variable
     Declare {
         identifier: &'a str,
@@ -161,6 +284,12 @@ pub enum AST<'a> {
         value: Rc<Tree<'a>>,
     },
 
+    /// Assigning the parts of a value to existing variables, like `[a, b] = [b, a]`
+    AssignPattern {
+        pattern: Pattern<'a>,
+        value: Rc<Tree<'a>>,
+    },
+
     /// Assign + operator for a variable
     AssignOp {
         identifier: &'a str,
@@ -169,12 +298,14 @@ pub enum AST<'a> {
         operator: Operator,
     },
 
-    /// Assign data to an array at the given index or indices for a multi-dimensional array
+    /// Assign data to an array at the given index or indices for a multi-dimensional array,
+    /// or to a slice of the array after the indices
     AssignIndex {
         identifier: &'a str,
         value: Rc<Tree<'a>>,
         operator: Operator,
         indicies: Vec<Rc<Tree<'a>>>,
+        slice: Option<SliceBounds<'a>>,
     },
 
     /// Tells the VM to push the result to the output
@@ -192,13 +323,27 @@ pub enum AST<'a> {
     FunctionDecl {
         name: &'a str,
         arguments: Vec<&'a str>,
+        /// The default values of the last arguments
+        defaults: Vec<Rc<Tree<'a>>>,
+        /// The argument after the others that collects the rest of the arguments into an array, `...xs`
+        rest: Option<&'a str>,
         body: Rc<Tree<'a>>,
     },
 
-    /// Partially calls a function
-    PartialCall {
+    /// An anonymous function, `\x y -> x + y`
+    Lambda {
+        arguments: Vec<&'a str>,
+        /// The default values of the last arguments
+        defaults: Vec<Rc<Tree<'a>>>,
+        /// The argument after the others that collects the rest of the arguments into an array, `...xs`
+        rest: Option<&'a str>,
+        body: Rc<Tree<'a>>,
+    },
+
+    /// An argument given to a function by its name, `f(1, y: 2)`
+    NamedArgument {
         name: &'a str,
-        expressions: Vec<Rc<Tree<'a>>>,
+        value: Rc<Tree<'a>>,
     },
 
     /// Used to delete variables or functions
@@ -206,6 +351,13 @@ pub enum AST<'a> {
         name: &'a str,
     },
 
+    /// Runs another file as a module, `import "path" as name`. Without a name, its declarations are added to the current ones
+    Import {
+        /// The string with the path of the file, relative to the file that imports it
+        path: Rc<Tree<'a>>,
+        alias: Option<&'a str>,
+    },
+
     /// Invokes the print function
     Print {
         expressions: Vec<Rc<Tree<'a>>>,
@@ -216,6 +368,34 @@ pub enum AST<'a> {
         contents: String,
     },
 
+    /// A format string, made up of strings and formatted expressions that are joined together
+    FormatString {
+        parts: Vec<Rc<Tree<'a>>>,
+    },
+
+    /// An expression within a format string, which is converted to a string following the specifier
+    Format {
+        value: Rc<Tree<'a>>,
+        spec: FormatSpec,
+    },
+
+    /// The derivative of an expression by a variable, `diff(x ** 2, x)`. It is worked out when parsing,
+    /// and the simplified expression is the result
+    Derivative {
+        expression: Rc<Tree<'a>>,
+        variable: &'a str,
+        result: Rc<Tree<'a>>,
+    },
+
+    /// Solving equations for names, `solve x ** 2 = 4 for x` or `solve [x + y = 2, x - y = 0] for [x, y]` for a system of linear
+    /// equations. The equations are turned into a call that finds the roots or solves the system when parsing, which is the result
+    Solve {
+        equations: Vec<(Rc<Tree<'a>>, Rc<Tree<'a>>)>,
+        variables: Vec<&'a str>,
+        system: bool,
+        result: Rc<Tree<'a>>,
+    },
+
     /// Used for direct function calls to a variable
     Name {
         value: &'a str,
@@ -231,22 +411,53 @@ pub enum AST<'a> {
         expressions: Vec<Rc<Tree<'a>>>,
     },
 
-    /// Index an array to get the value
+    /// Create a map from pairs of keys and values
+    Map {
+        entries: Vec<(Rc<Tree<'a>>, Rc<Tree<'a>>)>,
+    },
+
+    /// Index an array or a map to get the value. A field of a map, `map.key`, is an index by a string
     Index {
         to_index: Rc<Tree<'a>>,
         expression: Rc<Tree<'a>>,
     },
 
+    /// Take a part of an array or a string
+    Slice {
+        to_slice: Rc<Tree<'a>>,
+        bounds: SliceBounds<'a>,
+    },
+
+    /// Checks a value against the patterns of the arms in order, and results in the body of the first arm that matches
+    Match {
+        value: Rc<Tree<'a>>,
+        arms: Vec<MatchArm<'a>>,
+    },
+
     /// A null value
     Null,
 }
 
+/// Writes the arguments of a function, along with the default values of the last ones and the rest argument: `x (y = 2) ...z`
+pub(crate) fn parameters(arguments: &[&str], defaults: &[impl Display], rest: Option<&str>) -> String {
+    let required = arguments.len() - defaults.len();
+    let mut parameters = arguments[..required].iter().map(|argument| argument.to_string()).collect::<Vec<_>>();
+    for (argument, default) in arguments[required..].iter().zip(defaults) {
+        parameters.push(format!("({argument} = {default})"));
+    }
+    if let Some(rest) = rest {
+        parameters.push(format!("...{rest}"));
+    }
+    parameters.join(" ")
+}
+
 impl Display for AST<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::Null => write!(f, "{}Null{}", "{", "}"),
             Self::TypeOf { expression } => write!(f, "<TypeOf> ({expression})"),
             Self::Index { to_index, expression } => write!(f, "({to_index}[{expression}])"),
+            Self::Slice { to_slice, bounds } => write!(f, "({to_slice}[{bounds}])"),
             
             Self::BinaryOp { lhs, rhs, op } => write!(f, "({lhs} {op} {rhs})"),
             Self::UnaryOp { rhs, op } => write!(f, "({op}{rhs})"),
@@ -256,23 +467,45 @@ impl Display for AST<'_> {
             
             Self::Identifier { name } => write!(f, "{name}"),
             Self::String { contents } => write!(f, "\"{contents}\""),
+            Self::FormatString { parts } => {
+                write!(f, "f\"")?;
+                for part in parts {
+                    match &part.ast {
+                        Self::String { contents } => write!(f, "{contents}")?,
+                        _ => write!(f, "{part}")?,
+                    }
+                }
+                write!(f, "\"")
+            },
+            Self::Format { value, spec } if spec.to_string().is_empty() => write!(f, "{{{value}}}"),
+            Self::Format { value, spec } => write!(f, "{{{value}:{spec}}}"),
             Self::Delete { name } => write!(f, "(delete {name})"),
+            Self::Import { path, alias: Some(alias) } => write!(f, "(import {path} as {alias})"),
+            Self::Import { path, alias: None } => write!(f, "(import {path})"),
 
             Self::Assign { identifier, value, .. } => write!(f, "({identifier} = {value})"),
+            Self::AssignPattern { pattern, value } => write!(f, "({pattern} = {value})"),
             Self::AssignOp { operator, identifier, value, .. } => write!(f, "({identifier} {operator} {value})"),
             
-            Self::AssignIndex { identifier, value, indicies, operator } => {
+            Self::AssignIndex { identifier, value, indicies, operator, slice } => {
                 let mut arguments = String::new();
                 for expr in indicies {
                     arguments = format!("{arguments}[{expr}]");
                 }
+                if let Some(slice) = slice {
+                    arguments = format!("{arguments}[{slice}]");
+                }
                 write!(f, "({identifier}{arguments} {operator} {value})")
             }
             
             Self::DeclareAssign { identifier, value, .. } => write!(f, "(let {identifier} = {value})"),
+            Self::DeclarePattern { pattern, value } => write!(f, "(let {pattern} = {value})"),
             Self::Declare { identifier, identifier_span: _ } =>  write!(f, "(let {identifier})"),
             
-            Self::FunctionDecl { name, arguments, body } => write!(f, "(let {name} {} = {body})", arguments.join(" ")),
+            Self::FunctionDecl { name, arguments, defaults, rest, body } => write!(f, "(let {name} {} = {body})", parameters(arguments, defaults, *rest)),
+            Self::Lambda { arguments, rest: None, body, .. } if arguments.is_empty() => write!(f, "(\\_ -> {body})"),
+            Self::Lambda { arguments, defaults, rest, body } => write!(f, "(\\{} -> {body})", parameters(arguments, defaults, *rest)),
+            Self::NamedArgument { name, value } => write!(f, "{name}: {value}"),
             Self::FunctionCall { name, expressions } => {
                 let mut arguments = String::new();
                 for expr in expressions {
@@ -285,17 +518,6 @@ impl Display for AST<'_> {
                 write!(f, "{name}({})", arguments)
             }
 
-            Self::PartialCall { name, expressions } => {
-                let mut arguments = String::new();
-                for expr in expressions {
-                    if arguments.is_empty() {
-                        arguments = format!("{expr}");
-                    } else {
-                        arguments = format!("{arguments}, {expr}");
-                    }
-                }
-                write!(f, "(<PARTIAL> {name}({}))", arguments)
-            }
 
 
             Self::Print { expressions } => {
@@ -314,6 +536,16 @@ impl Display for AST<'_> {
                 write!(f, "{value}")
             },
 
+            Self::Derivative { expression, variable, .. } => write!(f, "diff({expression}, {variable})"),
+            Self::Solve { equations, variables, system, .. } => {
+                let equations = equations.iter().map(|(lhs, rhs)| format!("{lhs} = {rhs}")).collect::<Vec<_>>().join(", ");
+                if *system {
+                    write!(f, "(solve [{equations}] for [{}])", variables.join(", "))
+                } else {
+                    write!(f, "(solve {equations} for {})", variables.join(", "))
+                }
+            }
+
             Self::Array { expressions } => {
                 let mut expression_str = String::new();
                 for expr in expressions {
@@ -326,6 +558,19 @@ impl Display for AST<'_> {
 
                 write!(f, "[{}]", expression_str)
             }
+
+            Self::Match { value, arms } => {
+                let arms = arms.iter().map(|arm| match &arm.guard {
+                    Some(guard) => format!("{} if {guard} => {}", arm.pattern, arm.body),
+                    None => format!("{} => {}", arm.pattern, arm.body),
+                });
+                write!(f, "(match {value} {{{}}})", arms.collect::<Vec<_>>().join(", "))
+            }
+
+            Self::Map { entries } => {
+                let entries = entries.iter().map(|(key, value)| format!("{key}: {value}")).collect::<Vec<_>>();
+                write!(f, "{{{}}}", entries.join(", "))
+            }
         }
     }
 }
\ No newline at end of file
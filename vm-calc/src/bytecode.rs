@@ -1,13 +1,24 @@
-use std::{borrow::Borrow, collections::HashMap, rc::Rc};
-use crate::{ast::{Tree, AST}, errors::Error, instruction::{Instruction, Symbol, Value}, parser::Parser};
+use std::{borrow::Borrow, collections::HashMap, path::{Path, PathBuf}, rc::Rc};
+use crate::{ast::{Operator, SliceBounds, Tree, AST}, errors::Error, signatures::get_constant, instruction::{Instruction, Symbol, Value}, lexer::Lexer, parser::Parser, utils::Span};
 
 pub struct Bytecode<'a> {
     parser: Parser<'a>,
+    /// The trees of the functions declared in the parsed code, used to inspect them later
+    pub(crate) declarations: HashMap<&'a str, Rc<Tree<'a>>>,
+    /// The file of the code, which the paths of its imports are relative to. Otherwise they are relative to the current directory
+    path: Option<PathBuf>,
+    /// The files of the modules that are being compiled, up to this one, to find modules that import themselves
+    importing: Vec<PathBuf>,
 }
 
 impl<'a> Bytecode<'a> {
     pub fn new(parser: Parser<'a>) -> Self {
-        Self { parser }
+        Self { parser, declarations: HashMap::new(), path: None, importing: vec![] }
+    }
+
+    pub fn new_in_file(parser: Parser<'a>, path: &str) -> Self {
+        let path = Path::new(path).canonicalize().ok();
+        Self { parser, declarations: HashMap::new(), importing: path.iter().cloned().collect(), path }
     }
 
     pub fn generate_bytecode(&mut self) -> Vec<Instruction<'a>> {
@@ -16,6 +27,7 @@ impl<'a> Bytecode<'a> {
             match self.parser.next_expression() {
                 Ok(tree) => {
                     let mut instructions = Self::traverse(&tree);
+                    self.import(&mut instructions, tree.span);
 
                     if instructions.get(0) == Some(&Instruction::CompileError) {
                         complete_bytecode.clear();
@@ -47,6 +59,7 @@ impl<'a> Bytecode<'a> {
                                     }
                                 }
                             }
+                            self.declare(name, &tree);
                         }
                         complete_bytecode.append(&mut instructions);
                     }
@@ -75,6 +88,7 @@ impl<'a> Bytecode<'a> {
             match self.parser.next_expression_repl() {
                 Ok(tree) => {
                     let mut instructions = Self::traverse(&tree);
+                    self.import(&mut instructions, tree.span);
                     if instructions.get(0) == Some(&Instruction::CompileError) {
                         complete_bytecode.clear();
                         complete_bytecode.push(Instruction::CompileError);
@@ -88,7 +102,7 @@ impl<'a> Bytecode<'a> {
                                     for instruction_test in &function_bytecode {
                                         if let Instruction::FunctionCall { name: other_calling, .. } = instruction_test {
                                             // If another function calls this function, we are experiencing recursion
-                                            if *other_calling == Some(&name) {
+                                            if *other_calling == Some(name) {
                                                 let error = 
                                                     Error::PError { 
                                                         message: 
@@ -106,9 +120,13 @@ impl<'a> Bytecode<'a> {
                                     }
                                 }
                             }
+                            self.declare(name, &tree);
                             if let (Instruction::UData { .. }, Instruction::UData { number: end}) = (&instructions[1], &instructions[2]) {
                                 function_bytecode.append(&mut (instructions[0..=(0 + end + 2)]).to_vec());
                             }
+                        } else if let Instruction::Import { len, .. } = instructions[0] {
+                            // Modules are kept along with the functions, so that the functions they declare can still be called
+                            function_bytecode.extend_from_slice(&instructions[..=len]);
                         }
                         complete_bytecode.append(&mut instructions);
                     }
@@ -131,6 +149,66 @@ impl<'a> Bytecode<'a> {
         (complete_bytecode, function_bytecode)
     }
 
+    /// Remember the tree of a function declaration, which may have been wrapped in an output by the repl
+    fn declare(&mut self, name: &'a str, tree: &Rc<Tree<'a>>) {
+        let declaration = match tree.ast.borrow() {
+            AST::Output { value } => value,
+            _ => tree,
+        };
+        self.declarations.insert(name, Rc::clone(declaration));
+    }
+
+    /// Compiles the module of an import into the instructions after it. Any error replaces the instructions with a compile error
+    fn import(&self, instructions: &mut Vec<Instruction<'a>>, span: Span) {
+        let Some(Instruction::Import { module: path, .. }) = instructions.first() else {
+            return;
+        };
+        match self.module(path) {
+            Ok((file, module)) => {
+                if let Instruction::Import { module: path, len, .. } = &mut instructions[0] {
+                    *path = file;
+                    *len = module.len();
+                }
+                instructions.splice(1..1, module);
+            }
+            Err(message) => {
+                println!("{}", Error::PError { message, span });
+                *instructions = vec![Instruction::CompileError];
+            }
+        }
+    }
+
+    /// Reads and compiles the file of a module, giving its full path along with its instructions
+    fn module(&self, path: &str) -> Result<(String, Vec<Instruction<'a>>), String> {
+        let directory = self.path.as_deref().and_then(Path::parent).unwrap_or(Path::new(""));
+        let file = match directory.join(path).canonicalize() {
+            Ok(file) => file,
+            Err(error) => return Err(format!("The module `{path}` could not be found: {error}")),
+        };
+        if self.importing.contains(&file) {
+            return Err(format!("The module `{path}` is imported by itself, through the modules that it imports!"));
+        }
+
+        let source = match std::fs::read_to_string(&file) {
+            Ok(source) => source,
+            Err(error) => return Err(format!("The module `{path}` could not be read: {error}")),
+        };
+        // The instructions refer to the source of the module, so it is kept for as long as the program runs
+        let source: &'a str = Box::leak(source.into_boxed_str());
+        let Ok(lexer) = Lexer::new(source) else {
+            return Err(format!("The module `{path}` could not be read!"));
+        };
+
+        let mut importing = self.importing.clone();
+        importing.push(file.clone());
+        let mut bytecode = Self { parser: Parser::new(lexer), declarations: HashMap::new(), path: Some(file.clone()), importing };
+        let instructions = bytecode.generate_bytecode();
+        if instructions.first() == Some(&Instruction::CompileError) {
+            return Err(format!("The module `{path}` could not be compiled!"));
+        }
+        Ok((file.to_string_lossy().into_owned(), instructions))
+    }
+
     pub fn get_symbols(self) -> HashMap<Symbol<'a>, usize> {
         self.parser.symbols
     }
@@ -164,6 +242,18 @@ impl<'a> Bytecode<'a> {
                 instructions
             }
 
+            AST::DeclarePattern { pattern, value } => {
+                let mut instructions = Self::traverse(value);
+                instructions.push(Instruction::LoadPattern { pattern: pattern.clone() });
+                instructions
+            }
+
+            AST::AssignPattern { pattern, value } => {
+                let mut instructions = Self::traverse(value);
+                instructions.push(Instruction::ReloadPattern { pattern: pattern.clone() });
+                instructions
+            }
+
             AST::Assign { identifier, value, .. } => {
                 let mut instructions = Self::traverse(value);
                 instructions.push(Instruction::ReloadSymbol { name: identifier });
@@ -177,8 +267,18 @@ impl<'a> Bytecode<'a> {
                 instructions
             }
             
+            AST::Solve { result, .. } => Self::traverse(result),
+
+            AST::Derivative { result, .. } => {
+                vec![Instruction::Load { value: Value::String(result.to_string()) }]
+            }
+
             AST::Identifier { name } => {
-                vec![Instruction::CallSymbol { name }]
+                // The parser does not allow constants to be declared, so the name always means the constant
+                match get_constant(name) {
+                    Ok(value) => vec![Instruction::Load { value: Value::Number(value) }],
+                    Err(..) => vec![Instruction::CallSymbol { name }],
+                }
             }
 
             AST::Output { value } => {
@@ -187,43 +287,32 @@ impl<'a> Bytecode<'a> {
                 instructions
             }
 
-            AST::PartialCall { name, expressions } => {
-                let mut instructions = vec![];
-                for expr in expressions {
-                    instructions.extend(Self::traverse(expr));
-                }
-                instructions.push(Instruction::PartialCall { name, len: expressions.len() });
-                instructions
-            }
-
             AST::FunctionCall { name, expressions } => {
                 let mut instructions = vec![];
+                let mut names = vec![];
                 for expr in expressions {
                     instructions.extend(Self::traverse(expr));
+                    if let AST::NamedArgument { name, .. } = expr.ast {
+                        names.push(name);
+                    }
                 }
                 match name.ast.borrow() {
                     AST::Name { value } => {
-                        instructions.push(Instruction::FunctionCall { name: Some(&value), len: expressions.len() });
+                        instructions.push(Instruction::FunctionCall { name: Some(value), len: expressions.len(), names });
                     }
 
                     _ => {
                         instructions.extend(Self::traverse(name));
-                        instructions.push(Instruction::FunctionCall { name: None, len: expressions.len() });
+                        instructions.push(Instruction::FunctionCall { name: None, len: expressions.len(), names });
                     }
                 }
                 instructions
             }
 
-            AST::FunctionDecl { name, arguments, body } => {
-                let mut instructions = vec![Instruction::FunctionDecl { name }];
-               
-                instructions.push(Instruction::UData { number: arguments.len() });
-                instructions.push(Instruction::UData { number: 0 });
+            AST::NamedArgument { value, .. } => Self::traverse(value),
 
-                instructions.extend(arguments.iter().map(|name: &&str| Instruction::ArgumentName { name }));
-                instructions.extend(Self::traverse(body));
-                let end = instructions.len() - 1;
-                instructions[2] = Instruction::UData { number: end - 2 };
+            AST::FunctionDecl { name, arguments, defaults, rest, body } => {
+                let mut instructions = Self::function(Instruction::FunctionDecl { name }, arguments, defaults, *rest, body);
 
                 // Check for recursion. Recursion makes no sense with single statement functions
                 for instruction in &instructions {
@@ -241,8 +330,21 @@ impl<'a> Bytecode<'a> {
                 instructions
             }
 
+            AST::Lambda { arguments, defaults, rest, body } => {
+                let mut instructions = Self::function(Instruction::Lambda { captures: vec![] }, arguments, defaults, *rest, body);
+                let captures = Self::captures(&instructions[3..], arguments, *rest);
+                instructions[0] = Instruction::Lambda { captures };
+                instructions
+            }
+
             AST::Delete { name } => vec![Instruction::Delete { name }],
 
+            // The module is compiled into the instructions after this one by `import`, since it needs the path of the file
+            AST::Import { path, alias } => match &path.ast {
+                AST::String { contents } => vec![Instruction::Import { module: contents.clone(), alias: *alias, len: 0 }],
+                _ => vec![Instruction::CompileError],
+            },
+
             AST::Null => vec![Instruction::Null],
 
             AST::TypeOf { expression } => {
@@ -266,6 +368,22 @@ impl<'a> Bytecode<'a> {
                 vec![Instruction::Load { value: Value::String((*contents).to_owned()) }]
             }
 
+            // The parts are each converted to a string and then added together
+            AST::FormatString { parts } => {
+                let mut instructions = vec![Instruction::Load { value: Value::String(String::new()) }];
+                for part in parts {
+                    instructions.extend(Self::traverse(part));
+                    instructions.push(Instruction::Binary { operator: Operator::Plus });
+                }
+                instructions
+            }
+
+            AST::Format { value, spec } => {
+                let mut instructions = Self::traverse(value);
+                instructions.push(Instruction::Format { spec: spec.clone() });
+                instructions
+            }
+
             AST::Array { expressions } => {
                 let mut instructions = vec![];
                 for expr in expressions {
@@ -275,6 +393,53 @@ impl<'a> Bytecode<'a> {
                 instructions
             }
 
+            // Each arm checks its pattern and guard, jumping to the next arm when either fails.
+            // The arms jump to the end once their body is done, which is only known after all of them
+            AST::Match { value, arms } => {
+                let mut instructions = Self::traverse(value);
+                let mut ends = vec![];
+                for arm in arms {
+                    instructions.push(Instruction::MatchPattern { pattern: arm.pattern.clone() });
+                    let pattern_jump = instructions.len();
+                    instructions.push(Instruction::JumpIfFalse { offset: 0 });
+
+                    let mut guard_jump = None;
+                    if let Some(guard) = &arm.guard {
+                        instructions.extend(Self::traverse(guard));
+                        guard_jump = Some(instructions.len());
+                        instructions.push(Instruction::JumpIfFalse { offset: 0 });
+                    }
+
+                    instructions.push(Instruction::Pop);
+                    instructions.extend(Self::traverse(&arm.body));
+                    instructions.push(Instruction::Unbind);
+                    ends.push(instructions.len());
+                    instructions.push(Instruction::Jump { offset: 0 });
+
+                    // The names were bound before the guard failed, so they are unbound before the next arm
+                    if let Some(guard_jump) = guard_jump {
+                        instructions[guard_jump] = Instruction::JumpIfFalse { offset: instructions.len() - guard_jump - 1 };
+                        instructions.push(Instruction::Unbind);
+                    }
+                    instructions[pattern_jump] = Instruction::JumpIfFalse { offset: instructions.len() - pattern_jump - 1 };
+                }
+                instructions.push(Instruction::NoMatch);
+                for end in ends {
+                    instructions[end] = Instruction::Jump { offset: instructions.len() - end - 1 };
+                }
+                instructions
+            }
+
+            AST::Map { entries } => {
+                let mut instructions = vec![];
+                for (key, value) in entries {
+                    instructions.extend(Self::traverse(key));
+                    instructions.extend(Self::traverse(value));
+                }
+                instructions.push(Instruction::Map { len: entries.len() });
+                instructions
+            }
+
             AST::Index { to_index, expression } => {
                 let mut instructions = vec![];
                 instructions.extend(Self::traverse(to_index));
@@ -283,19 +448,82 @@ impl<'a> Bytecode<'a> {
                 instructions
             }
 
-            AST::AssignIndex { identifier, value, indicies, operator } => {
+            AST::AssignIndex { identifier, value, indicies, operator, slice } => {
                 let mut instructions = vec![];
-                for index in indicies {
+                // The bounds of the slice are used after the indices, so they are deeper in the stack
+                if let Some(bounds) = slice {
+                    instructions.extend(Self::traverse_bounds(bounds));
+                }
+                // The first index has to be on the top of the stack
+                for index in indicies.iter().rev() {
                     instructions.extend(Self::traverse(index));
                 }
-                instructions.reverse();
                 instructions.extend(Self::traverse(value));
-                instructions.push(Instruction::ReloadIndex { name: identifier, depth: indicies.len(), operator: *operator });
+                instructions.push(Instruction::ReloadIndex { name: identifier, depth: indicies.len(), operator: *operator, slice: slice.is_some() });
+                instructions
+            }
+
+            AST::Slice { to_slice, bounds } => {
+                let mut instructions = Self::traverse(to_slice);
+                instructions.extend(Self::traverse_bounds(bounds));
+                instructions.push(Instruction::Slice);
                 instructions
             }
 
             _ => vec![Instruction::Illegal],
         }
     }
-    
+
+    /// Pushes the start, end and step of a slice, using a null value for the ones that are left out
+    fn traverse_bounds(bounds: &SliceBounds<'a>) -> Vec<Instruction<'a>> {
+        let mut instructions = vec![];
+        for bound in [&bounds.start, &bounds.end, &bounds.step] {
+            match bound {
+                Some(bound) => instructions.extend(Self::traverse(bound)),
+                None => instructions.push(Instruction::Null),
+            }
+        }
+        instructions
+    }
+
+    /// The names used by the instructions of a lambda other than its own arguments, including those that the lambdas
+    /// within it capture
+    fn captures(instructions: &[Instruction<'a>], arguments: &[&'a str], rest: Option<&'a str>) -> Vec<&'a str> {
+        let mut captures = vec![];
+        for instruction in instructions {
+            let names = match instruction {
+                Instruction::CallSymbol { name } | Instruction::ReloadSymbol { name } | Instruction::ReloadSymbolOp { name }
+                    | Instruction::ReloadIndex { name, .. } | Instruction::FunctionCall { name: Some(name), .. } => vec![*name],
+                Instruction::Lambda { captures } => captures.clone(),
+                _ => continue,
+            };
+            for name in names {
+                if !arguments.contains(&name) && rest != Some(name) && !captures.contains(&name) {
+                    captures.push(name);
+                }
+            }
+        }
+        captures
+    }
+
+    /// Lays out a function after the instruction that declares it: the number of arguments and the length of the rest,
+    /// then the arguments, each default value following its argument, the rest argument and finally the body
+    fn function(declaration: Instruction<'a>, arguments: &[&'a str], defaults: &[Rc<Tree<'a>>], rest: Option<&'a str>, body: &Rc<Tree<'a>>) -> Vec<Instruction<'a>> {
+        let mut instructions = vec![declaration];
+        instructions.push(Instruction::UData { number: arguments.len() + rest.is_some() as usize });
+        instructions.push(Instruction::UData { number: 0 });
+
+        let required = arguments.len() - defaults.len();
+        instructions.extend(arguments[..required].iter().map(|name| Instruction::ArgumentName { name }));
+        for (name, default) in arguments[required..].iter().zip(defaults) {
+            let default = Self::traverse(default);
+            instructions.push(Instruction::DefaultArgument { name, len: default.len() });
+            instructions.extend(default);
+        }
+        instructions.extend(rest.map(|name| Instruction::RestArgument { name }));
+        instructions.extend(Self::traverse(body));
+        let end = instructions.len() - 1;
+        instructions[2] = Instruction::UData { number: end - 2 };
+        instructions
+    }
 }
\ No newline at end of file
@@ -0,0 +1,201 @@
+use crate::{functions::{expect_function, expect_number, ValueFunction}, instruction::Value, vm::VM};
+
+pub const FUNCTIONS: [(&str, (usize, ValueFunction)); 4] = [
+    ("deriv", (2, deriv)),
+    ("integrate", (3, integrate)),
+    ("solve", (2, solve)),
+    ("minimize", (3, minimize)),
+];
+
+/// How close the results are to the exact answers, which is relative to the area for areas larger than 1
+const TOLERANCE: f64 = 1e-10;
+/// The most steps taken by the methods that get closer to an answer on each step
+const MAX_STEPS: usize = 200;
+/// How many times an interval can be halved when integrating
+const MAX_DEPTH: usize = 50;
+/// The most times the function is called when integrating, for functions that never settle, like random ones
+const MAX_EVALUATIONS: usize = 100_000;
+/// How many intervals the integration starts from, so that the first samples are less likely to miss a jump of the function
+const PANELS: usize = 16;
+
+/// Calls the function given to a builtin with a number, which it should return a number for
+fn evaluate(vm: &mut VM, builtin: &str, function: &Value, x: f64) -> Result<f64, String> {
+    match vm.call(function, vec![Value::Number(x)])? {
+        Value::Number(number) => Ok(number),
+        value => Err(format!("The function given to `{builtin}` should return a value of type {{Number}} but returned a value of type {}!", value.type_of())),
+    }
+}
+
+/// The derivative by central differences, with a step that is halved once to cancel out most of the error (Richardson extrapolation)
+fn derivative(vm: &mut VM, builtin: &str, function: &Value, x: f64) -> Result<f64, String> {
+    let h = 1e-3 * x.abs().max(1.0);
+    let mut difference = |h: f64| -> Result<f64, String> {
+        Ok((evaluate(vm, builtin, function, x + h)? - evaluate(vm, builtin, function, x - h)?) / (2.0 * h))
+    };
+    let (wide, narrow) = (difference(h)?, difference(h / 2.0)?);
+    Ok((4.0 * narrow - wide) / 3.0)
+}
+
+fn deriv(vm: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    expect_function("deriv", &values[0])?;
+    let x = expect_number("deriv", &values[1])?;
+    Ok(Value::Number(derivative(vm, "deriv", &values[0], x)?))
+}
+
+/// Adaptive Simpson's rule: an interval is split in half until Simpson's rule gives the same area for it as for its halves.
+/// The integration starts from several intervals of the same width
+fn integrate(vm: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    expect_function("integrate", &values[0])?;
+    let a = expect_number("integrate", &values[1])?;
+    let b = expect_number("integrate", &values[2])?;
+    let function = &values[0];
+
+    let mut evaluations = 0;
+    let mut points = vec![];
+    for index in 0..=2 * PANELS {
+        let x = if index == 2 * PANELS { b } else { a + (b - a) * index as f64 / (2 * PANELS) as f64 };
+        points.push((x, sample(vm, function, x, &mut evaluations)?));
+    }
+    let panels = points.windows(3).step_by(2).map(|points| {
+        let [(a, fa), (_, fm), (b, fb)] = [points[0], points[1], points[2]];
+        ((a, fa), (b, fb), fm, (b - a) / 6.0 * (fa + 4.0 * fm + fb))
+    }).collect::<Vec<_>>();
+
+    let whole = panels.iter().map(|(.., area)| area).sum::<f64>();
+    let tolerance = TOLERANCE * whole.abs().max(1.0) / PANELS as f64;
+    let mut area = 0.0;
+    for (a, b, fm, whole) in panels {
+        area += simpson(vm, function, a, b, fm, whole, tolerance, MAX_DEPTH, &mut evaluations)?;
+    }
+    if !area.is_finite() {
+        return Err(format!("The function given to `integrate` cannot be integrated from {a} to {b}, since the area is not finite!"));
+    }
+    Ok(Value::Number(area))
+}
+
+/// Calls the function being integrated, counting the calls so that the integration stops once there have been too many
+fn sample(vm: &mut VM, function: &Value, x: f64, evaluations: &mut usize) -> Result<f64, String> {
+    *evaluations += 1;
+    if *evaluations > MAX_EVALUATIONS {
+        return Err(format!("The function given to `integrate` could not be integrated within {MAX_EVALUATIONS} calls, since the area does not settle!"));
+    }
+    evaluate(vm, "integrate", function, x)
+}
+
+/// The area from `a` to `b`, given the values at both ends and in the middle along with the area by Simpson's rule
+#[allow(clippy::too_many_arguments)]
+fn simpson(vm: &mut VM, function: &Value, (a, fa): (f64, f64), (b, fb): (f64, f64), fm: f64, whole: f64, tolerance: f64, depth: usize, evaluations: &mut usize) -> Result<f64, String> {
+    let m = (a + b) / 2.0;
+    let (left_m, right_m) = ((a + m) / 2.0, (m + b) / 2.0);
+    let (f_left, f_right) = (sample(vm, function, left_m, evaluations)?, sample(vm, function, right_m, evaluations)?);
+    let left = (m - a) / 6.0 * (fa + 4.0 * f_left + fm);
+    let right = (b - m) / 6.0 * (fm + 4.0 * f_right + fb);
+    let difference = left + right - whole;
+    // The interval cannot be split any further once its points are as close as numbers can be
+    let shrinks = a < left_m && left_m < m && m < right_m && right_m < b;
+    if depth == 0 || !shrinks || difference.abs() <= 15.0 * tolerance {
+        return Ok(left + right + difference / 15.0);
+    }
+    Ok(simpson(vm, function, (a, fa), (m, fm), f_left, left, tolerance / 2.0, depth - 1, evaluations)?
+        + simpson(vm, function, (m, fm), (b, fb), f_right, right, tolerance / 2.0, depth - 1, evaluations)?)
+}
+
+/// A root of the function near the guess. Newton's method is tried first, and when it does not find a root,
+/// the interval around the guess is widened until the function changes sign, and the root within it is found by bisection
+fn solve(vm: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    expect_function("solve", &values[0])?;
+    let guess = expect_number("solve", &values[1])?;
+    let function = &values[0];
+
+    let mut x = guess;
+    for _ in 0..MAX_STEPS {
+        let y = evaluate(vm, "solve", function, x)?;
+        if y == 0.0 {
+            return Ok(Value::Number(x));
+        }
+        let slope = derivative(vm, "solve", function, x)?;
+        if slope == 0.0 || !slope.is_finite() {
+            break;
+        }
+        let next = x - y / slope;
+        if !next.is_finite() {
+            break;
+        }
+        if (next - x).abs() <= TOLERANCE * next.abs().max(1.0) {
+            if evaluate(vm, "solve", function, next)?.abs() <= TOLERANCE.sqrt() {
+                return Ok(Value::Number(next));
+            }
+            break;
+        }
+        x = next;
+    }
+
+    let (mut low, mut high) = (guess - 1.0, guess + 1.0);
+    let (mut f_low, mut f_high) = (evaluate(vm, "solve", function, low)?, evaluate(vm, "solve", function, high)?);
+    let mut widened = 0;
+    while f_low.signum() == f_high.signum() {
+        if widened == MAX_STEPS / 2 || !f_low.is_finite() || !f_high.is_finite() {
+            return Err(format!("The function `solve` could not find a root of the function near {guess}!"));
+        }
+        let width = high - low;
+        (low, high) = (low - width, high + width);
+        (f_low, f_high) = (evaluate(vm, "solve", function, low)?, evaluate(vm, "solve", function, high)?);
+        widened += 1;
+    }
+    for _ in 0..MAX_STEPS {
+        let middle = (low + high) / 2.0;
+        let f_middle = evaluate(vm, "solve", function, middle)?;
+        if f_middle == 0.0 || (high - low) / 2.0 <= TOLERANCE * middle.abs().max(1.0) {
+            return Ok(Value::Number(middle));
+        }
+        if f_middle.signum() == f_low.signum() {
+            (low, f_low) = (middle, f_middle);
+        } else {
+            high = middle;
+        }
+    }
+    Ok(Value::Number((low + high) / 2.0))
+}
+
+/// The point between `a` and `b` where the function is the smallest, by golden section search.
+/// The function is expected to go down and then up within the interval, otherwise a local minimum may be found
+fn minimize(vm: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    expect_function("minimize", &values[0])?;
+    let a = expect_number("minimize", &values[1])?;
+    let b = expect_number("minimize", &values[2])?;
+    if a > b {
+        return Err(format!("The function `minimize` expected the start of the interval to be at most its end, but found {a} and {b}!"));
+    }
+    let function = &values[0];
+
+    // 1 / the golden ratio, so that one of the two inner points can be used again on each step
+    let ratio = (5f64.sqrt() - 1.0) / 2.0;
+    let (mut low, mut high) = (a, b);
+    let (mut left, mut right) = (high - ratio * (high - low), low + ratio * (high - low));
+    let (mut f_left, mut f_right) = (evaluate(vm, "minimize", function, left)?, evaluate(vm, "minimize", function, right)?);
+    for _ in 0..MAX_STEPS {
+        if high - low <= TOLERANCE * (left.abs() + right.abs()).max(1.0) {
+            break;
+        }
+        if f_left < f_right {
+            (high, right, f_right) = (right, left, f_left);
+            left = high - ratio * (high - low);
+            f_left = evaluate(vm, "minimize", function, left)?;
+        } else {
+            (low, left, f_left) = (left, right, f_right);
+            right = low + ratio * (high - low);
+            f_right = evaluate(vm, "minimize", function, right)?;
+        }
+    }
+    let middle = (low + high) / 2.0;
+
+    // The ends are checked as well, for a function that keeps going down or up within the interval
+    let mut best = (middle, evaluate(vm, "minimize", function, middle)?);
+    for x in [a, b] {
+        let y = evaluate(vm, "minimize", function, x)?;
+        if y < best.1 {
+            best = (x, y);
+        }
+    }
+    Ok(Value::Number(best.0))
+}
@@ -35,6 +35,12 @@ pub enum Error {
         span: Span,
     },
 
+    /// The source ended in the middle of an expression, such as an unclosed bracket or a trailing operator
+    PUnexpectedEOF {
+        message: String,
+        span: Span,
+    },
+
     /// An internal, or unexpected error
     PInternalError {
         message: String,
@@ -63,7 +69,7 @@ impl Display for Error {
 
             Self::TEOF => format!("End of file reached. No new tokens can be generated"),
 
-            Self::PError { message, span } | Self::PInvalidStatement { message, span } => {
+            Self::PError { message, span } | Self::PInvalidStatement { message, span } | Self::PUnexpectedEOF { message, span } => {
                 format!("[PARSE ERROR] {span}: {message}")
             },
 
@@ -71,4 +77,22 @@ impl Display for Error {
         };
         write!(f, "{string}")
     }
+}
+
+impl Error {
+    /// The part of the source the error occured at, if there is one
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Self::TNumberExpected { location } | Self::TInvalidCharacter { location } => Some(Span::new(*location, *location + 1)),
+            Self::TInvalidOctal { span } | Self::TInvalidBinary { span } | Self::TIncompleteString { span } => Some(*span),
+            Self::PInvalidStatement { span, .. } | Self::PError { span, .. } | Self::PUnexpectedEOF { span, .. } | Self::PInternalError { span, .. } => Some(*span),
+            Self::TEOF | Self::NoResult => None,
+        }
+    }
+
+    /// Whether the error was caused by the source ending before the expression did,
+    /// meaning that more input could still complete it
+    pub fn is_incomplete(&self) -> bool {
+        matches!(self, Self::TIncompleteString { .. } | Self::PUnexpectedEOF { .. })
+    }
 }
\ No newline at end of file
@@ -0,0 +1,243 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::instruction::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Align {
+    /// <
+    Left,
+    /// ^
+    Center,
+    /// >
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Notation {
+    /// b
+    Binary,
+    /// o
+    Octal,
+    /// x
+    Hex,
+    /// X
+    UpperHex,
+    /// e
+    Exponent,
+}
+
+/// The largest width or precision, so that a specifier cannot make a string too large to be stored
+const MAX_NUMBER: usize = 1000;
+
+/// How a value within a format string is written, using a part of the syntax of Rust's format strings:
+/// `[[fill]align][+][0][width][.precision][b|o|x|X|e]`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FormatSpec {
+    fill: Option<char>,
+    align: Option<Align>,
+    sign: bool,
+    zero: bool,
+    width: usize,
+    precision: Option<usize>,
+    notation: Option<Notation>,
+}
+
+impl FormatSpec {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let characters = spec.chars().collect::<Vec<_>>();
+        let mut result = Self::default();
+        let mut position = 0;
+
+        if let Some(align) = characters.get(1).and_then(|c| Self::align(*c)) {
+            result.fill = Some(characters[0]);
+            result.align = Some(align);
+            position = 2;
+        } else if let Some(align) = characters.first().and_then(|c| Self::align(*c)) {
+            result.align = Some(align);
+            position = 1;
+        }
+
+        if characters.get(position) == Some(&'+') {
+            result.sign = true;
+            position += 1;
+        }
+        if characters.get(position) == Some(&'0') {
+            result.zero = true;
+            position += 1;
+        }
+
+        let (width, length) = Self::number(&characters[position..], spec)?;
+        result.width = width.unwrap_or(0);
+        position += length;
+
+        if characters.get(position) == Some(&'.') {
+            position += 1;
+            let (precision, length) = Self::number(&characters[position..], spec)?;
+            if precision.is_none() {
+                return Err(format!("Expected a precision after `.` in the format specifier `{spec}`!"));
+            }
+            result.precision = precision;
+            position += length;
+        }
+
+        result.notation = match characters.get(position) {
+            Some('b') => Some(Notation::Binary),
+            Some('o') => Some(Notation::Octal),
+            Some('x') => Some(Notation::Hex),
+            Some('X') => Some(Notation::UpperHex),
+            Some('e') => Some(Notation::Exponent),
+            _ => None,
+        };
+        if result.notation.is_some() {
+            position += 1;
+        }
+
+        if position != characters.len() {
+            return Err(format!("Unknown format specifier `{spec}`!"));
+        }
+        if result.precision.is_some() && !matches!(result.notation, None | Some(Notation::Exponent)) {
+            return Err(format!("The format specifier `{spec}` cannot have a precision for a binary, octal or hexadecimal number!"));
+        }
+        Ok(result)
+    }
+
+    /// Writes a value following the specifier
+    pub fn apply(&self, value: &Value) -> Result<String, String> {
+        let text = match value {
+            Value::Number(number) => {
+                let magnitude = number.abs();
+                let digits = match (self.notation, self.precision) {
+                    (None, None) => format!("{magnitude}"),
+                    (None, Some(precision)) => format!("{magnitude:.precision$}"),
+                    (Some(Notation::Exponent), None) => format!("{magnitude:e}"),
+                    (Some(Notation::Exponent), Some(precision)) => format!("{magnitude:.precision$e}"),
+                    (Some(notation), _) => {
+                        if magnitude.fract() != 0.0 || !magnitude.is_finite() {
+                            return Err(format!("Cannot write {number} as a {} number, only whole numbers can be!", notation.name()));
+                        }
+                        // A cast would give the largest u128 for anything larger, rather than its digits
+                        if magnitude >= 2f64.powi(128) {
+                            return Err(format!("Cannot write {number} as a {} number, since it is not smaller than 2**128!", notation.name()));
+                        }
+                        let whole = magnitude as u128;
+                        match notation {
+                            Notation::Binary => format!("{whole:b}"),
+                            Notation::Octal => format!("{whole:o}"),
+                            Notation::UpperHex => format!("{whole:X}"),
+                            _ => format!("{whole:x}"),
+                        }
+                    }
+                };
+                let sign = if *number < 0.0 { "-" } else if self.sign { "+" } else { "" };
+
+                // Zeroes go between the sign and the digits
+                if self.zero {
+                    let zeroes = self.width.saturating_sub(sign.len() + digits.chars().count());
+                    return Ok(format!("{sign}{}{digits}", "0".repeat(zeroes)));
+                }
+                format!("{sign}{digits}")
+            }
+
+            value => {
+                if self.sign || self.zero || self.notation.is_some() {
+                    return Err(format!("The format specifier `{self}` can only be used with a value of type {{Number}}, not {}!", value.type_of()));
+                }
+                let text = match value {
+                    Value::String(string) => string.clone(),
+                    value => format!("{value}"),
+                };
+                // The precision of a string is the number of characters to keep
+                match self.precision {
+                    Some(precision) => text.chars().take(precision).collect(),
+                    None => text,
+                }
+            }
+        };
+
+        let padding = self.width.saturating_sub(text.chars().count());
+        if padding == 0 {
+            return Ok(text);
+        }
+        let fill = self.fill.unwrap_or(' ').to_string();
+        // Numbers are aligned to the right by default, like Rust does
+        let default = if matches!(value, Value::Number(..)) { Align::Right } else { Align::Left };
+        Ok(match self.align.unwrap_or(default) {
+            Align::Left => format!("{text}{}", fill.repeat(padding)),
+            Align::Right => format!("{}{text}", fill.repeat(padding)),
+            Align::Center => format!("{}{text}{}", fill.repeat(padding / 2), fill.repeat(padding - padding / 2)),
+        })
+    }
+
+    fn align(character: char) -> Option<Align> {
+        match character {
+            '<' => Some(Align::Left),
+            '^' => Some(Align::Center),
+            '>' => Some(Align::Right),
+            _ => None,
+        }
+    }
+
+    /// Reads the digits at the start of the characters, returning the number and how many characters it took up
+    fn number(characters: &[char], spec: &str) -> Result<(Option<usize>, usize), String> {
+        let digits = characters.iter().take_while(|c| c.is_ascii_digit()).collect::<String>();
+        if digits.is_empty() {
+            return Ok((None, 0));
+        }
+        match digits.parse() {
+            Ok(number) if number <= MAX_NUMBER => Ok((Some(number), digits.len())),
+            _ => Err(format!("The number {digits} in the format specifier `{spec}` is too large, it can be at most {MAX_NUMBER}!")),
+        }
+    }
+}
+
+impl Notation {
+    fn name(&self) -> &str {
+        match self {
+            Self::Binary => "binary",
+            Self::Octal => "octal",
+            Self::Hex | Self::UpperHex => "hexadecimal",
+            Self::Exponent => "exponent",
+        }
+    }
+}
+
+impl Display for FormatSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(align) = self.align {
+            if let Some(fill) = self.fill {
+                write!(f, "{fill}")?;
+            }
+            let align = match align {
+                Align::Left => '<',
+                Align::Center => '^',
+                Align::Right => '>',
+            };
+            write!(f, "{align}")?;
+        }
+        if self.sign {
+            write!(f, "+")?;
+        }
+        if self.zero {
+            write!(f, "0")?;
+        }
+        if self.width > 0 {
+            write!(f, "{}", self.width)?;
+        }
+        if let Some(precision) = self.precision {
+            write!(f, ".{precision}")?;
+        }
+        if let Some(notation) = self.notation {
+            let notation = match notation {
+                Notation::Binary => 'b',
+                Notation::Octal => 'o',
+                Notation::Hex => 'x',
+                Notation::UpperHex => 'X',
+                Notation::Exponent => 'e',
+            };
+            write!(f, "{notation}")?;
+        }
+        Ok(())
+    }
+}
\ No newline at end of file
@@ -0,0 +1,287 @@
+use std::{borrow::Borrow, rc::Rc};
+
+use crate::{ast::{self, Operator, SliceBounds, Tree, AST}, errors::Error, lexer::Lexer, parser::Parser, utils::Span};
+
+/// Re-emits source code with consistent spacing and only the brackets that are needed,
+/// while keeping the comments and the `:` / `;` terminators of the original code
+pub struct Formatter<'a> {
+    source: &'a str,
+    comments: Vec<Span>,
+}
+
+impl<'a> Formatter<'a> {
+    pub fn new(source: &'a str) -> Self {
+        Self {
+            source,
+            comments: Self::find_comments(source),
+        }
+    }
+
+    pub fn format(&self) -> Result<String, Error> {
+        let lexer = Lexer::new(self.source).map_err(|_| Error::NoResult)?;
+        let mut parser = Parser::new(lexer);
+        parser.increment()?;
+
+        // Every statement along with the position of its first token
+        let mut statements = vec![];
+        while !parser.eof {
+            let start = parser.token.span.start;
+            statements.push((start, parser.next_expression()?));
+        }
+
+        let mut output = String::new();
+        let mut position = 0;
+        for (i, (start, tree)) in statements.iter().enumerate() {
+            let next = statements.get(i + 1).map(|(start, _)| *start).unwrap_or(self.source.len());
+            position = self.write_comments(&mut output, position, *start);
+            if !output.is_empty() && self.blank_line(position, *start) {
+                output.push('\n');
+            }
+
+            let end = self.terminator_end(*start, next);
+            if self.comments.iter().any(|comment| comment.start >= *start && comment.start < end) {
+                // Comments within a statement cannot be placed back reliably, so it is left untouched
+                output.push_str(&self.source[*start..end]);
+            } else {
+                let (value, terminator) = match tree.ast.borrow() {
+                    AST::Output { value } => (value, ':'),
+                    _ => (tree, ';'),
+                };
+                output.push_str(&self.expression(value));
+                output.push(terminator);
+            }
+            position = end;
+
+            // A comment on the same line as the statement stays on that line
+            if let Some(comment) = self.comments.iter().find(|comment| comment.start >= end && comment.start < next) {
+                if !self.source[end..comment.start].contains('\n') {
+                    output.push(' ');
+                    output.push_str(self.source[comment.as_range()].trim_end());
+                    position = comment.end;
+                }
+            }
+            output.push('\n');
+        }
+        self.write_comments(&mut output, position, self.source.len());
+
+        Ok(output)
+    }
+
+    /// Formats an expression, adding brackets around the parts that would otherwise be parsed differently
+    pub fn expression(&self, tree: &Rc<Tree<'a>>) -> String {
+        match tree.ast.borrow() {
+            // Keep the original representation of numbers and strings, such as hexadecimal numbers and escape sequences
+            AST::Number { .. } | AST::String { .. } => self.source[tree.span.as_range()].to_string(),
+            AST::Identifier { name } => name.to_string(),
+            AST::Name { value } => value.to_string(),
+            AST::Null => "Null".to_string(),
+
+            AST::BinaryOp { lhs, rhs, op } => {
+                let precedence = Self::operator_precedence(*op);
+                // Operators are left associative, so the right side needs brackets for the same precedence
+                format!("{} {op} {}", self.operand(lhs, precedence), self.operand(rhs, precedence + 1))
+            }
+
+            AST::UnaryOp { rhs, op } => format!("{op}{}", self.operand(rhs, Self::precedence(&tree.ast))),
+
+            AST::Declare { identifier, .. } => format!("let {identifier}"),
+            AST::DeclareAssign { identifier, value, .. } => format!("let {identifier} = {}", self.expression(value)),
+            AST::DeclarePattern { pattern, value } => format!("let {pattern} = {}", self.expression(value)),
+            AST::AssignPattern { pattern, value } => format!("{pattern} = {}", self.expression(value)),
+            AST::Assign { identifier, value, .. } => format!("{identifier} = {}", self.expression(value)),
+            AST::AssignOp { identifier, value, operator, .. } => format!("{identifier} {operator} {}", self.expression(value)),
+            AST::AssignIndex { identifier, value, operator, indicies, slice } => {
+                let mut indicies = indicies.iter().map(|index| self.index(index)).collect::<String>();
+                if let Some(slice) = slice {
+                    indicies.push_str(&format!("[{}]", self.bounds(slice)));
+                }
+                format!("{identifier}{indicies} {operator} {}", self.expression(value))
+            }
+
+            AST::FunctionDecl { name, arguments, defaults, rest, body } => {
+                format!("let {name} {} = {}", self.parameters(arguments, defaults, *rest), self.expression(body))
+            }
+
+            AST::Lambda { arguments, defaults, rest, body } => {
+                format!("\\{} -> {}", self.parameters(arguments, defaults, *rest), self.expression(body))
+            }
+
+            AST::FunctionCall { name, expressions } => {
+                let name = match name.ast.borrow() {
+                    AST::Name { value } => value.to_string(),
+                    // `(a)(...)` is kept as is since `a(...)` is parsed as a call by name
+                    AST::Identifier { name } => format!("({name})"),
+                    // A function can be called straight after it is indexed, `m.f(...)`
+                    _ => self.operand(name, 10),
+                };
+                format!("{name}({})", self.list(expressions))
+            }
+            AST::NamedArgument { name, value } => format!("{name}: {}", self.expression(value)),
+            AST::Print { expressions } => format!("print({})", self.list(expressions)),
+            AST::Derivative { expression, variable, .. } => format!("diff({}, {variable})", self.expression(expression)),
+            AST::Solve { equations, variables, system, .. } => {
+                let equations = equations.iter().map(|(lhs, rhs)| format!("{} = {}", self.expression(lhs), self.expression(rhs))).collect::<Vec<_>>().join(", ");
+                if *system {
+                    format!("solve [{equations}] for [{}]", variables.join(", "))
+                } else {
+                    format!("solve {equations} for {}", variables.join(", "))
+                }
+            }
+
+            AST::Delete { name } => format!("delete {name}"),
+            AST::Import { path, alias: Some(alias) } => format!("import {} as {alias}", self.expression(path)),
+            AST::Import { path, alias: None } => format!("import {}", self.expression(path)),
+            AST::TypeOf { expression } => format!("typeof {}", self.expression(expression)),
+            AST::Array { expressions } => format!("[{}]", self.list(expressions)),
+            AST::Match { value, arms } => {
+                let arms = arms.iter().map(|arm| match &arm.guard {
+                    Some(guard) => format!("{} if {} => {}", arm.pattern, self.expression(guard), self.expression(&arm.body)),
+                    None => format!("{} => {}", arm.pattern, self.expression(&arm.body)),
+                });
+                format!("match {} {{ {} }}", self.expression(value), arms.collect::<Vec<_>>().join(", "))
+            }
+            AST::Map { entries } => {
+                let entries = entries.iter().map(|(key, value)| format!("{}: {}", self.expression(key), self.expression(value)));
+                format!("{{{}}}", entries.collect::<Vec<_>>().join(", "))
+            }
+            AST::Index { to_index, expression } => format!("{}{}", self.operand(to_index, 10), self.index(expression)),
+            AST::Slice { to_slice, bounds } => format!("{}[{}]", self.operand(to_slice, 10), self.bounds(bounds)),
+            AST::Output { value } => format!("{}:", self.expression(value)),
+            AST::FormatString { parts } => {
+                let parts = parts.iter().map(|part| match part.ast {
+                    // The text is kept as it is written, along with its escape sequences
+                    AST::String { .. } => self.source[part.span.as_range()].to_string(),
+                    _ => self.expression(part),
+                });
+                format!("f\"{}\"", parts.collect::<String>())
+            }
+            AST::Format { value, spec } => {
+                let spec = spec.to_string();
+                if spec.is_empty() {
+                    format!("{{{}}}", self.expression(value))
+                } else {
+                    format!("{{{}:{spec}}}", self.expression(value))
+                }
+            }
+        }
+    }
+
+    /// Formats an expression that is part of another expression with the given precedence
+    fn operand(&self, tree: &Rc<Tree<'a>>, precedence: u8) -> String {
+        if Self::precedence(&tree.ast) < precedence {
+            format!("({})", self.expression(tree))
+        } else {
+            self.expression(tree)
+        }
+    }
+
+    /// Formats an index, keeping fields written as `.key` rather than `["key"]`
+    fn index(&self, expression: &Rc<Tree<'a>>) -> String {
+        let text = &self.source[expression.span.as_range()];
+        match expression.ast {
+            AST::String { .. } if !text.starts_with('"') => format!(".{text}"),
+            _ => format!("[{}]", self.expression(expression)),
+        }
+    }
+
+    fn bounds(&self, bounds: &SliceBounds<'a>) -> String {
+        let bound = |bound: &Option<Rc<Tree<'a>>>| bound.as_ref().map(|bound| self.expression(bound)).unwrap_or_default();
+        match &bounds.step {
+            Some(step) => format!("{}:{}:{}", bound(&bounds.start), bound(&bounds.end), self.expression(step)),
+            None => format!("{}:{}", bound(&bounds.start), bound(&bounds.end)),
+        }
+    }
+
+    /// The arguments of a function, where `_` stands for none
+    fn parameters(&self, arguments: &[&str], defaults: &[Rc<Tree<'a>>], rest: Option<&str>) -> String {
+        if arguments.is_empty() && rest.is_none() {
+            return "_".to_string();
+        }
+        ast::parameters(arguments, &defaults.iter().map(|default| self.expression(default)).collect::<Vec<_>>(), rest)
+    }
+
+    fn list(&self, expressions: &[Rc<Tree<'a>>]) -> String {
+        expressions.iter().map(|expression| self.expression(expression)).collect::<Vec<_>>().join(", ")
+    }
+
+    /// How tightly an expression binds, following the order of the functions in the `Parser`
+    fn precedence(ast: &AST) -> u8 {
+        match ast {
+            AST::BinaryOp { op, .. } => Self::operator_precedence(*op),
+            AST::UnaryOp { .. } => 9,
+            AST::Index { .. } | AST::Slice { .. } => 10,
+            // These take everything to their right as a part of them
+            AST::DeclareAssign { .. } | AST::Assign { .. } | AST::AssignOp { .. } | AST::AssignIndex { .. }
+            | AST::DeclarePattern { .. } | AST::AssignPattern { .. }
+            | AST::FunctionDecl { .. } | AST::Lambda { .. } | AST::TypeOf { .. } | AST::Output { .. } => 0,
+            _ => 11,
+        }
+    }
+
+    fn operator_precedence(operator: Operator) -> u8 {
+        match operator {
+            Operator::IsEqual | Operator::NotEqual | Operator::Less | Operator::LessEqual | Operator::Greater | Operator::GreaterEqual => 1,
+            Operator::BitOr => 2,
+            Operator::BitXor => 3,
+            Operator::BitAnd => 4,
+            Operator::BitLeftShift | Operator::BitRightShift => 5,
+            Operator::Plus | Operator::Minus => 6,
+            Operator::Multiply | Operator::Divide | Operator::Modulo => 7,
+            Operator::Exponent => 8,
+            _ => 0,
+        }
+    }
+
+    /// Writes out the comments between two positions, keeping single blank lines between them.
+    /// Returns the position after the last comment written
+    fn write_comments(&self, output: &mut String, from: usize, to: usize) -> usize {
+        let mut position = from;
+        for comment in self.comments.iter().filter(|comment| comment.start >= from && comment.start < to) {
+            if !output.is_empty() && self.blank_line(position, comment.start) {
+                output.push('\n');
+            }
+            output.push_str(self.source[comment.as_range()].trim_end());
+            output.push('\n');
+            position = comment.end;
+        }
+        position
+    }
+
+    fn blank_line(&self, from: usize, to: usize) -> bool {
+        self.source[from..to].matches('\n').count() >= 2
+    }
+
+    /// The position right after the terminator of a statement, which is the last character before the next statement
+    /// that is not whitespace or a part of a comment
+    fn terminator_end(&self, start: usize, next: usize) -> usize {
+        let mut end = start;
+        for (offset, character) in self.source[start..next].char_indices() {
+            let position = start + offset;
+            let in_comment = self.comments.iter().any(|comment| comment.as_range().contains(&position));
+            if !in_comment && !character.is_whitespace() {
+                end = position + character.len_utf8();
+            }
+        }
+        end
+    }
+
+    /// Finds the spans of all the `//` comments that are not within strings
+    pub(crate) fn find_comments(source: &str) -> Vec<Span> {
+        let mut comments = vec![];
+        let mut characters = source.char_indices().peekable();
+        let mut in_string = false;
+        while let Some((position, character)) = characters.next() {
+            match character {
+                '\\' if in_string => { characters.next(); },
+                '"' => in_string = !in_string,
+                '/' if !in_string && matches!(characters.peek(), Some((_, '/'))) => {
+                    let end = source[position..].find('\n').map(|end| position + end).unwrap_or(source.len());
+                    comments.push(Span::new(position, end));
+                    while characters.next_if(|(next, _)| *next < end).is_some() {}
+                },
+                _ => (),
+            }
+        }
+        comments
+    }
+}
\ No newline at end of file
@@ -1,3 +1,6 @@
+use std::collections::BTreeMap;
+
+use crate::{calculus, instruction::Value, matrix, parser::Parser, plot, polynomial, random, stats, vm::VM};
 
 macro_rules! decl_fn {
     ($($name: ident,)*) => {
@@ -7,10 +10,30 @@ macro_rules! decl_fn {
     };
 }
 
-decl_fn!(sin, cos, tan, abs, acos, cosh, acosh, asin, atan, asinh, atanh, cbrt, ceil, floor, log10, log2, ln, round, sqrt, to_radians, to_degrees, );
+decl_fn!(sin, cos, tan, abs, acos, cosh, acosh, asin, atan, asinh, atanh, cbrt, ceil, floor, log10, log2, ln, round, sqrt, to_radians, to_degrees, exp, trunc, );
+
+/// -1, 0 or 1, unlike `f64::signum`, which gives 1 for 0
+fn sign(values: &[f64]) -> f64 {
+    if values[0] == 0.0 { 0.0 } else { values[0].signum() }
+}
 
+/// 1 if the number is a prime and 0 otherwise
+fn is_prime(values: &[f64]) -> f64 {
+    let number = values[0];
+    if number < 2.0 || number.fract() != 0.0 {
+        return 0.0;
+    }
+    let mut divisor = 2.0;
+    while divisor * divisor <= number {
+        if number % divisor == 0.0 {
+            return 0.0;
+        }
+        divisor += 1.0;
+    }
+    1.0
+}
 
-pub const FUNCTIONS: [(&str, (usize, fn(&[f64]) -> f64)); 22] = [
+pub const FUNCTIONS: [(&str, (usize, fn(&[f64]) -> f64)); 26] = [
     ("cos", (1, cos)),
     ("sin", (1, sin)),
     ("tan", (1, tan)),
@@ -33,6 +56,10 @@ pub const FUNCTIONS: [(&str, (usize, fn(&[f64]) -> f64)); 22] = [
     ("sqrt", (1, sqrt)),
     ("to_radians", (1, to_radians)),
     ("to_degrees", (1, to_degrees)),
+    ("exp", (1, exp)),
+    ("trunc", (1, trunc)),
+    ("sign", (1, sign)),
+    ("is_prime", (1, is_prime)),
 ];
 
 pub fn get_function(key: &str) -> Result<(usize, fn(&[f64]) -> f64), ()> {
@@ -42,4 +69,452 @@ pub fn get_function(key: &str) -> Result<(usize, fn(&[f64]) -> f64), ()> {
         }
     }
     Err(())
+}
+
+/// A builtin function that works with values of any type. The VM is passed along to call the functions given as arguments.
+/// Errors are returned as messages for the VM to report
+pub type ValueFunction = fn(&mut VM, Vec<Value>) -> Result<Value, String>;
+
+pub const VALUE_FUNCTIONS: [(&str, (usize, ValueFunction)); 32] = [
+    ("len", (1, len)),
+    ("upper", (1, upper)),
+    ("lower", (1, lower)),
+    ("trim", (1, trim)),
+    ("split", (2, split)),
+    ("join", (2, join)),
+    ("replace", (3, replace)),
+    ("find", (2, find)),
+    ("slice", (3, slice)),
+    ("num", (1, num)),
+    ("str", (1, str)),
+    ("push", (2, push)),
+    ("pop", (1, pop)),
+    ("insert", (3, insert)),
+    ("remove", (2, remove)),
+    ("concat", (2, concat)),
+    ("reverse", (1, reverse)),
+    ("sort", (1, sort)),
+    ("range", (3, range)),
+    ("map", (2, map)),
+    ("filter", (2, filter)),
+    ("reduce", (3, reduce)),
+    ("zip", (2, zip)),
+    ("keys", (1, keys)),
+    ("values", (1, values)),
+    ("has", (2, has)),
+    ("log", (2, log)),
+    ("floor_div", (2, floor_div)),
+    ("gcd", (2, gcd)),
+    ("lcm", (2, lcm)),
+    ("factorial", (1, factorial)),
+    ("choose", (2, choose)),
+];
+
+pub fn get_value_function(key: &str) -> Result<(usize, ValueFunction), ()> {
+    for element in value_functions() {
+        if element.0 == key {
+            return Ok(element.1);
+        }
+    }
+    Err(())
+}
+
+/// The builtin functions that work with values of any type, including the ones from other modules
+pub fn value_functions() -> impl Iterator<Item = (&'static str, (usize, ValueFunction))> {
+    VALUE_FUNCTIONS.into_iter().chain(matrix::FUNCTIONS).chain(stats::FUNCTIONS).chain(random::FUNCTIONS).chain(calculus::FUNCTIONS).chain(polynomial::FUNCTIONS).chain(plot::FUNCTIONS)
+}
+
+pub(crate) fn expect_string(function: &str, value: &Value) -> Result<String, String> {
+    match value {
+        Value::String(string) => Ok(string.clone()),
+        value => Err(format!("The function `{function}` expected a value of type {{String}} but found a value of type {}!", value.type_of())),
+    }
+}
+
+pub(crate) fn expect_number(function: &str, value: &Value) -> Result<f64, String> {
+    match value {
+        Value::Number(number) => Ok(*number),
+        value => Err(format!("The function `{function}` expected a value of type {{Number}} but found a value of type {}!", value.type_of())),
+    }
+}
+
+/// Checks that a number can be used as a position within a string
+pub(crate) fn expect_position(function: &str, value: &Value) -> Result<usize, String> {
+    let number = expect_number(function, value)?;
+    if number < 0.0 || number.fract() != 0.0 {
+        return Err(format!("The function `{function}` expected a whole, positive number but found {number}!"));
+    }
+    Ok(number as usize)
+}
+
+/// Checks that a number is whole, for the functions of number theory
+pub(crate) fn expect_whole(function: &str, value: &Value) -> Result<f64, String> {
+    let number = expect_number(function, value)?;
+    if number.fract() != 0.0 {
+        return Err(format!("The function `{function}` expected a whole number but found {number}!"));
+    }
+    Ok(number)
+}
+
+pub(crate) fn expect_array<'a>(function: &str, value: &'a Value) -> Result<&'a Vec<Value>, String> {
+    match value {
+        Value::Array(array) => Ok(array),
+        value => Err(format!("The function `{function}` expected a value of type {{Array}} but found a value of type {}!", value.type_of())),
+    }
+}
+
+pub(crate) fn expect_map<'a>(function: &str, value: &'a Value) -> Result<&'a BTreeMap<String, Value>, String> {
+    match value {
+        Value::Map(map) => Ok(map),
+        value => Err(format!("The function `{function}` expected a value of type {{Map}} but found a value of type {}!", value.type_of())),
+    }
+}
+
+pub(crate) fn expect_function(function: &str, value: &Value) -> Result<(), String> {
+    match value {
+        Value::Function(..) => Ok(()),
+        value => Err(format!("The function `{function}` expected a value of type {{Function}} but found a value of type {}!", value.type_of())),
+    }
+}
+
+/// Checks that a function returned a number, which is taken as false when it is zero and true otherwise
+fn expect_truth(function: &str, value: &Value) -> Result<bool, String> {
+    match value {
+        Value::Number(number) => Ok(*number != 0.0),
+        value => Err(format!("The function given to `{function}` should return a value of type {{Number}} but returned a value of type {}!", value.type_of())),
+    }
+}
+
+fn len(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    match &values[0] {
+        Value::String(string) => Ok(Value::Number(string.chars().count() as f64)),
+        Value::Array(array) => Ok(Value::Number(array.len() as f64)),
+        Value::Map(map) => Ok(Value::Number(map.len() as f64)),
+        value => Err(format!("The function `len` expected a value of type {{String}}, {{Array}} or {{Map}} but found a value of type {}!", value.type_of())),
+    }
+}
+
+fn upper(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    Ok(Value::String(expect_string("upper", &values[0])?.to_uppercase()))
+}
+
+fn lower(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    Ok(Value::String(expect_string("lower", &values[0])?.to_lowercase()))
+}
+
+fn trim(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    Ok(Value::String(expect_string("trim", &values[0])?.trim().to_string()))
+}
+
+fn split(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    let string = expect_string("split", &values[0])?;
+    let separator = expect_string("split", &values[1])?;
+    let parts = if separator.is_empty() {
+        // Split into characters rather than having empty strings at both ends
+        string.chars().map(|character| Value::String(character.to_string())).collect()
+    } else {
+        string.split(separator.as_str()).map(|part| Value::String(part.to_string())).collect()
+    };
+    Ok(Value::Array(parts))
+}
+
+fn join(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    let separator = expect_string("join", &values[1])?;
+    match &values[0] {
+        Value::Array(array) => Ok(Value::String(array.iter().map(|value| format!("{value}")).collect::<Vec<_>>().join(&separator))),
+        value => Err(format!("The function `join` expected a value of type {{Array}} but found a value of type {}!", value.type_of())),
+    }
+}
+
+fn replace(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    let string = expect_string("replace", &values[0])?;
+    let from = expect_string("replace", &values[1])?;
+    let to = expect_string("replace", &values[2])?;
+    if from.is_empty() {
+        return Err("The function `replace` cannot replace an empty string!".to_string());
+    }
+    Ok(Value::String(string.replace(&from, &to)))
+}
+
+/// The position of the first occurence of a string in characters, or -1 if it is not found
+fn find(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    let string = expect_string("find", &values[0])?;
+    let pattern = expect_string("find", &values[1])?;
+    let position = match string.find(&pattern) {
+        Some(index) => string[..index].chars().count() as f64,
+        None => -1.0,
+    };
+    Ok(Value::Number(position))
+}
+
+/// The part of a string or an array from the start position up to, but not including the end position
+fn slice(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    let start = expect_position("slice", &values[1])?;
+    let end = expect_position("slice", &values[2])?;
+    let length = match &values[0] {
+        Value::String(string) => string.chars().count(),
+        value => expect_array("slice", value)?.len(),
+    };
+    if start > end || end > length {
+        return Err(format!("Cannot slice a value of length {length} from {start} to {end}!"));
+    }
+    match &values[0] {
+        Value::String(string) => Ok(Value::String(string.chars().skip(start).take(end - start).collect())),
+        value => Ok(Value::Array(expect_array("slice", value)?[start..end].to_vec())),
+    }
+}
+
+fn num(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    let string = expect_string("num", &values[0])?;
+    match Parser::parse_text_number(&string) {
+        Some(number) => Ok(Value::Number(number)),
+        None => Err(format!("Cannot convert the string \"{string}\" to a number!")),
+    }
+}
+
+fn str(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    Ok(Value::String(format!("{}", values[0])))
+}
+
+// Arrays are values like any other, so these functions return a new array rather than changing the one given to them
+
+fn push(_: &mut VM, mut values: Vec<Value>) -> Result<Value, String> {
+    let value = values.pop().unwrap();
+    let mut array = expect_array("push", &values[0])?.clone();
+    array.push(value);
+    Ok(Value::Array(array))
+}
+
+/// The array without its last value
+fn pop(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    let mut array = expect_array("pop", &values[0])?.clone();
+    if array.pop().is_none() {
+        return Err("Cannot pop a value from an empty array!".to_string());
+    }
+    Ok(Value::Array(array))
+}
+
+fn insert(_: &mut VM, mut values: Vec<Value>) -> Result<Value, String> {
+    let value = values.pop().unwrap();
+    let mut array = expect_array("insert", &values[0])?.clone();
+    let index = expect_position("insert", &values[1])?;
+    if index > array.len() {
+        return Err(format!("Cannot insert a value at {index} in an array of length {}!", array.len()));
+    }
+    array.insert(index, value);
+    Ok(Value::Array(array))
+}
+
+fn remove(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    let mut array = expect_array("remove", &values[0])?.clone();
+    let index = expect_position("remove", &values[1])?;
+    if index >= array.len() {
+        return Err(format!("Cannot remove the value at {index} from an array of length {}!", array.len()));
+    }
+    array.remove(index);
+    Ok(Value::Array(array))
+}
+
+fn concat(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    let mut array = expect_array("concat", &values[0])?.clone();
+    array.extend(expect_array("concat", &values[1])?.iter().cloned());
+    Ok(Value::Array(array))
+}
+
+fn reverse(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    match &values[0] {
+        Value::String(string) => Ok(Value::String(string.chars().rev().collect())),
+        value => Ok(Value::Array(expect_array("reverse", value)?.iter().rev().cloned().collect())),
+    }
+}
+
+/// Sorts an array of numbers or an array of strings
+fn sort(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    let mut array = expect_array("sort", &values[0])?.clone();
+    if array.iter().all(|value| matches!(value, Value::Number(..))) {
+        array.sort_by(|a, b| match (a, b) {
+            (Value::Number(a), Value::Number(b)) => a.total_cmp(b),
+            _ => unreachable!(),
+        });
+    } else if array.iter().all(|value| matches!(value, Value::String(..))) {
+        array.sort_by(|a, b| match (a, b) {
+            (Value::String(a), Value::String(b)) => a.cmp(b),
+            _ => unreachable!(),
+        });
+    } else {
+        return Err("The function `sort` can only sort an array of only numbers or only strings!".to_string());
+    }
+    Ok(Value::Array(array))
+}
+
+/// The most numbers that `range` gives, which keeps a mistyped end from using all of the memory
+const MAX_RANGE_LENGTH: f64 = 1e6;
+
+/// The numbers from the start up to, but not including the end, counting by the step
+fn range(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    let start = expect_number("range", &values[0])?;
+    let end = expect_number("range", &values[1])?;
+    let step = expect_number("range", &values[2])?;
+    if step == 0.0 || !step.is_finite() {
+        return Err(format!("The function `range` cannot count by a step of {step}!"));
+    }
+    if !start.is_finite() || !end.is_finite() {
+        return Err(format!("The function `range` cannot count from {start} to {end}, since they should be finite numbers!"));
+    }
+    let length = ((end - start) / step).ceil().max(0.0);
+    if length > MAX_RANGE_LENGTH {
+        return Err(format!("The function `range` can give at most {MAX_RANGE_LENGTH} numbers, but would give {length}!"));
+    }
+
+    let mut array = Vec::with_capacity(length as usize);
+    let mut i = 0.0;
+    loop {
+        // Multiplying rather than adding up the steps keeps the error of each number from building up
+        let number = start + i * step;
+        if (step > 0.0 && number >= end) || (step < 0.0 && number <= end) {
+            break;
+        }
+        array.push(Value::Number(number));
+        i += 1.0;
+    }
+    Ok(Value::Array(array))
+}
+
+/// The function and the array given to a higher order function, which can come in either order: `map(f, array)` or `map(array, f)`
+fn function_and_array<'v>(function: &str, first: &'v Value, second: &'v Value) -> Result<(&'v Value, &'v Vec<Value>), String> {
+    if let (Value::Array(array), Value::Function(..)) = (first, second) {
+        return Ok((second, array));
+    }
+    expect_function(function, first)?;
+    Ok((first, expect_array(function, second)?))
+}
+
+fn map(vm: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    let (function, array) = function_and_array("map", &values[0], &values[1])?;
+    let mut result = vec![];
+    for value in array {
+        result.push(vm.call(function, vec![value.clone()])?);
+    }
+    Ok(Value::Array(result))
+}
+
+/// The values of the array for which the function does not return zero
+fn filter(vm: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    let (function, array) = function_and_array("filter", &values[0], &values[1])?;
+    let mut result = vec![];
+    for value in array {
+        if expect_truth("filter", &vm.call(function, vec![value.clone()])?)? {
+            result.push(value.clone());
+        }
+    }
+    Ok(Value::Array(result))
+}
+
+/// Combines the values of the array from the left, starting with the initial value: `reduce(f, array, initial)`
+fn reduce(vm: &mut VM, mut values: Vec<Value>) -> Result<Value, String> {
+    let mut accumulator = values.pop().unwrap();
+    let (function, array) = function_and_array("reduce", &values[0], &values[1])?;
+    for value in array {
+        accumulator = vm.call(function, vec![accumulator, value.clone()])?;
+    }
+    Ok(accumulator)
+}
+
+/// Pairs up the values of two arrays, stopping at the end of the shorter one
+fn zip(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    let a = expect_array("zip", &values[0])?;
+    let b = expect_array("zip", &values[1])?;
+    Ok(Value::Array(a.iter().zip(b).map(|(a, b)| Value::Array(vec![a.clone(), b.clone()])).collect()))
+}
+
+/// The keys of a map in order, as an array of strings
+fn keys(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    Ok(Value::Array(expect_map("keys", &values[0])?.keys().cloned().map(Value::String).collect()))
+}
+
+/// The values of a map, in the order of their keys
+fn values(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    Ok(Value::Array(expect_map("values", &values[0])?.values().cloned().collect()))
+}
+
+/// 1 if the map has the key and 0 otherwise
+fn has(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    let map = expect_map("has", &values[0])?;
+    let key = expect_string("has", &values[1])?;
+    Ok(Value::Number(if map.contains_key(&key) { 1.0 } else { 0.0 }))
+}
+
+/// The logarithm of the second argument in the base of the first
+fn log(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    let base = expect_number("log", &values[0])?;
+    let number = expect_number("log", &values[1])?;
+    // No power of 1 or of a base that is not positive gives every number
+    if base <= 0.0 || base == 1.0 {
+        return Err(format!("The function `log` expected a positive base other than 1 but found {base}!"));
+    }
+    Ok(Value::Number(number.log(base)))
+}
+
+/// Division rounded down, so that `floor_div(-7, 2)` is -4
+fn floor_div(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    let a = expect_number("floor_div", &values[0])?;
+    let b = expect_number("floor_div", &values[1])?;
+    // The same error as `/`
+    if b == 0.0 {
+        return Err("Cannot divide a number by zero!".to_string());
+    }
+    Ok(Value::Number((a / b).floor()))
+}
+
+/// The greatest common divisor, by the Euclidean algorithm
+fn gcd(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    let (mut a, mut b) = (expect_whole("gcd", &values[0])?.abs(), expect_whole("gcd", &values[1])?.abs());
+    while b != 0.0 {
+        (a, b) = (b, a % b);
+    }
+    Ok(Value::Number(a))
+}
+
+fn lcm(vm: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    let a = expect_whole("lcm", &values[0])?.abs();
+    let b = expect_whole("lcm", &values[1])?.abs();
+    if a == 0.0 || b == 0.0 {
+        return Ok(Value::Number(0.0));
+    }
+    let Value::Number(divisor) = gcd(vm, values)? else { unreachable!() };
+    Ok(Value::Number(a / divisor * b))
+}
+
+fn factorial(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    let number = expect_whole("factorial", &values[0])?;
+    if number < 0.0 {
+        return Err(format!("The function `factorial` expected a positive number but found {number}!"));
+    }
+    // 171! is too large to be a number
+    if number > 170.0 {
+        return Err(format!("The function `factorial` cannot give the factorial of {number}, since it is too large to be a number!"));
+    }
+    Ok(Value::Number((2..=number as u64).fold(1.0, |product, k| product * k as f64)))
+}
+
+/// The number of ways to choose `k` items out of `n`, which is 0 when `k` is out of the range from 0 to `n`
+fn choose(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    let n = expect_whole("choose", &values[0])?;
+    let k = expect_whole("choose", &values[1])?;
+    if n < 0.0 {
+        return Err(format!("The function `choose` expected a positive number but found {n}!"));
+    }
+    if k < 0.0 || k > n {
+        return Ok(Value::Number(0.0));
+    }
+    // Multiplying and dividing in turn keeps every step a whole number. The steps only grow, so they stop with an error once one is too large
+    let k = k.min(n - k) as u64;
+    let mut result = 1.0;
+    for i in 1..=k {
+        result = result * (n - k as f64 + i as f64) / i as f64;
+        if !result.is_finite() {
+            return Err(format!("The function `choose` cannot give the number of ways to choose {k} out of {n}, since it is too large to be a number!"));
+        }
+    }
+    Ok(Value::Number(result))
 }
\ No newline at end of file
@@ -0,0 +1,119 @@
+use serde_json::{json, Value};
+
+use crate::{signatures, tokens::{TokenType, KEYWORDS}};
+
+/// The TextMate scope of a token that is always written the same way
+fn scope(token_type: &TokenType) -> &'static str {
+    match token_type {
+        TokenType::Semicolon | TokenType::Colon => "punctuation.terminator.vm-calc",
+        TokenType::Comma => "punctuation.separator.vm-calc",
+        TokenType::OpeningBracket | TokenType::ClosingBracket => "punctuation.brackets.round.vm-calc",
+        TokenType::OpenArray | TokenType::CloseArray => "punctuation.brackets.square.vm-calc",
+        TokenType::OpenMap | TokenType::CloseMap => "punctuation.brackets.curly.vm-calc",
+        TokenType::Dot => "punctuation.accessor.vm-calc",
+        TokenType::Ellipsis => "keyword.operator.rest.vm-calc",
+        TokenType::Equal | TokenType::AddEqual | TokenType::SubtractEqual | TokenType::MultiplyEqual | TokenType::DivideEqual
+        | TokenType::ModuloEqual | TokenType::ExponentEqual | TokenType::BitAndEqual | TokenType::BitOrEqual | TokenType::BitXorEqual
+        | TokenType::BitLeftShiftEqual | TokenType::BitRightShiftEqual => "keyword.operator.assignment.vm-calc",
+        TokenType::IsEqual | TokenType::NotEqual | TokenType::Less | TokenType::LessEqual | TokenType::Greater
+        | TokenType::GreaterEqual => "keyword.operator.comparison.vm-calc",
+        TokenType::FatArrow | TokenType::Arrow => "keyword.operator.arrow.vm-calc",
+        TokenType::Backslash => "keyword.operator.lambda.vm-calc",
+        TokenType::BitAnd | TokenType::BitOr | TokenType::BitXor | TokenType::BitLeftShift | TokenType::BitRightShift => "keyword.operator.bitwise.vm-calc",
+        _ => "keyword.operator.arithmetic.vm-calc",
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.chars().map(|c| if c.is_alphanumeric() || c == '_' { c.to_string() } else { format!("\\{c}") }).collect()
+}
+
+/// Creates a TextMate grammar for the language. Operators and punctuation come from the tokens of the lexer,
+/// and longer tokens are matched first so that `**=` is not read as `**` followed by `=`
+pub fn textmate() -> Value {
+    let mut symbols = TokenType::SYMBOLS.to_vec();
+    symbols.sort_by_key(|token_type| std::cmp::Reverse(token_type.to_string().len()));
+    let symbol_patterns = symbols.iter()
+        .map(|token_type| json!({ "name": scope(token_type), "match": escape(&token_type.to_string()) }))
+        .collect::<Vec<_>>();
+
+    let keywords = KEYWORDS.iter().filter(|keyword| **keyword != "Null").copied().collect::<Vec<_>>().join("|");
+    let constants = signatures::CONSTANTS.iter().map(|(name, _)| *name).collect::<Vec<_>>().join("|");
+    let mut builtins = vec!["print", "diff"];
+    for (name, _) in signatures::BUILTINS {
+        if !builtins.contains(&name) {
+            builtins.push(name);
+        }
+    }
+
+    // The characters allowed in identifiers by the lexer
+    let identifier = "[\\p{L}_~#$@`][\\p{L}\\p{N}_~#$@`]*";
+
+    json!({
+        "$schema": "https://raw.githubusercontent.com/martinring/tmlanguage/master/tmlanguage.json",
+        "name": "vm-calc",
+        "scopeName": "source.vm-calc",
+        "fileTypes": ["vmc"],
+        "patterns": [
+            { "include": "#comment" },
+            { "include": "#format-string" },
+            { "include": "#string" },
+            { "include": "#number" },
+            { "include": "#keyword" },
+            { "include": "#function" },
+            { "include": "#identifier" },
+            { "include": "#symbol" },
+        ],
+        "repository": {
+            "comment": { "name": "comment.line.double-slash.vm-calc", "match": "//.*$" },
+            "string": {
+                "name": "string.quoted.double.vm-calc",
+                "begin": "\"",
+                "end": "\"",
+                "patterns": [{ "name": "constant.character.escape.vm-calc", "match": "\\\\." }],
+            },
+            "format-string": {
+                "name": "string.quoted.double.format.vm-calc",
+                "begin": "f\"",
+                "end": "\"",
+                "patterns": [
+                    { "name": "constant.character.escape.vm-calc", "match": "\\\\.|\\{\\{|\\}\\}" },
+                    {
+                        "name": "meta.interpolation.vm-calc",
+                        "begin": "\\{",
+                        "end": "\\}",
+                        "beginCaptures": { "0": { "name": "punctuation.section.interpolation.begin.vm-calc" } },
+                        "endCaptures": { "0": { "name": "punctuation.section.interpolation.end.vm-calc" } },
+                        "patterns": [
+                            { "name": "storage.modifier.format.vm-calc", "match": ":[^}\"]*(?=\\})" },
+                            { "include": "$self" },
+                        ],
+                    },
+                ],
+            },
+            "number": {
+                "patterns": [
+                    { "name": "constant.numeric.hex.vm-calc", "match": "\\b0x[0-9a-fA-F]+\\b" },
+                    { "name": "constant.numeric.binary.vm-calc", "match": "\\b0b[01]+\\b" },
+                    { "name": "constant.numeric.octal.vm-calc", "match": "\\b0o[0-7]+\\b" },
+                    { "name": "constant.numeric.decimal.vm-calc", "match": "(\\b[0-9]+\\.?[0-9]*|\\.[0-9]+)" },
+                ],
+            },
+            "keyword": {
+                "patterns": [
+                    { "name": "keyword.other.vm-calc", "match": format!("\\b({keywords})\\b") },
+                    { "name": "constant.language.null.vm-calc", "match": "\\bNull\\b" },
+                    { "name": "constant.language.vm-calc", "match": format!("\\b({constants})\\b") },
+                ],
+            },
+            "function": {
+                "patterns": [
+                    { "name": "support.function.builtin.vm-calc", "match": format!("\\b({})\\b(?=\\s*\\()", builtins.join("|")) },
+                    { "name": "entity.name.function.vm-calc", "match": format!("{identifier}(?=\\s*\\()") },
+                ],
+            },
+            "identifier": { "name": "variable.other.vm-calc", "match": identifier },
+            "symbol": { "patterns": symbol_patterns },
+        },
+    })
+}
\ No newline at end of file
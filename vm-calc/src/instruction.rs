@@ -1,13 +1,15 @@
-use std::{ops::Range, fmt::Display};
+use std::{collections::BTreeMap, ops::Range, fmt::Display};
 
 use serde::{Deserialize, Serialize};
 
-use crate::ast::Operator;
+use crate::{ast::{Operator, Pattern}, format::FormatSpec};
 
 #[derive(Debug, Clone, Eq, Hash, PartialEq, Serialize, Deserialize)]
 pub enum Symbol<'a> {
     Variable(&'a str),
     Function(&'a str),
+    /// Marks a function that takes any number of arguments after the ones counted by its `Function` symbol
+    Variadic(&'a str),
 }
 
 // There most definitely is a better, more efficient way to represent the bytecode, but I cannot think of it
@@ -18,6 +20,8 @@ pub enum Value {
     Function(Function),
     PartialFunction(Function, Vec<Value>),
     Array(Vec<Value>),
+    /// The keys are kept in order so that maps are written out and compared the same way every time
+    Map(BTreeMap<String, Value>),
     Null,
 }
 
@@ -39,6 +43,10 @@ impl Display for Value {
                 }
                 format!("<Array> [{}]", value_str)
             },
+            Value::Map(map) => {
+                let entries = map.iter().map(|(key, value)| format!("{key}: {value}")).collect::<Vec<_>>();
+                format!("<Map> {{{}}}", entries.join(", "))
+            },
             // WHY?
             Value::Null => format!("{}NULL{}", "{", "}"),
         };
@@ -56,6 +64,7 @@ impl Value {
             Value::PartialFunction(..) => "{PartialFunction}",
             Value::String(..) => "{String}",
             Value::Array(..) => "{Array}",
+            Value::Map(..) => "{Map}",
         }
     }
 }
@@ -93,16 +102,29 @@ pub enum Instruction<'a> {
         name: &'a str,
     },
 
+    /// Create the variables of a pattern, and initialize them with the parts of the value at the top of the stack
+    LoadPattern {
+        #[serde(borrow)]
+        pattern: Pattern<'a>,
+    },
+
+    /// Change the values of the variables of a pattern to the parts of the value at the top of the stack
+    ReloadPattern {
+        #[serde(borrow)]
+        pattern: Pattern<'a>,
+    },
+
     /// Change the value of a variable
     ReloadSymbolOp {
         name: &'a str,
     },
 
-    /// Changes the value at a position in an array
+    /// Changes the value at a position in an array, or a slice of the array after the indices
     ReloadIndex {
         name: &'a str,
         depth: usize,
         operator: Operator,
+        slice: bool,
     },
 
     /// Invoke the value of a variable
@@ -114,12 +136,9 @@ pub enum Instruction<'a> {
     FunctionCall {
         name: Option<&'a str>,
         len: usize,
-    },
-
-    /// Invoke a partial function
-    PartialCall {
-        name: &'a str,
-        len: usize,
+        /// The names of the last arguments, when they are given by name
+        #[serde(borrow)]
+        names: Vec<&'a str>,
     },
 
     /// A function declaration
@@ -127,16 +146,45 @@ pub enum Instruction<'a> {
         name: &'a str,
     },
 
+    /// An anonymous function, laid out like a function declaration but only pushed onto the stack
+    Lambda {
+        /// The names that the body uses from outside of the lambda, whose values are kept with the lambda when they
+        /// are local to the function or match arm that creates it
+        #[serde(borrow)]
+        captures: Vec<&'a str>,
+    },
+
     /// Used to declare a function argument
     ArgumentName {
         name: &'a str,
     },
 
+    /// Used to declare a function argument with a default value, which is computed by the `len` instructions after
+    /// this one when the argument is not given
+    DefaultArgument {
+        name: &'a str,
+        len: usize,
+    },
+
+    /// Used to declare the argument that collects the rest of the arguments into an array, which is always the last one
+    RestArgument {
+        name: &'a str,
+    },
+
     /// Deletes a variable or function
     Delete {
         name: &'a str,
     },
 
+    /// Runs the module in the `len` instructions after this one, unless it has been run already, then declares its symbols
+    /// as a map by the name, or adds them to the current symbols when there is no name
+    Import {
+        /// The full path of the file of the module, which is only the path that was written until it is compiled
+        module: String,
+        alias: Option<&'a str>,
+        len: usize,
+    },
+
     /// Calls the print function
     Print {
         depth: usize
@@ -151,15 +199,46 @@ pub enum Instruction<'a> {
     /// An array
     Array { len: usize, },
 
-    /// Array indexing, used to access the value in an array
+    /// A map, made from pairs of keys and values
+    Map { len: usize, },
+
+    /// Indexing, used to access the value in an array or a map
     Index,
 
+    /// Takes a part of an array or a string, using the start, end and step at the top of the stack
+    Slice,
+
     /// A null value
     Null,
 
+    /// Removes the value at the top of the stack
+    Pop,
+
+    /// Moves forward by a number of instructions
+    Jump { offset: usize },
+
+    /// Takes a number from the stack, and moves forward by a number of instructions when it is 0
+    JumpIfFalse { offset: usize },
+
+    /// Checks the value at the top of the stack against a pattern, leaving the value where it is. When it matches,
+    /// the names of the pattern are bound and 1 is pushed, otherwise 0 is pushed
+    MatchPattern {
+        #[serde(borrow)]
+        pattern: Pattern<'a>,
+    },
+
+    /// Ends the scope of the names bound by the last pattern that matched, giving them back their previous values
+    Unbind,
+
+    /// Reports that none of the patterns of a match matched the value at the top of the stack
+    NoMatch,
+
     /// A keyword to check types
     TypeOf,
 
+    /// Converts a value to a string for a format string
+    Format { spec: FormatSpec },
+
     /// Present the result of the previous expression to the terminal
     Output,
 
@@ -173,12 +252,26 @@ pub enum Instruction<'a> {
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Function {
     pub(crate) arguments: usize,
+    /// Where the declarations of the arguments start, which are followed by the body
+    pub(crate) parameters: usize,
     pub(crate) instructions: Range<usize>,
-    pub(crate) is_partial: Vec<Value>,
+    /// The arguments that have been given to a partially called function, by their position
+    pub(crate) is_partial: Vec<Option<Value>>,
+    /// Whether the last argument is a rest argument, which collects any arguments after the others
+    pub(crate) is_variadic: bool,
+    /// The module that the function was declared in, whose declarations it can use wherever it is called
+    pub(crate) module: Option<String>,
+    /// The values of the captures of a lambda when it was created, by their position
+    pub(crate) captured: Vec<Option<Value>>,
 }
 
 impl Function {
-    pub fn new(arguments: usize, instructions: Range<usize>) -> Self {
-        Self { arguments, instructions, is_partial: vec![] }
+    pub fn new(arguments: usize, parameters: usize, instructions: Range<usize>) -> Self {
+        Self { arguments, parameters, instructions, is_partial: vec![], is_variadic: false, module: None, captured: vec![] }
+    }
+
+    /// The number of arguments that are not collected by the rest argument
+    pub(crate) fn fixed_arguments(&self) -> usize {
+        self.arguments - self.is_variadic as usize
     }
 }
\ No newline at end of file
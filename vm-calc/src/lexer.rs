@@ -1,22 +1,35 @@
 use std::str::Chars;
 use crate::{errors::Error, tokens::{NumberType, Token, TokenType}, utils::Span};
 
+/// Where the lexer is within a format string
+#[derive(Clone, Copy)]
+enum FormatMode {
+    /// The text of the string, outside of `{}`
+    Text,
+    /// An expression within `{}`, along with the number of brackets opened within it
+    Expression { depth: usize },
+}
+
+#[derive(Clone)]
 pub struct Lexer<'a> {
     pub(crate) source: &'a str,
     chars: Chars<'a>,
     current: Option<char>,
     position: usize,
+    /// The format strings that are being lexed. Expressions within them can contain other format strings
+    format: Vec<FormatMode>,
 }
 
 impl Lexer<'_> {
 
-    pub fn new(source: &'_ str) -> Result<Lexer, ()> {
+    pub fn new(source: &str) -> Result<Lexer<'_>, ()> {
         let chars = source.chars();
         Ok(Lexer {
             source,
             chars,
             current: None,
             position: 0,
+            format: vec![],
         })
     }
 
@@ -39,6 +52,10 @@ impl Lexer<'_> {
             }};
         }
 
+        if let Some(FormatMode::Text) = self.format.last() {
+            return self.format_text();
+        }
+
         let next =
             match self.chars.clone().next() {
                 Some(character) => character,
@@ -148,20 +165,28 @@ impl Lexer<'_> {
                 }
             },
 
-            // Leading decimal real numbers ie. `.15`, `.11111`
+            // Leading decimal real numbers ie. `.15`, `.11111`, a field of a map ie. `map.key`, or a rest argument ie. `...xs`
             _ if '.' == next => {
                 self.increment();
                 let mut cloned_iter = self.chars.clone();
-                match cloned_iter.next() { 
-                    Some(character) if Self::check_number(character) => {
+                match (cloned_iter.next(), cloned_iter.next()) { 
+                    (Some(character), _) if Self::check_number(character) => {
                         self.take_while(Self::check_number);
                         Ok(Token::new(TokenType::Number { number_type: NumberType::Real }, Span::new(start, self.position)))
                     },
 
-                    _ => Err(Error::TInvalidCharacter { location: self.position - 1 })
+                    (Some('.'), Some('.')) => variable_token!(2, TokenType::Ellipsis),
+
+                    _ => variable_token!(0, TokenType::Dot)
                 }
             }
 
+            // Format strings
+            'f' if self.chars.clone().nth(1) == Some('"') => {
+                self.format.push(FormatMode::Text);
+                variable_token!(2, TokenType::FormatStart)
+            },
+
             // Register identifiers
             _ if Self::check_ident_start(next) => {
                 self.take_while(Self::check_ident_continue);
@@ -169,22 +194,74 @@ impl Lexer<'_> {
             },
 
             // Delimiters 
-            '(' => token!(TokenType::OpeningBracket),
+            '(' => {
+                self.bracket(true);
+                token!(TokenType::OpeningBracket)
+            },
 
-            ')' => token!(TokenType::ClosingBracket),
+            ')' => {
+                self.bracket(false);
+                token!(TokenType::ClosingBracket)
+            },
 
-            '[' => token!(TokenType::OpenArray),
+            '[' => {
+                self.bracket(true);
+                token!(TokenType::OpenArray)
+            },
             
-            ']' => token!(TokenType::CloseArray),
+            ']' => {
+                self.bracket(false);
+                token!(TokenType::CloseArray)
+            },
+
+            '{' => {
+                self.bracket(true);
+                token!(TokenType::OpenMap)
+            },
 
             ';' => token!(TokenType::Semicolon),
+
+            // The end of an expression within a format string, followed by a specifier
+            ':' if matches!(self.format.last(), Some(FormatMode::Expression { depth: 0 })) => {
+                self.take_while(|c| c != '}' && c != '"');
+                Ok(Token::new(TokenType::FormatSpec, Span::new(start, self.position)))
+            },
+
+            '}' if matches!(self.format.last(), Some(FormatMode::Expression { depth: 0 })) => {
+                if let Some(mode) = self.format.last_mut() {
+                    *mode = FormatMode::Text;
+                }
+                token!(TokenType::InterpolationEnd)
+            },
             
+            '}' => {
+                self.bracket(false);
+                token!(TokenType::CloseMap)
+            },
+
             ':' => token!(TokenType::Colon),
 
+            '\\' => token!(TokenType::Backslash),
+
             ',' => token!(TokenType::Comma),
 
             // Operators
-            '=' => token!(TokenType::Equal),
+            '=' => {
+                self.increment();
+                match self.peek() {
+                    Ok('=') => token!(TokenType::IsEqual),
+                    Ok('>') => token!(TokenType::FatArrow),
+                    _ => variable_token!(0, TokenType::Equal)
+                }
+            },
+
+            '!' => {
+                self.increment();
+                match self.peek() {
+                    Ok('=') => token!(TokenType::NotEqual),
+                    _ => Err(Error::TInvalidCharacter { location: self.position - 1 })
+                }
+            },
 
             '+' => {
                 self.increment();
@@ -198,6 +275,7 @@ impl Lexer<'_> {
                 self.increment();
                 match self.peek() {
                     Ok('=') => variable_token!(1, TokenType::SubtractEqual),
+                    Ok('>') => variable_token!(1, TokenType::Arrow),
                     _ => variable_token!(0, TokenType::Subtract)
                 }
             },
@@ -248,7 +326,8 @@ impl Lexer<'_> {
                             _ => variable_token!(0, TokenType::BitLeftShift)
                         }
                     }
-                    _ => Err(Error::TInvalidCharacter { location: self.position - 1 })
+                    Ok('=') => token!(TokenType::LessEqual),
+                    _ => variable_token!(0, TokenType::Less)
                 }
             },
 
@@ -262,7 +341,8 @@ impl Lexer<'_> {
                             _ => variable_token!(0, TokenType::BitRightShift)
                         }
                     }
-                    _ => Err(Error::TInvalidCharacter { location: self.position - 1 })
+                    Ok('=') => token!(TokenType::GreaterEqual),
+                    _ => variable_token!(0, TokenType::Greater)
                 }
             },
 
@@ -325,6 +405,57 @@ impl Lexer<'_> {
         }
     }
 
+    /// Lexes the text of a format string, up to the next expression or the end of the string
+    fn format_text(&mut self) -> Result<Token, Error> {
+        let start = self.position;
+        let mut ahead = self.chars.clone();
+        match (ahead.next(), ahead.next()) {
+            (None, _) => {
+                self.format.clear();
+                return Err(Error::TIncompleteString { span: Span::new(start, self.position) });
+            },
+
+            (Some('"'), _) => {
+                self.increment();
+                self.format.pop();
+                return Ok(Token::new(TokenType::FormatEnd, Span::new(start, self.position)));
+            },
+
+            (Some('{'), next) if next != Some('{') => {
+                self.increment();
+                if let Some(mode) = self.format.last_mut() {
+                    *mode = FormatMode::Expression { depth: 0 };
+                }
+                return Ok(Token::new(TokenType::InterpolationStart, Span::new(start, self.position)));
+            },
+
+            _ => (),
+        }
+
+        // `{{` and `}}` are written as braces, so they are a part of the text
+        loop {
+            let mut ahead = self.chars.clone();
+            match (ahead.next(), ahead.next()) {
+                (None, _) | (Some('"'), _) => break,
+                (Some('{'), Some('{')) | (Some('}'), Some('}')) | (Some('\\'), Some(_)) => {
+                    self.increment();
+                    self.increment();
+                },
+                (Some('{'), _) => break,
+                _ => self.increment(),
+            }
+        }
+        Ok(Token::new(TokenType::FormatText, Span::new(start, self.position)))
+    }
+
+    /// Keeps track of the brackets within an expression of a format string,
+    /// so that a `:` within them is not taken as the start of the specifier
+    fn bracket(&mut self, open: bool) {
+        if let Some(FormatMode::Expression { depth }) = self.format.last_mut() {
+            *depth = if open { *depth + 1 } else { depth.saturating_sub(1) };
+        }
+    }
+
     // utils
 
     fn take_while<T>(&mut self, mut predicate: T) 
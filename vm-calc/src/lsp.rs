@@ -0,0 +1,396 @@
+use std::{collections::HashMap, io::{BufRead, Write}};
+
+use serde_json::{json, Value};
+
+use crate::{errors::Error, formatter::Formatter, instruction::Symbol, lexer::Lexer, parser::{Definition, Parser}, prelude, signatures::{get_builtin_arguments, get_constant, BUILTINS, CONSTANTS}, tokens::{Token, TokenType, KEYWORDS}, utils::Span};
+
+/// The types of semantic tokens, in the order of their indices
+const TOKEN_TYPES: [&str; 7] = ["keyword", "function", "variable", "number", "string", "operator", "comment"];
+
+/// A language server that communicates over stdio using JSON-RPC
+#[derive(Default)]
+pub struct LanguageServer {
+    /// The text of the open documents by their uri
+    documents: HashMap<String, String>,
+    exit: bool,
+}
+
+/// The information collected by parsing a document
+struct Analysis<'a> {
+    errors: Vec<Error>,
+    symbols: HashMap<Symbol<'a>, usize>,
+    definitions: Vec<Definition<'a>>,
+}
+
+impl LanguageServer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn run(&mut self) -> Result<(), ()> {
+        let stdin = std::io::stdin();
+        let mut reader = stdin.lock();
+        while !self.exit {
+            let message = match Self::read_message(&mut reader) {
+                Some(message) => message,
+                None => break,
+            };
+            for response in self.handle(&message) {
+                Self::send(&response)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn read_message(reader: &mut impl BufRead) -> Option<Value> {
+        let mut length = None;
+        loop {
+            let mut header = String::new();
+            if reader.read_line(&mut header).ok()? == 0 {
+                return None;
+            }
+            let header = header.trim();
+            if header.is_empty() {
+                break;
+            }
+            if let Some(value) = header.strip_prefix("Content-Length:") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+        let mut body = vec![0; length?];
+        reader.read_exact(&mut body).ok()?;
+        serde_json::from_slice(&body).ok()
+    }
+
+    fn send(message: &Value) -> Result<(), ()> {
+        let body = message.to_string();
+        let mut stdout = std::io::stdout().lock();
+        write!(stdout, "Content-Length: {}\r\n\r\n{body}", body.len()).map_err(|_| ())?;
+        stdout.flush().map_err(|_| ())
+    }
+
+    /// Handles a message from the client, returning the responses and notifications to send back
+    pub fn handle(&mut self, message: &Value) -> Vec<Value> {
+        let method = message["method"].as_str().unwrap_or("");
+        let params = &message["params"];
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or("").to_string();
+
+        let result = match method {
+            "initialize" => json!({
+                "capabilities": {
+                    "textDocumentSync": 1,
+                    "hoverProvider": true,
+                    "definitionProvider": true,
+                    "completionProvider": {},
+                    "semanticTokensProvider": {
+                        "legend": { "tokenTypes": TOKEN_TYPES, "tokenModifiers": [] },
+                        "full": true,
+                    },
+                },
+                "serverInfo": { "name": "vm-calc" },
+            }),
+
+            "shutdown" => Value::Null,
+
+            "exit" => {
+                self.exit = true;
+                return vec![];
+            }
+
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or("").to_string();
+                self.documents.insert(uri.clone(), text);
+                return vec![self.diagnostics(&uri)];
+            }
+
+            "textDocument/didChange" => {
+                // The whole document is sent on every change
+                if let Some(text) = params["contentChanges"].as_array().and_then(|changes| changes.last()) {
+                    self.documents.insert(uri.clone(), text["text"].as_str().unwrap_or("").to_string());
+                }
+                return vec![self.diagnostics(&uri)];
+            }
+
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                return vec![json!({
+                    "jsonrpc": "2.0",
+                    "method": "textDocument/publishDiagnostics",
+                    "params": { "uri": uri, "diagnostics": [] },
+                })];
+            }
+
+            "textDocument/hover" => self.with_document(&uri, |source| {
+                let offset = Self::offset(source, &params["position"]);
+                Self::hover(source, offset)
+            }),
+
+            "textDocument/definition" => self.with_document(&uri, |source| {
+                let offset = Self::offset(source, &params["position"]);
+                match Self::definition(source, offset) {
+                    Some(span) => json!({ "uri": uri, "range": Self::range(source, span) }),
+                    None => Value::Null,
+                }
+            }),
+
+            "textDocument/completion" => self.with_document(&uri, Self::completion),
+
+            "textDocument/semanticTokens/full" => self.with_document(&uri, |source| json!({ "data": Self::semantic_tokens(source) })),
+
+            _ => {
+                // Notifications that are not supported are ignored, but requests need a response
+                if message["id"].is_null() {
+                    return vec![];
+                }
+                return vec![json!({
+                    "jsonrpc": "2.0",
+                    "id": message["id"],
+                    "error": { "code": -32601, "message": format!("Unsupported method `{method}`") },
+                })];
+            }
+        };
+
+        vec![json!({ "jsonrpc": "2.0", "id": message["id"], "result": result })]
+    }
+
+    fn with_document(&self, uri: &str, function: impl FnOnce(&str) -> Value) -> Value {
+        match self.documents.get(uri) {
+            Some(source) => function(source),
+            None => Value::Null,
+        }
+    }
+
+    fn analyze(source: &str) -> Analysis<'_> {
+        let lexer = Lexer::new(source).expect("Failed to initialize the lexer!");
+        // The functions of the prelude can be used like any other
+        let mut parser = Parser::new_fn_symbols(lexer, prelude::symbols());
+        let mut errors = vec![];
+        while !parser.eof {
+            let before = parser.token.clone();
+            match parser.next_expression() {
+                Ok(..) | Err(Error::NoResult) => (),
+                Err(error) => errors.push(error),
+            }
+            // Skip the token if the parser could not move past it
+            if parser.token == before && !parser.eof {
+                if let Err(error) = parser.increment() {
+                    errors.push(error);
+                }
+            }
+        }
+        Analysis { errors, symbols: parser.symbols, definitions: parser.definitions }
+    }
+
+    fn diagnostics(&self, uri: &str) -> Value {
+        let source = self.documents.get(uri).map(String::as_str).unwrap_or("");
+        let diagnostics = Self::analyze(source).errors.iter()
+            .filter_map(|error| {
+                let span = error.span()?;
+                Some(json!({
+                    "range": Self::range(source, span),
+                    "severity": 1,
+                    "source": "vm-calc",
+                    "message": format!("{error}"),
+                }))
+            })
+            .collect::<Vec<_>>();
+
+        json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/publishDiagnostics",
+            "params": { "uri": uri, "diagnostics": diagnostics },
+        })
+    }
+
+    fn hover(source: &str, offset: usize) -> Value {
+        let Some(token) = Self::identifier_at(source, offset) else {
+            return Value::Null;
+        };
+        let name = &source[token.span.as_range()];
+        let symbols = Self::analyze(source).symbols;
+        let contents = if let Ok(arguments) = get_builtin_arguments(name) {
+            format!("```\n{name}({})\n```\nBuiltin function taking {arguments} argument(s)", vec!["*"; arguments].join(", "))
+        } else if name == "print" {
+            "```\nprint(...)\n```\nBuiltin function taking any number of arguments".to_string()
+        } else if name == "diff" {
+            "```\ndiff(expression, variable)\n```\nBuiltin function giving the derivative of the expression by the variable".to_string()
+        } else if let Ok(value) = get_constant(name) {
+            format!("```\n{name} = {value}\n```\nConstant")
+        } else if KEYWORDS.contains(&name) {
+            format!("`{name}` keyword")
+        } else if let Some(&arguments) = symbols.get(&Symbol::Function(name)) {
+            let mut parameters = vec!["*"; arguments];
+            if symbols.contains_key(&Symbol::Variadic(name)) {
+                parameters.push("...");
+            }
+            format!("```\n{name}({})\n```\nFunction taking {}", parameters.join(", "), Self::argument_count(&symbols, name, arguments))
+        } else {
+            format!("`{name}`")
+        };
+        json!({ "contents": { "kind": "markdown", "value": contents }, "range": Self::range(source, token.span) })
+    }
+
+    /// Finds the declaration of the identifier at the offset. Arguments are preferred within their function,
+    /// followed by the closest declaration before the identifier and then the first one after it
+    fn definition(source: &str, offset: usize) -> Option<Span> {
+        let token = Self::identifier_at(source, offset)?;
+        let name = &source[token.span.as_range()];
+        let definitions = Self::analyze(source).definitions;
+        let matching = definitions.iter().filter(|definition| {
+            matches!(definition.symbol, Symbol::Variable(symbol) | Symbol::Function(symbol) if symbol == name)
+        });
+
+        let mut scoped = None;
+        let mut before = None;
+        let mut after = None;
+        for definition in matching {
+            match definition.scope {
+                Some(scope) if scope.start <= offset && offset <= scope.end => scoped = Some(definition.span),
+                Some(..) => (),
+                None if definition.span.start <= offset => before = Some(definition.span),
+                None => { after.get_or_insert(definition.span); },
+            }
+        }
+        scoped.or(before).or(after)
+    }
+
+    /// How many arguments a declared function takes, which has no upper limit when it has a rest argument
+    fn argument_count(symbols: &HashMap<Symbol, usize>, name: &str, arguments: usize) -> String {
+        if symbols.contains_key(&Symbol::Variadic(name)) {
+            format!("{arguments} or more argument(s)")
+        } else {
+            format!("{arguments} argument(s)")
+        }
+    }
+
+    fn completion(source: &str) -> Value {
+        let mut items = vec![];
+        for (name, arguments) in BUILTINS {
+            items.push(json!({ "label": name, "kind": 3, "detail": format!("Builtin function taking {arguments} argument(s)") }));
+        }
+        items.push(json!({ "label": "print", "kind": 3, "detail": "Builtin function taking any number of arguments" }));
+        items.push(json!({ "label": "diff", "kind": 3, "detail": "Builtin function giving the derivative of an expression by a variable" }));
+        for (name, value) in CONSTANTS {
+            items.push(json!({ "label": name, "kind": 21, "detail": format!("Constant equal to {value}") }));
+        }
+        for keyword in KEYWORDS {
+            items.push(json!({ "label": keyword, "kind": 14 }));
+        }
+
+        let analysis = Self::analyze(source);
+        let mut symbols = analysis.symbols.iter().collect::<Vec<_>>();
+        symbols.sort_by_key(|(symbol, _)| format!("{symbol:?}"));
+        for (symbol, arguments) in symbols {
+            match *symbol {
+                Symbol::Function(name) => items.push(json!({ "label": name, "kind": 3, "detail": format!("Function taking {}", Self::argument_count(&analysis.symbols, name, *arguments)) })),
+                Symbol::Variable(name) => items.push(json!({ "label": name, "kind": 6 })),
+                Symbol::Variadic(..) => (),
+            }
+        }
+        Value::Array(items)
+    }
+
+    /// Encodes the tokens of the source in the relative format of the protocol
+    fn semantic_tokens(source: &str) -> Vec<u32> {
+        let symbols = Self::analyze(source).symbols;
+        let mut tokens = vec![];
+        let mut lexer = Lexer::new(source).expect("Failed to initialize the lexer!");
+        loop {
+            let token = match lexer.next() {
+                Ok(token) => token,
+                Err(Error::TEOF) => break,
+                // The lexer moves past invalid input, so the rest can still be highlighted
+                Err(..) => continue,
+            };
+            let token_type = match &token.token_type {
+                TokenType::Number { .. } => Some(3),
+                TokenType::String | TokenType::FormatStart | TokenType::FormatText | TokenType::FormatSpec | TokenType::FormatEnd => Some(4),
+                TokenType::Identifier => {
+                    let name = &source[token.span.as_range()];
+                    if KEYWORDS.contains(&name) {
+                        Some(0)
+                    } else if get_builtin_arguments(name).is_ok() || name == "print" || name == "diff" || symbols.contains_key(&Symbol::Function(name)) {
+                        Some(1)
+                    } else {
+                        Some(2)
+                    }
+                }
+                TokenType::Semicolon | TokenType::Colon | TokenType::Comma | TokenType::OpeningBracket | TokenType::ClosingBracket
+                | TokenType::OpenArray | TokenType::CloseArray | TokenType::OpenMap | TokenType::CloseMap | TokenType::Dot
+                | TokenType::InterpolationStart | TokenType::InterpolationEnd
+                | TokenType::Null | TokenType::EOF => None,
+                _ => Some(5),
+            };
+            if let Some(token_type) = token_type {
+                tokens.push((token.span, token_type));
+            }
+        }
+        tokens.extend(Formatter::find_comments(source).into_iter().map(|span| (span, 6)));
+        tokens.sort_by_key(|(span, _)| span.start);
+
+        let mut data = vec![];
+        let (mut last_line, mut last_character) = (0, 0);
+        for (span, token_type) in tokens {
+            // Tokens cannot span multiple lines, so strings are split into one token per line
+            let mut start = span.start;
+            for line in source[span.as_range()].split_inclusive('\n') {
+                let text = line.trim_end_matches(['\n', '\r']);
+                let (line_number, character) = Self::line_character(source, start);
+                let length = text.encode_utf16().count() as u32;
+                if length > 0 {
+                    let delta_line = line_number - last_line;
+                    let delta_character = if delta_line == 0 { character - last_character } else { character };
+                    data.extend([delta_line, delta_character, length, token_type, 0]);
+                    (last_line, last_character) = (line_number, character);
+                }
+                start += line.len();
+            }
+        }
+        data
+    }
+
+    fn identifier_at(source: &str, offset: usize) -> Option<Token> {
+        let mut lexer = Lexer::new(source).ok()?;
+        loop {
+            match lexer.next() {
+                Ok(token) if token.span.start > offset => return None,
+                Ok(token) if token.token_type == TokenType::Identifier && offset <= token.span.end => return Some(token),
+                Ok(..) => (),
+                Err(Error::TEOF) => return None,
+                Err(..) => (),
+            }
+        }
+    }
+
+    /// Converts a byte offset to a line and a character offset in UTF-16 code units, as the protocol expects
+    fn line_character(source: &str, offset: usize) -> (u32, u32) {
+        let before = &source[..offset.min(source.len())];
+        let line = before.matches('\n').count() as u32;
+        let line_start = before.rfind('\n').map(|position| position + 1).unwrap_or(0);
+        (line, before[line_start..].encode_utf16().count() as u32)
+    }
+
+    fn range(source: &str, span: Span) -> Value {
+        let (start_line, start_character) = Self::line_character(source, span.start);
+        let (end_line, end_character) = Self::line_character(source, span.end);
+        json!({
+            "start": { "line": start_line, "character": start_character },
+            "end": { "line": end_line, "character": end_character },
+        })
+    }
+
+    /// Converts a position of the protocol to a byte offset in the source
+    fn offset(source: &str, position: &Value) -> usize {
+        let line = position["line"].as_u64().unwrap_or(0) as usize;
+        let character = position["character"].as_u64().unwrap_or(0) as usize;
+        let line_start = source.split_inclusive('\n').take(line).map(str::len).sum::<usize>();
+        let mut units = 0;
+        for (offset, c) in source[line_start..].char_indices() {
+            if units >= character || c == '\n' {
+                return line_start + offset;
+            }
+            units += c.len_utf16();
+        }
+        source.len()
+    }
+}
\ No newline at end of file
@@ -9,16 +9,29 @@ mod utils;
 mod vm;
 mod functions;
 mod processchain;
+mod formatter;
+mod lsp;
+mod grammar;
+mod format;
+mod matrix;
+mod prelude;
+mod stats;
+mod random;
+mod calculus;
+mod symbolic;
+mod plot;
+mod polynomial;
+mod signatures;
 
 // Tests
 mod tests;
 
-use std::{collections::HashMap, io::Write, time::Instant};
+use std::{collections::HashMap, io::Write, rc::Rc, time::Instant};
 
-use instruction::{Instruction, Value};
+use instruction::{Instruction, Symbol};
 use processchain::ProcessChain;
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
@@ -43,6 +56,14 @@ struct Args {
     #[arg(long="show-instructions", short = 'i')]
     showinstructions: Option<String>,
 
+    /// Show the tokens of a file as JSON
+    #[arg(long="tokens")]
+    showtokens: Option<String>,
+
+    /// Show a TextMate grammar to highlight the language
+    #[arg(long="grammar")]
+    grammar: bool,
+
     /// Run code from text
     #[arg(long="text", short = 't')]
     text: Option<String>,
@@ -50,6 +71,33 @@ struct Args {
     /// Run the REPL
     #[arg(long="repl", short = 'l')]
     repl: bool,
+
+    /// Seed the random numbers, so that running the code again gives the same ones
+    #[arg(long="seed")]
+    seed: Option<u64>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Format a file and print the formatted code
+    Fmt {
+        /// File to format
+        path: String,
+
+        /// Only check if the file is formatted, failing if it is not
+        #[arg(long = "check")]
+        check: bool,
+
+        /// Write the formatted code back to the file
+        #[arg(long = "write", short = 'w')]
+        write: bool,
+    },
+
+    /// Run the language server over stdio
+    Lsp,
 }
 
 fn main() -> Result<(), ()> {
@@ -60,9 +108,23 @@ fn main() -> Result<(), ()> {
 fn run() -> Result<(), ()> {
     let args = Args::parse();
 
+    match args.command {
+        Some(Command::Fmt { path, check, write }) => {
+            ProcessChain::format_file(&path, check, write)?;
+            return Ok(());
+        },
+
+        Some(Command::Lsp) => {
+            lsp::LanguageServer::new().run()?;
+            return Ok(());
+        },
+
+        None => (),
+    }
+
     match args.runfile {
         Some(value) => {
-            ProcessChain::run_from_file(&value)?;
+            ProcessChain::run_from_file(&value, args.seed)?;
             return Ok(());
         },
 
@@ -79,14 +141,14 @@ fn run() -> Result<(), ()> {
             };
             &format!("{}.bin", res)
         };
-        ProcessChain::store_bytecode_from_file(&value, output)?;
+        ProcessChain::store_bytecode_from_file(value, output)?;
         Ok(())
     };
 
     match args.runfilebin {
         Some(value) => {
             store(&value)?;
-            ProcessChain::run_from_file(&value)?;
+            ProcessChain::run_from_file(&value, args.seed)?;
             return Ok(());
         },
 
@@ -134,9 +196,19 @@ fn run() -> Result<(), ()> {
         None => (),
     };
 
+    if let Some(path) = args.showtokens {
+        ProcessChain::show_tokens_from_file(&path)?;
+        return Ok(());
+    }
+
+    if args.grammar {
+        ProcessChain::show_grammar()?;
+        return Ok(());
+    }
+
     match args.text {
         Some(value) => {
-            ProcessChain::run_from_text(&value)?;
+            ProcessChain::run_from_text(&value, args.seed)?;
             return Ok(());
         }
 
@@ -146,7 +218,7 @@ fn run() -> Result<(), ()> {
     // No point in this, but yes.
     // Runs the repl anyway if no other commands are passed
     if args.repl || true {
-        repl();
+        repl(args.seed);
     }
 
     Ok(())
@@ -155,28 +227,44 @@ fn run() -> Result<(), ()> {
 
 // Known Issue: creating a function and referncing it, then deleting the original function before calling the referenced function 
 // will cause the program to crash due to the function code it references, not existing. A similar thing applies to partial functions
-fn repl() {
+fn repl(seed: Option<u64>) {
     // Introduction
     println!("Running repl...");
     println!("Type `.quit` | `.q` to exit the repl");
     println!("Type `.show symbols` | `.show sym` to show the symbols in the session");
     println!("Type `.time` | `.timer` to time the execution of the code");
+    println!("Type `.seed <number>` to seed the random numbers, so that the same code gives the same ones");
     println!("Type `.load <filepath>` to load and execute code (timer does not apply to this)");
     println!("Type `.load bytecode <filepath>` | `.load b <filepath>` to load and execute bytecode (timer does not apply to this)");
+    println!("Type `.show fn <name>` to show the arguments and body of a function");
+    println!("Type `.dis <name>` to disassemble the bytecode of a function");
+    println!("Type `.ast <expr>` | `.bytecode <expr>` to show the parsed tree | instructions of an expression without running it");
+    println!("Unfinished expressions continue on the next line after a `..` prompt. Enter an empty line to submit them as they are");
 
     let mut symbols = HashMap::new();
-    let mut p_symbols = HashMap::new();
+    let mut p_symbols = prelude::symbols();
 
-    let mut fn_bytecode = vec![];
+    // The functions of the prelude are declared again on each line, like those of the user
+    let mut fn_bytecode = prelude::instructions();
     let mut functions = HashMap::new();
+    // The parsed declarations of the functions, to display them with `.show fn`
+    let mut declarations: HashMap<&str, Rc<ast::Tree>> = HashMap::new();
     
+    // The modules are kept between lines like the symbols, so that each one is only run once in the session
+    let mut modules = HashMap::new();
+    // The instructions of the functions in the symbols, like lambdas, which are put before the instructions of each line
+    let mut kept = vec![];
     let mut time = false;
+    // The generator is kept between lines, so that a seeded session gives the same numbers each time
+    let mut random = ProcessChain::random(seed);
+    // Lines of an expression that has not been completed yet
+    let mut pending = String::new();
     loop {
-        print!(">> ");
+        print!("{}", if pending.is_empty() { ">> " } else { ".. " });
         std::io::stdout().flush().expect("Failed to flush the buffer");
         let mut buffer = String::new();
         std::io::stdin().read_line(&mut buffer).expect("Failed to read from the command line");
-        if [".quit", ".q", ".exit", ".quit()", ".q()", ".stop", ".stop()"].contains(&buffer.trim()) {
+        if pending.is_empty() && [".quit", ".q", ".exit", ".quit()", ".q()", ".stop", ".stop()"].contains(&buffer.trim()) {
             break;
         }
         let comment: Option<usize> = buffer.find("//");
@@ -185,7 +273,18 @@ fn repl() {
             None => (),
         };
         buffer = buffer.trim().to_string();
-        if buffer.is_empty() {
+
+        let continued = !pending.is_empty();
+        if continued {
+            // An empty line submits the input as is, so that its error is reported instead of waiting forever
+            if !buffer.is_empty() {
+                pending = format!("{pending}\n{buffer}");
+                if incomplete_input(&pending, &p_symbols) {
+                    continue;
+                }
+            }
+            buffer = std::mem::take(&mut pending);
+        } else if buffer.is_empty() {
             println!("No expression was provided. Did you mean to exit? Type in `.quit` to exit");
             continue;
         }
@@ -198,7 +297,7 @@ fn repl() {
             match split.next() {
                 Some(filename) => {
                     println!("loading binary file and executing: ");
-                    ProcessChain::run_from_bytecode(filename).ok();
+                    ProcessChain::run_from_bytecode(filename, Some(random.next_u64())).ok();
                 },
                 None => println!("Expected file path to load file!"),
             };
@@ -209,7 +308,7 @@ fn repl() {
             match split.next() {
                 Some(filename) => {
                     println!("loading file and executing: ");
-                    ProcessChain::run_from_file(filename).ok();
+                    ProcessChain::run_from_file(filename, Some(random.next_u64())).ok();
                 },
                 None => println!("Expected file path to load file!"),
             };
@@ -225,18 +324,83 @@ fn repl() {
             continue;
         } else if [".show builtin", ".display builtin"].contains(&buffer.as_str()) {
             println!("BUILTIN FUNCTIONS: ");
-            for (function, (args, _)) in functions::FUNCTIONS {
+            for (function, args) in signatures::BUILTINS {
                 let repeated = "*, ".repeat(args);
                 println!("{function}({})", if args > 0 { &repeated[..(args * 3 - 2)] } else { "" });
             }
             continue;
         }
+        else if let Some(name) = buffer.strip_prefix(".show fn ") {
+            let name = name.trim();
+            match declarations.get(name) {
+                Some(tree) if functions.contains_key(name) => {
+                    if let ast::AST::FunctionDecl { arguments, defaults, rest, body, .. } = &tree.ast {
+                        println!("Function `{name}`:");
+                        println!("Arguments: {}", if arguments.is_empty() && rest.is_none() { "None".to_string() } else { ast::parameters(arguments, defaults, *rest) });
+                        println!("Body: {body}");
+                    }
+                },
+                _ => println!("The function `{name}` does not exist!"),
+            }
+            continue;
+        } else if let Some(name) = buffer.strip_prefix(".dis ") {
+            let name = name.trim();
+            match functions.get(name) {
+                Some(&start) => {
+                    let end = match fn_bytecode.get(start + 2) {
+                        Some(Instruction::UData { number }) => start + number + 2,
+                        _ => start,
+                    };
+                    println!("Bytecode of function `{name}`:");
+                    for (index, instruction) in fn_bytecode[start..=end].iter().enumerate() {
+                        println!("{index:>4}: {instruction:?}");
+                    }
+                },
+                None => println!("The function `{name}` does not exist!"),
+            }
+            continue;
+        } else if let Some(expression) = buffer.strip_prefix(".ast ") {
+            let lexer = lexer::Lexer::new(expression).expect("Failed to initialize the lexer!");
+            let mut parser = parser::Parser::new_fn_symbols(lexer, p_symbols.clone());
+            loop {
+                match parser.next_expression_repl() {
+                    Ok(tree) => println!("{tree}"),
+                    Err(error) => println!("{error}"),
+                }
+                if parser.eof { break; }
+            }
+            continue;
+        } else if let Some(expression) = buffer.strip_prefix(".bytecode ") {
+            let lexer = lexer::Lexer::new(expression).expect("Failed to initialize the lexer!");
+            let parser = parser::Parser::new_fn_symbols(lexer, p_symbols.clone());
+            let (instructions, _) = bytecode::Bytecode::new(parser).generate_fn_bytecode(vec![]);
+            for (index, instruction) in instructions.iter().enumerate() {
+                println!("{index:>4}: {instruction:?}");
+            }
+            continue;
+        }
         else if [".time", ".timer"].contains(&buffer.as_str()) {
             time = !time;
             println!("The timer is now {}", if time { "on" } else { "off" });
             continue;
         }
+        else if let Some(seed) = buffer.strip_prefix(".seed") {
+            match seed.trim().parse() {
+                Ok(seed) => {
+                    random = random::Random::new(seed);
+                    println!("The random numbers are now seeded with {seed}");
+                },
+                Err(..) => println!("Expected a whole, positive number to seed the random numbers with!"),
+            }
+            continue;
+        }
         
+        // Wait for the rest of the expression on the next lines
+        if !continued && incomplete_input(&buffer, &p_symbols) {
+            pending = buffer;
+            continue;
+        }
+
         let source = Box::leak(Box::new(buffer));
         
         if time { println!("Begin compilation"); }
@@ -249,69 +413,45 @@ fn repl() {
         let mut bytecode_gen = bytecode::Bytecode::new(parser);
         let (instructions, new_fn_bytecode) = bytecode_gen.generate_fn_bytecode(fn_bytecode.clone());
 
+        declarations.extend(std::mem::take(&mut bytecode_gen.declarations));
         p_symbols = bytecode_gen.get_symbols();
 
-        let delete_fn = |functions: &mut HashMap<&str, usize>, symbols: &mut HashMap<&str, Value>, fn_bytecode: &mut Vec<Instruction>, name: &str| {
-            if functions.contains_key(name) {
-                if symbols.contains_key(name) {
-                    symbols.remove(name);
-                }
-                // Delete function
-                let start = *functions.get(name).unwrap();
-                if let Some(Instruction::FunctionDecl { .. }) = &fn_bytecode.get(start) {
-                    let end = match fn_bytecode.get(start + 2) {
-                        Some(Instruction::UData { number }) => number,
-                        _ => panic!("Unknown error trying to run the repl!"),
-                    };
-                    fn_bytecode.drain(start..=(start + end + 2));
-                }
-                functions.remove(name);
-            }
-        };
-
-        for instr in new_fn_bytecode.clone() {
-            match instr {
-                Instruction::FunctionDecl { name } => {
-                    if !functions.contains_key(name) {
-                        functions.insert(name, fn_bytecode.len());
-                    } else {
-                        // Delete old function to replace with new
-                        let start = *functions.get(name).unwrap();
-                        if let Some(Instruction::FunctionDecl { .. }) = &fn_bytecode.get(start) {
-                            let end = match fn_bytecode.get(start + 2) {
-                                Some(Instruction::UData { number }) => number,
-                                _ => panic!("Unknown error trying to run the repl!"),
-                            };
-                            fn_bytecode.drain(start..=(start + end + 2));
-                        }
-                    }
-                    fn_bytecode.push(instr);
-                }
-
-                Instruction::Output => (),
-
-                _ => { fn_bytecode.push(instr); }
+        // Only the last declaration of each function is kept to be run before the next lines
+        let mut declared: Vec<Vec<Instruction>> = vec![];
+        for declaration in split_declarations(&new_fn_bytecode) {
+            if let Instruction::FunctionDecl { name } = declaration[0] {
+                declared.retain(|other| other[0] != Instruction::FunctionDecl { name });
             }
+            declared.push(declaration);
         }
 
-        
+        // A function that is deleted or given another value is not declared again
         for instr in &instructions {
-            match instr {
-                Instruction::Delete { name } => {
-                    delete_fn(&mut functions, &mut symbols, &mut fn_bytecode, name);
-                }
-
-                Instruction::ReloadSymbol { name } => {
-                    delete_fn(&mut functions, &mut symbols, &mut fn_bytecode, name);
+            if let Instruction::Delete { name } | Instruction::ReloadSymbol { name } = instr {
+                let declaration = Instruction::FunctionDecl { name };
+                if declared.iter().any(|other| other[0] == declaration) {
+                    symbols.remove(name);
+                    declared.retain(|other| other[0] != declaration);
                 }
+            }
+        }
 
-                _ => (),
+        fn_bytecode.clear();
+        functions.clear();
+        for declaration in declared {
+            if let Instruction::FunctionDecl { name } = declaration[0] {
+                functions.insert(name, fn_bytecode.len());
             }
+            fn_bytecode.extend(declaration);
         }
 
+        // The functions that were kept from the last line are only needed when this one is run
+        let compiled = instructions.first() != Some(&Instruction::CompileError);
+        let instructions = if compiled { [kept.clone(), instructions].concat() } else { instructions };
+
         if time { println!("Finished compilation in {:?}", instant.elapsed()); }
         
-        let mut vm = vm::VM::new_with_symbols(instructions, symbols);
+        let mut vm = vm::VM::new_with_symbols(instructions, symbols).with_prelude(prelude::names()).with_modules(modules).with_random(random);
         
         if time { println!("Begin run"); }
         let instant = Instant::now();
@@ -320,9 +460,50 @@ fn repl() {
 
         vm.print_output();
 
+        random = vm.random.clone();
+        modules = vm.take_modules();
+        if compiled {
+            kept = vm.take_functions();
+        }
         symbols = vm.get_symbols();
         
         if time { println!("Finished run in {:?}", instant.elapsed()); }
     }
     println!("Finished repl");
+}
+
+/// Splits the code that is run before each line of the repl into the declarations of functions and the imports of modules
+fn split_declarations<'a>(bytecode: &[Instruction<'a>]) -> Vec<Vec<Instruction<'a>>> {
+    let mut declarations = vec![];
+    let mut start = 0;
+    while start < bytecode.len() {
+        let len = match (&bytecode[start], bytecode.get(start + 2)) {
+            (Instruction::FunctionDecl { .. }, Some(Instruction::UData { number })) => number + 3,
+            (Instruction::Import { len, .. }, _) => len + 1,
+            _ => 1,
+        };
+        let end = bytecode.len().min(start + len);
+        declarations.push(bytecode[start..end].to_vec());
+        start = end;
+    }
+    declarations
+}
+
+/// Checks if the input ends partway through an expression, like an unclosed bracket, string or a trailing operator
+fn incomplete_input(source: &str, symbols: &HashMap<Symbol, usize>) -> bool {
+    let Ok(lexer) = lexer::Lexer::new(source) else {
+        return false;
+    };
+    let mut parser = parser::Parser::new_fn_symbols(lexer, symbols.clone());
+    loop {
+        match parser.next_expression_repl() {
+            Ok(..) => (),
+            // Any other error is left for the compilation to report
+            Err(error) => return error.is_incomplete(),
+        }
+
+        if parser.eof {
+            return false;
+        }
+    }
 }
\ No newline at end of file
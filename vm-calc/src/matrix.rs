@@ -0,0 +1,181 @@
+use crate::{functions::{expect_array, expect_position, ValueFunction}, instruction::Value, vm::VM};
+
+/// Pivots smaller than this are taken as zero when solving matrices
+const EPSILON: f64 = 1e-12;
+
+pub const FUNCTIONS: [(&str, (usize, ValueFunction)); 7] = [
+    ("dot", (2, dot)),
+    ("matmul", (2, matmul)),
+    ("transpose", (1, transpose)),
+    ("det", (1, det)),
+    ("inv", (1, inv)),
+    ("identity", (1, identity)),
+    ("linsolve", (2, linsolve)),
+];
+
+/// The length of an array followed by the lengths of the arrays within it, such as `[2, 3]` for a 2 by 3 matrix
+pub fn shape(array: &[Value]) -> Vec<usize> {
+    let mut lengths = vec![array.len()];
+    if let Some(Value::Array(inner)) = array.first() {
+        lengths.extend(shape(inner));
+    }
+    lengths
+}
+
+pub(crate) fn vector(function: &str, value: &Value) -> Result<Vec<f64>, String> {
+    expect_array(function, value)?.iter().map(|value| match value {
+        Value::Number(number) => Ok(*number),
+        value => Err(format!("The function `{function}` expected an array of numbers but found a value of type {} in it!", value.type_of())),
+    }).collect()
+}
+
+/// Reads a matrix, which is an array of rows that are arrays of numbers with the same length
+fn matrix(function: &str, value: &Value) -> Result<Vec<Vec<f64>>, String> {
+    let rows = expect_array(function, value)?.iter().map(|row| vector(function, row)).collect::<Result<Vec<_>, _>>()?;
+    if rows.is_empty() || rows[0].is_empty() {
+        return Err(format!("The function `{function}` cannot be used on an empty matrix!"));
+    }
+    if rows.iter().any(|row| row.len() != rows[0].len()) {
+        return Err(format!("The function `{function}` expected a matrix, but the rows of the array have different lengths!"));
+    }
+    Ok(rows)
+}
+
+fn square_matrix(function: &str, value: &Value) -> Result<Vec<Vec<f64>>, String> {
+    let rows = matrix(function, value)?;
+    if rows.len() != rows[0].len() {
+        return Err(format!("The function `{function}` expected a square matrix but found a matrix of shape [{}, {}]!", rows.len(), rows[0].len()));
+    }
+    Ok(rows)
+}
+
+fn to_value(rows: Vec<Vec<f64>>) -> Value {
+    Value::Array(rows.into_iter().map(|row| Value::Array(row.into_iter().map(Value::Number).collect())).collect())
+}
+
+fn dot(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    let a = vector("dot", &values[0])?;
+    let b = vector("dot", &values[1])?;
+    if a.len() != b.len() {
+        return Err(format!("Cannot take the dot product of vectors of different shapes: [{}] and [{}]!", a.len(), b.len()));
+    }
+    Ok(Value::Number(a.iter().zip(&b).map(|(a, b)| a * b).sum()))
+}
+
+fn matmul(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    let a = matrix("matmul", &values[0])?;
+    let b = matrix("matmul", &values[1])?;
+    if a[0].len() != b.len() {
+        return Err(format!(
+            "Cannot multiply matrices of shapes [{}, {}] and [{}, {}], the columns of the first must match the rows of the second!",
+            a.len(), a[0].len(), b.len(), b[0].len(),
+        ));
+    }
+    let result = a.iter()
+        .map(|row| (0..b[0].len()).map(|column| row.iter().zip(&b).map(|(a, b_row)| a * b_row[column]).sum()).collect())
+        .collect();
+    Ok(to_value(result))
+}
+
+fn transpose(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    let rows = matrix("transpose", &values[0])?;
+    Ok(to_value((0..rows[0].len()).map(|column| rows.iter().map(|row| row[column]).collect()).collect()))
+}
+
+/// Finds the determinant by reducing the matrix to an upper triangular one
+fn det(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    let mut rows = square_matrix("det", &values[0])?;
+    let size = rows.len();
+    let mut determinant = 1.0;
+    for column in 0..size {
+        // The largest pivot keeps the rounding errors small
+        let pivot = (column..size).max_by(|a, b| rows[*a][column].abs().total_cmp(&rows[*b][column].abs())).unwrap();
+        if rows[pivot][column].abs() < EPSILON {
+            return Ok(Value::Number(0.0));
+        }
+        if pivot != column {
+            rows.swap(pivot, column);
+            determinant = -determinant;
+        }
+        determinant *= rows[column][column];
+        let pivot_row = rows[column].clone();
+        for row in rows.iter_mut().skip(column + 1) {
+            let factor = row[column] / pivot_row[column];
+            for (value, pivot) in row.iter_mut().zip(&pivot_row).skip(column) {
+                *value -= factor * pivot;
+            }
+        }
+    }
+    Ok(Value::Number(determinant))
+}
+
+/// Inverts a matrix with Gauss-Jordan elimination
+fn inv(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    let mut rows = square_matrix("inv", &values[0])?;
+    let size = rows.len();
+    let mut inverse = (0..size).map(|row| (0..size).map(|column| if row == column { 1.0 } else { 0.0 }).collect::<Vec<_>>()).collect::<Vec<_>>();
+    for column in 0..size {
+        let pivot = (column..size).max_by(|a, b| rows[*a][column].abs().total_cmp(&rows[*b][column].abs())).unwrap();
+        if rows[pivot][column].abs() < EPSILON {
+            return Err("Cannot invert a singular matrix, its determinant is 0!".to_string());
+        }
+        rows.swap(pivot, column);
+        inverse.swap(pivot, column);
+
+        let divisor = rows[column][column];
+        for i in 0..size {
+            rows[column][i] /= divisor;
+            inverse[column][i] /= divisor;
+        }
+        for row in 0..size {
+            if row != column {
+                let factor = rows[row][column];
+                for i in 0..size {
+                    rows[row][i] -= factor * rows[column][i];
+                    inverse[row][i] -= factor * inverse[column][i];
+                }
+            }
+        }
+    }
+    Ok(to_value(inverse))
+}
+
+/// Solves the linear system `A x = b` for `x` with Gaussian elimination, where `A` is a square matrix
+fn linsolve(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    let mut rows = square_matrix("linsolve", &values[0])?;
+    let mut b = vector("linsolve", &values[1])?;
+    let size = rows.len();
+    if b.len() != size {
+        return Err(format!("Cannot solve a system of {size} equations with {} values on the right hand side!", b.len()));
+    }
+    for column in 0..size {
+        let pivot = (column..size).max_by(|a, b| rows[*a][column].abs().total_cmp(&rows[*b][column].abs())).unwrap();
+        if rows[pivot][column].abs() < EPSILON {
+            return Err("The system does not have exactly one solution, its matrix is singular!".to_string());
+        }
+        rows.swap(pivot, column);
+        b.swap(pivot, column);
+        let b_pivot = b[column];
+        let pivot_row = rows[column].clone();
+        for (row, value) in rows.iter_mut().zip(b.iter_mut()).skip(column + 1) {
+            let factor = row[column] / pivot_row[column];
+            for (entry, pivot) in row.iter_mut().zip(&pivot_row).skip(column) {
+                *entry -= factor * pivot;
+            }
+            *value -= factor * b_pivot;
+        }
+    }
+
+    // Back substitution, from the last row up
+    let mut x = vec![0.0; size];
+    for row in (0..size).rev() {
+        let known = (row + 1..size).map(|i| rows[row][i] * x[i]).sum::<f64>();
+        x[row] = (b[row] - known) / rows[row][row];
+    }
+    Ok(Value::Array(x.into_iter().map(Value::Number).collect()))
+}
+
+fn identity(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    let size = expect_position("identity", &values[0])?;
+    Ok(to_value((0..size).map(|row| (0..size).map(|column| if row == column { 1.0 } else { 0.0 }).collect()).collect()))
+}
\ No newline at end of file
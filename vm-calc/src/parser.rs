@@ -1,12 +1,33 @@
 
-use std::{collections::HashMap, rc::Rc};
-use crate::{ast::{Operator, Tree, AST}, errors::Error, functions::get_function, instruction::Symbol, lexer::Lexer, tokens::{NumberType, Token, TokenType}, utils::Span};
+use std::{collections::HashMap, num::ParseIntError, rc::Rc};
+use crate::{ast::{MatchArm, Operator, Pattern, SliceBounds, Tree, AST}, errors::Error, format::FormatSpec, instruction::{Symbol, Value}, lexer::Lexer, signatures::{get_builtin_arguments, get_constant}, symbolic, tokens::{NumberType, Token, TokenType, KEYWORDS}, utils::Span};
 
 pub struct Parser<'a> {
     lexer: Lexer<'a>,
-    token: Token,
+    pub(crate) token: Token,
     pub(crate) eof: bool,
     pub(crate) symbols: HashMap<Symbol<'a>, usize>,
+    /// Where each symbol has been declared, in the order of declaration
+    pub(crate) definitions: Vec<Definition<'a>>,
+    /// Within the equations of `solve`, `=` separates the two sides rather than assigning
+    equation: bool,
+}
+
+/// The arguments of a function or a lambda, with the default values of the last ones and the rest argument
+struct Parameters<'a> {
+    arguments: Vec<&'a str>,
+    spans: Vec<Span>,
+    defaults: Vec<Rc<Tree<'a>>>,
+    rest: Option<(&'a str, Span)>,
+}
+
+/// The declaration of a variable, function or function argument
+#[derive(Debug, Clone, PartialEq)]
+pub struct Definition<'a> {
+    pub(crate) symbol: Symbol<'a>,
+    pub(crate) span: Span,
+    /// The part of the code the declaration is limited to, which is the body of the function for arguments
+    pub(crate) scope: Option<Span>,
 }
 macro_rules! create_fn {
     ($self: ident, $below_fn: ident, $token_type: pat) => {{
@@ -15,7 +36,7 @@ macro_rules! create_fn {
             let operator = $self.token.token_type.clone().into();
             $self.increment()?;
             if $self.eof {
-                return Err(Error::PError { 
+                return Err(Error::PUnexpectedEOF { 
                     message: format!("Expected an expression after the `{}` operator, but found nothing!", operator), 
                     span: $self.token.span
                 });
@@ -43,6 +64,8 @@ impl<'a> Parser<'a> {
             lexer,
             eof: false,
             symbols: HashMap::new(),
+            definitions: vec![],
+            equation: false,
         }
     }
 
@@ -52,6 +75,8 @@ impl<'a> Parser<'a> {
             lexer,
             eof: false,
             symbols,
+            definitions: vec![],
+            equation: false,
         }
     }
 
@@ -105,13 +130,15 @@ impl<'a> Parser<'a> {
     }
 
     fn expression(&mut self, repl: bool) -> Result<Rc<Tree<'a>>, Error> {
-        let mut result = self.final_stage()?;
+        // An import can only be a statement of its own, and it has nothing to show in the repl
+        let import = self.token.token_type == TokenType::Identifier && &self.lexer.source[self.token.span.as_range()] == "import";
+        let mut result = if import { self.import()? } else { self.final_stage()? };
         match &self.token.token_type {
 
             TokenType::Semicolon => {
                 self.increment()?;
                 // Show output, for all terminators in the repl
-                if repl {
+                if repl && !import {
                     let span = result.span;
                     result = Rc::new(Tree::new(AST::Output { value: result }, span));    
                 }
@@ -132,6 +159,8 @@ impl<'a> Parser<'a> {
                     });
             },
 
+            _ if import => (),
+
             _ => {
                 let span = result.span;
                 result = Rc::new(Tree::new(AST::Output { value: result }, span));
@@ -140,8 +169,191 @@ impl<'a> Parser<'a> {
         Ok(result)
     }
 
+    /// `import "path"` or `import "path" as name`, starting at the `import` keyword
+    fn import(&mut self) -> Result<Rc<Tree<'a>>, Error> {
+        let start = self.token.span.start;
+        self.increment()?;
+        self.expect(TokenType::String)?;
+        let span = self.token.span;
+        let contents = Self::unescape(&self.lexer.source[(span.start + 1)..(span.end - 1)], span)?;
+        let path = Rc::new(Tree::new(AST::String { contents }, span));
+        self.increment()?;
+
+        let mut alias = None;
+        let mut end = span.end;
+        if self.token.token_type == TokenType::Identifier && &self.lexer.source[self.token.span.as_range()] == "as" {
+            self.increment()?;
+            self.expect(TokenType::Identifier)?;
+            let alias_span = self.token.span;
+            let name = &self.lexer.source[alias_span.as_range()];
+            Self::not_constant(name, alias_span)?;
+            self.increment()?;
+
+            self.symbols.entry(Symbol::Variable(name)).or_insert(0);
+            self.definitions.push(Definition { symbol: Symbol::Variable(name), span: alias_span, scope: None });
+            alias = Some(name);
+            end = alias_span.end;
+        }
+
+        Ok(Rc::new(
+            Tree::new(
+                AST::Import { path, alias },
+                Span::new(start, end)
+            )
+        ))
+    }
+
+    /// Whether `solve` starts an equation to solve rather than a call of the builtin function, which is the case
+    /// when `for` comes before the end of the expression
+    fn solve_ahead(&self) -> bool {
+        let mut lexer = self.lexer.clone();
+        let mut token = self.token.clone();
+        let mut depth = 0;
+        loop {
+            match token.token_type {
+                TokenType::OpeningBracket | TokenType::OpenArray | TokenType::OpenMap => depth += 1,
+                TokenType::ClosingBracket | TokenType::CloseArray | TokenType::CloseMap if depth == 0 => return false,
+                TokenType::ClosingBracket | TokenType::CloseArray | TokenType::CloseMap => depth -= 1,
+                TokenType::Semicolon | TokenType::Colon | TokenType::EOF if depth == 0 => return false,
+                TokenType::Identifier if depth == 0 && &self.lexer.source[token.span.as_range()] == "for" => return true,
+                _ => (),
+            }
+            token = match lexer.next() {
+                Ok(token) => token,
+                Err(..) => return false,
+            };
+        }
+    }
+
+    /// `solve lhs = rhs for x`, which gives the roots of a polynomial, or `solve [lhs = rhs, ...] for [x, ...]` for a
+    /// system of linear equations. The equations are turned into a call of `roots` or `linsolve` when parsing,
+    /// starting after the `solve` keyword
+    fn solve(&mut self, start: usize) -> Result<Rc<Tree<'a>>, Error> {
+        let system = self.token.token_type == TokenType::OpenArray;
+        let mut equations = vec![];
+        if system {
+            self.increment()?;
+            while self.token.token_type != TokenType::CloseArray {
+                equations.push(self.equation()?);
+                if self.token.token_type == TokenType::CloseArray {
+                    break;
+                }
+                self.expect(TokenType::Comma)?;
+                self.increment()?;
+            }
+            self.increment()?;
+        } else {
+            equations.push(self.equation()?);
+        }
+
+        // `for` was found ahead, so it comes next unless the equations are followed by something else
+        if self.token.token_type != TokenType::Identifier || &self.lexer.source[self.token.span.as_range()] != "for" {
+            return Err(Error::PError { 
+                message: format!("Expected `for` and the name to solve for after the equation, but found `{}`!", &self.lexer.source[self.token.span.as_range()]), 
+                span: self.token.span,
+            });
+        }
+        self.increment()?;
+        let mut variables = vec![];
+        if system {
+            self.expect(TokenType::OpenArray)?;
+            self.increment()?;
+            while self.token.token_type != TokenType::CloseArray {
+                variables.push(self.unknown(&variables)?);
+                if self.token.token_type == TokenType::CloseArray {
+                    break;
+                }
+                self.expect(TokenType::Comma)?;
+                self.increment()?;
+            }
+        } else {
+            variables.push(self.unknown(&variables)?);
+        }
+        let end = self.token.span.end;
+        if system {
+            self.increment()?;
+        }
+        let span = Span::new(start, end);
+
+        // Everything is moved to the left hand side, so that the equations are `lhs - rhs = 0`
+        let sides = equations.iter().map(|(lhs, rhs)| {
+            let span = Span::new(lhs.span.start, rhs.span.end);
+            (Rc::new(Tree::new(AST::BinaryOp { lhs: lhs.clone(), rhs: rhs.clone(), op: Operator::Minus }, span)), span)
+        }).collect::<Vec<_>>();
+        let node = |ast| Rc::new(Tree::new(ast, span));
+        let call = |name, expressions| node(AST::FunctionCall { name: node(AST::Name { value: name }), expressions });
+        let result = if system {
+            if equations.len() != variables.len() {
+                return Err(Error::PError { 
+                    message: format!("Cannot solve {} equation(s) for {} name(s), there should be as many equations as names!", equations.len(), variables.len()), 
+                    span,
+                });
+            }
+            let mut rows = vec![];
+            let mut constants = vec![];
+            for (side, span) in &sides {
+                let mut coefficients = symbolic::linear(side, &variables).map_err(|message| Error::PError { message, span: *span })?;
+                let constant = coefficients.pop().unwrap();
+                constants.push(symbolic::simplify(&node(AST::UnaryOp { rhs: constant, op: Operator::Minus })));
+                rows.push(node(AST::Array { expressions: coefficients }));
+            }
+            call("linsolve", vec![node(AST::Array { expressions: rows }), node(AST::Array { expressions: constants })])
+        } else {
+            let (side, span) = &sides[0];
+            let mut coefficients = symbolic::polynomial(side, variables[0]).map_err(|message| Error::PError { message, span: *span })?;
+            coefficients.reverse();
+            call("roots", vec![node(AST::Array { expressions: coefficients })])
+        };
+
+        Ok(Rc::new(
+            Tree::new(
+                AST::Solve { equations, variables, system, result },
+                span
+            )
+        ))
+    }
+
+    /// One equation of `solve`, `lhs = rhs`
+    fn equation(&mut self) -> Result<(Rc<Tree<'a>>, Rc<Tree<'a>>), Error> {
+        let equation = std::mem::replace(&mut self.equation, true);
+        let sides = self.equation_sides();
+        self.equation = equation;
+        sides
+    }
+
+    fn equation_sides(&mut self) -> Result<(Rc<Tree<'a>>, Rc<Tree<'a>>), Error> {
+        let lhs = self.final_stage()?;
+        self.expect(TokenType::Equal)?;
+        self.increment()?;
+        if self.eof {
+            return Err(Error::PUnexpectedEOF { 
+                message: "Unexpected end of file in an equation! Help: Provide an expression to the right of the `=` symbol".to_string(), 
+                span: Span::new(lhs.span.start, self.token.span.end),
+            });
+        }
+        let rhs = self.final_stage()?;
+        Ok((lhs, rhs))
+    }
+
+    /// A name that `solve` solves for, which is not one of the names before it
+    fn unknown(&mut self, variables: &[&'a str]) -> Result<&'a str, Error> {
+        self.expect(TokenType::Identifier)?;
+        let span = self.token.span;
+        let name = &self.lexer.source[span.as_range()];
+        Self::not_constant(name, span)?;
+        if variables.contains(&name) {
+            return Err(Error::PError { message: format!("The name `{name}` is solved for more than once!"), span });
+        }
+        self.increment()?;
+        Ok(name)
+    }
+
     fn final_stage(&mut self) -> Result<Rc<Tree<'a>>, Error> {
-        self.bitor()
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Rc<Tree<'a>>, Error> {
+        create_fn!(self, bitor, TokenType::IsEqual | TokenType::NotEqual | TokenType::Less | TokenType::LessEqual | TokenType::Greater | TokenType::GreaterEqual)
     }
 
     fn bitor(&mut self) -> Result<Rc<Tree<'a>>, Error> {
@@ -179,10 +391,10 @@ impl<'a> Parser<'a> {
                 let operator = self.token.token_type.clone().into();
                 self.increment()?;
                 if self.eof {
-                    return Err(Error::PError { 
+                    Err(Error::PUnexpectedEOF { 
                         message: format!("Expected an expression after the `{}` operator, but found nothing!", operator), 
                         span: self.token.span
-                    });
+                    })
                 } else {
                     let rhs = self.unary()?;
                     let end = rhs.span.end;
@@ -210,42 +422,147 @@ impl<'a> Parser<'a> {
         }
 
         let mut expressions = vec![];
+        // The bounds of a slice, along with whether it is the last of the brackets
+        let mut slice = None;
+
+        while matches!(self.token.token_type, TokenType::OpenArray | TokenType::Dot | TokenType::OpeningBracket) {
+            // Calling a function that was indexed, like `module.function(1)`. The result cannot be assigned to
+            if self.token.token_type == TokenType::OpeningBracket {
+                result = self.call(start, result)?;
+                name = None;
+                continue;
+            }
+
+            // A field, `map.key`, is the same as indexing by the string `map["key"]`
+            if self.token.token_type == TokenType::Dot {
+                self.increment()?;
+                let field = self.token.span;
+                if self.token.token_type != TokenType::Identifier {
+                    let message = format!("Expected the name of a field after `.`{}!", 
+                        if self.eof { " But found an unexpected end of file!".to_string() } 
+                        else { format!(" But found an unexpected token `{}`!", self.token.token_type) }
+                    );
+                    if self.eof {
+                        return Err(Error::PUnexpectedEOF { message, span: field });
+                    }
+                    return Err(Error::PError { message, span: field });
+                }
+                self.increment()?;
+
+                if let Some((_, last)) = slice.as_mut() {
+                    *last = false;
+                }
+                let expression = Rc::new(Tree::new(AST::String { contents: self.lexer.source[field.as_range()].to_string() }, field));
+                expressions.push(expression.clone());
+                result = Rc::new(
+                    Tree::new(
+                        AST::Index { to_index: result, expression },
+                        Span::new(start, field.end)
+                    )
+                );
+                continue;
+            }
 
-        while self.token.token_type == TokenType::OpenArray {
             self.increment()?;
             let expr_start = self.token.span.start;
-            let expression = self.final_stage()?;
-            if self.token.token_type != TokenType::CloseArray {
-                return Err(Error::PError { 
-                    message: 
-                        format!("Expected a closing index bracket `]`{}!", 
-                            if self.eof { format!(" But found an unexpected end of file!") } 
-                            else { format!(" But found an unexpected token `{}`!", self.token.token_type) }
-                        ), 
+            if self.eof {
+                return Err(Error::PUnexpectedEOF { 
+                    message: "Expected an index after `[`, but found an unexpected end of file!".to_string(), 
                     span: Span::new(expr_start, self.token.span.end),
                 });
             }
+            // The start of a slice can be left out, like `[:2]`
+            let expression = if self.token.token_type == TokenType::Colon { None } else { Some(self.final_stage()?) };
+            let bounds = if self.token.token_type == TokenType::Colon {
+                self.increment()?;
+                let end = self.slice_bound()?;
+                let step = if self.token.token_type == TokenType::Colon {
+                    self.increment()?;
+                    self.slice_bound()?
+                } else {
+                    None
+                };
+                Some(SliceBounds { start: expression.clone(), end, step })
+            } else {
+                None
+            };
+
+            if self.token.token_type != TokenType::CloseArray {
+                let message = format!("Expected a closing index bracket `]`{}!", 
+                    if self.eof { format!(" But found an unexpected end of file!") } 
+                    else { format!(" But found an unexpected token `{}`!", self.token.token_type) }
+                );
+                let span = Span::new(expr_start, self.token.span.end);
+                if self.eof {
+                    return Err(Error::PUnexpectedEOF { message, span });
+                }
+                return Err(Error::PError { message, span });
+            }
 
             self.increment()?;
             let end = self.token.span.end - 1;
 
-            expressions.push(expression.clone());
+            match (bounds, expression) {
+                (Some(bounds), _) => {
+                    if let Some((_, last)) = slice.as_mut() {
+                        *last = false;
+                    }
+                    slice = Some((bounds.clone(), true));
+                    result = Rc::new(
+                        Tree::new(
+                            AST::Slice { to_slice: result, bounds },
+                            Span::new(start, end)
+                        )
+                    );
+                }
+
+                (None, Some(expression)) => {
+                    if let Some((_, last)) = slice.as_mut() {
+                        *last = false;
+                    }
+                    expressions.push(expression.clone());
+                    result = Rc::new(
+                        Tree::new(
+                            AST::Index { to_index: result, expression },
+                            Span::new(start, end)
+                        )
+                    );
+                }
 
-            result = Rc::new(
-                Tree::new(
-                    AST::Index { to_index: result, expression },
-                    Span::new(start, end)
-                )
-            );
+                (None, None) => unreachable!("An index without an expression is always a slice"),
+            }
+        }
+
+        // Assigning to an array of names, like `[a, b] = [b, a]`
+        if self.token.token_type == TokenType::Equal && expressions.is_empty() && slice.is_none() && !self.equation {
+            if let Some(pattern) = Self::assignment_pattern(&result) {
+                Self::assigned_constants(&result)?;
+                self.increment()?;
+                if self.eof {
+                    return Err(Error::PUnexpectedEOF { 
+                        message: "Unexpected end of file while assigning to variables! Help: Provide an expression to the right of the `=` symbol".to_string(), 
+                        span: Span::new(start, self.token.span.end),
+                    });
+                }
+                let value = self.final_stage()?;
+                let end = value.span.end;
+                return Ok(Rc::new(
+                    Tree::new(
+                        AST::AssignPattern { pattern, value },
+                        Span::new(start, end)
+                    )
+                ));
+            }
         }
 
         let token_type = self.token.token_type.clone();
+        let equation = self.equation;
 
         let operator = |operator| {
             self.increment()?;
 
             if self.eof {
-                return Err(Error::PError { 
+                return Err(Error::PUnexpectedEOF { 
                     message: format!("Unexpected end of file while performing `{operator}` on a variable! Help: Provide an expression to the right of the `{operator}` symbol"), 
                     span: Span::new(start, self.token.span.end),
                 });
@@ -254,18 +571,30 @@ impl<'a> Parser<'a> {
             let result = self.final_stage()?;
             let end = result.span.end;
 
+            let slice = match slice {
+                Some((bounds, true)) => Some(bounds),
+                Some((_, false)) => {
+                    return Err(Error::PError { 
+                        message: "A slice can only be assigned to when it is the last index!".to_string(), 
+                        span: Span::new(start, end),
+                    });
+                }
+                None => None,
+            };
+
             match name {
                 Some(name) => {
-                    return Ok(Rc::new(
+                    Self::not_constant(name, Span::new(start, end))?;
+                    Ok(Rc::new(
                         Tree::new(
-                            AST::AssignIndex { identifier: name, indicies: expressions, value: result, operator },
+                            AST::AssignIndex { identifier: name, indicies: expressions, value: result, operator, slice },
                             Span::new(start, end)
                         )
-                    ));
+                    ))
                 },
 
                 None =>
-                    return Err(
+                    Err(
                         Error::PError { 
                             message: format!("Cannot assign values to temporary arrays / non-arrays!"), 
                             span: Span::new(start, end) 
@@ -276,7 +605,7 @@ impl<'a> Parser<'a> {
         };
 
         match token_type {
-            TokenType::Equal => return operator(Operator::Equal),
+            TokenType::Equal if !equation => return operator(Operator::Equal),
             TokenType::AddEqual => return operator(Operator::PlusEqual),
             TokenType::SubtractEqual => return operator(Operator::MinusEqual),
             TokenType::MultiplyEqual => return operator(Operator::MultiplyEqual),
@@ -294,46 +623,555 @@ impl<'a> Parser<'a> {
         Ok(result)
     }
 
-    fn partial(&mut self) -> Result<Rc<Tree<'a>>, Error> {
-        let start = self.token.span.start;
-        let mut result = self.base()?;
+    /// The end or the step of a slice, which can be left out
+    fn slice_bound(&mut self) -> Result<Option<Rc<Tree<'a>>>, Error> {
+        if matches!(self.token.token_type, TokenType::Colon | TokenType::CloseArray) {
+            return Ok(None);
+        }
+        Ok(Some(self.final_stage()?))
+    }
 
-        while self.token.token_type == TokenType::OpeningBracket {
+    /// `match value { pattern if guard => body, ... }`, after the `match` keyword
+    fn match_expression(&mut self, start: usize) -> Result<Rc<Tree<'a>>, Error> {
+        if self.eof {
+            return Err(Error::PUnexpectedEOF { 
+                message: "Unexpected end of file after `match`! Help: Provide a value followed by the arms of the match within `{}`".to_string(), 
+                span: Span::new(start, self.token.span.end),
+            });
+        }
+        let value = self.final_stage()?;
+        self.expect(TokenType::OpenMap)?;
+        self.increment()?;
+
+        let mut arms = vec![];
+        while self.token.token_type != TokenType::CloseMap {
+            if self.eof {
+                return Err(Error::PUnexpectedEOF { 
+                    message: "Unexpected end of file within a match! Did you mean to type `}` to close the match?".to_string(), 
+                    span: Span::new(start, self.token.span.end),
+                });
+            }
+
+            let arm_start = self.token.span.start;
+            let mut bindings = vec![];
+            let pattern = self.pattern(&mut bindings)?;
+
+            // The names bound by the pattern can only be used within the arm, the same as the arguments of a function
+            let old_symbols = self.symbols.clone();
+            for (name, _) in &bindings {
+                self.symbols.insert(Symbol::Variable(name), 0);
+            }
+            let arm = self.match_arm(pattern);
+            self.symbols = old_symbols;
+            arms.push(arm?);
+
+            let scope = Span::new(arm_start, self.token.span.start);
+            for (name, span) in bindings {
+                self.definitions.push(Definition { symbol: Symbol::Variable(name), span, scope: Some(scope) });
+            }
+
+            if self.token.token_type == TokenType::CloseMap {
+                break;
+            }
+
+            self.expect(TokenType::Comma)?;
             self.increment()?;
-            let expr_start = self.token.span.start;
-            let mut expressions = vec![];
-            
-            while self.token.token_type != TokenType::ClosingBracket {
-                
-                if self.eof {
+        }
+
+        let end = self.token.span.end;
+        if arms.is_empty() {
+            return Err(Error::PError { 
+                message: "A match needs at least one arm!".to_string(), 
+                span: Span::new(start, end),
+            });
+        }
+        self.increment()?;
+        Ok(Rc::new(
+            Tree::new(
+                AST::Match { value, arms },
+                Span::new(start, end)
+            )
+        ))
+    }
+
+    /// The optional guard and the body of an arm, after its pattern
+    fn match_arm(&mut self, pattern: Pattern<'a>) -> Result<MatchArm<'a>, Error> {
+        let mut guard = None;
+        if self.token.token_type == TokenType::Identifier && &self.lexer.source[self.token.span.as_range()] == "if" {
+            self.increment()?;
+            if self.eof {
+                return Err(Error::PUnexpectedEOF { 
+                    message: "Unexpected end of file after `if`! Help: Provide a condition for the arm".to_string(), 
+                    span: self.token.span,
+                });
+            }
+            guard = Some(self.final_stage()?);
+        }
+
+        self.expect(TokenType::FatArrow)?;
+        self.increment()?;
+        if self.eof {
+            return Err(Error::PUnexpectedEOF { 
+                message: "Unexpected end of file after `=>`! Help: Provide an expression for the arm".to_string(), 
+                span: self.token.span,
+            });
+        }
+        let body = self.final_stage()?;
+        Ok(MatchArm { pattern, guard, body })
+    }
+
+    /// An argument of a call, which is given by name when it is written as `name: value`.
+    /// The arguments given by name have to come after the others
+    fn argument(&mut self, arguments: &[Rc<Tree<'a>>]) -> Result<Rc<Tree<'a>>, Error> {
+        let expression = self.final_stage()?;
+        match expression.ast {
+            AST::Identifier { name } if self.token.token_type == TokenType::Colon => {
+                if arguments.iter().any(|argument| matches!(argument.ast, AST::NamedArgument { name: other, .. } if other == name)) {
                     return Err(Error::PError { 
-                        message: format!("Unexpected end of file while calling function! Did you mean to type `)` to close the function call?"), 
-                        span: Span::new(expr_start, self.token.span.end),
-                    })
+                        message: format!("The argument `{name}` was given more than once!"), 
+                        span: expression.span,
+                    });
                 }
+                self.increment()?;
+                if self.eof {
+                    return Err(Error::PUnexpectedEOF { 
+                        message: format!("Unexpected end of file after `{name}:`! Help: Provide a value for the argument"), 
+                        span: Span::new(expression.span.start, self.token.span.end),
+                    });
+                }
+                let value = self.final_stage()?;
+                let span = Span::new(expression.span.start, value.span.end);
+                Ok(Rc::new(Tree::new(AST::NamedArgument { name, value }, span)))
+            }
+
+            _ if arguments.last().is_some_and(|argument| matches!(argument.ast, AST::NamedArgument { .. })) => {
+                Err(Error::PError { 
+                    message: "An argument without a name cannot come after named arguments!".to_string(), 
+                    span: expression.span,
+                })
+            }
+
+            _ => Ok(expression),
+        }
+    }
 
-                expressions.push(self.final_stage()?);
+    /// The arguments and the body of a function declaration, after its name
+    fn function_body(&mut self, start: usize, name: &'a str, identifier_span: Span) -> Result<(Parameters<'a>, Rc<Tree<'a>>), Error> {
+        let parameters = self.parameters()?;
 
-                if self.token.token_type == TokenType::ClosingBracket {
+        if self.token.token_type == TokenType::Equal {
+            self.increment()?;
+            if self.eof {
+                return Err(Error::PUnexpectedEOF { 
+                    message: "Unexpected end of file while declaring function! Help: Provide an expression to the right of the `=` symbol".to_string(), 
+                    span: Span::new(start, self.token.span.end),
+                });
+            }
+            let body = self.final_stage()?;
+            Ok((parameters, body))
+        } else if self.eof {
+            Err(Error::PUnexpectedEOF { 
+                message: format!("Unexpected end of file while declaring function `{name}`! Help: Provide `=` and an expression for the function"), 
+                span: Span::new(start, self.token.span.end),
+            })
+        } else {
+            Err(Error::PError { 
+                message: format!("Expected an expression for the function `{name}`"), 
+                span: identifier_span,
+            })
+        }
+    }
+
+    /// The arguments of a function or a lambda, where the last ones can be given default values: `x (y = 2)`,
+    /// and a final rest argument collects any extra ones: `...xs`.
+    /// Each argument is added to the symbols as it is read, so a default value can use the arguments before it
+    fn parameters(&mut self) -> Result<Parameters<'a>, Error> {
+        let mut parameters = Parameters { arguments: vec![], spans: vec![], defaults: vec![], rest: None };
+        loop {
+            let span = match self.token.token_type {
+                TokenType::Identifier => {
+                    let span = self.token.span;
+                    if !parameters.defaults.is_empty() {
+                        return Err(Error::PError { 
+                            message: format!("The argument `{}` needs a default value, since it comes after an argument with one!", &self.lexer.source[span.as_range()]), 
+                            span,
+                        });
+                    }
+                    self.increment()?;
+                    span
+                }
+
+                // An argument with a default value: `(y = 2)`
+                TokenType::OpeningBracket => {
+                    self.increment()?;
+                    self.expect(TokenType::Identifier)?;
+                    let span = self.token.span;
+                    self.increment()?;
+                    self.expect(TokenType::Equal)?;
+                    self.increment()?;
+                    if self.eof {
+                        return Err(Error::PUnexpectedEOF { 
+                            message: format!("Unexpected end of file in the default value of the argument `{}`!", &self.lexer.source[span.as_range()]), 
+                            span: Span::new(span.start, self.token.span.end),
+                        });
+                    }
+                    parameters.defaults.push(self.final_stage()?);
+                    self.expect(TokenType::ClosingBracket)?;
+                    self.increment()?;
+                    span
+                }
+
+                // The rest argument: `...xs`
+                TokenType::Ellipsis => {
+                    self.increment()?;
+                    self.expect(TokenType::Identifier)?;
+                    let span = self.token.span;
+                    let name = &self.lexer.source[span.as_range()];
+                    Self::not_constant(name, span)?;
+                    self.increment()?;
+                    if matches!(self.token.token_type, TokenType::Identifier | TokenType::OpeningBracket | TokenType::Ellipsis) && !self.eof {
+                        return Err(Error::PError { 
+                            message: format!("The rest argument `{name}` must be the last argument!"), 
+                            span: self.token.span,
+                        });
+                    }
+                    self.symbols.insert(Symbol::Variable(name), 0);
+                    parameters.rest = Some((name, span));
                     break;
                 }
 
-                self.expect(TokenType::Comma)?;
+                _ => break,
+            };
+            let name = &self.lexer.source[span.as_range()];
+            Self::not_constant(name, span)?;
+            self.symbols.insert(Symbol::Variable(name), 0);
+            parameters.arguments.push(name);
+            parameters.spans.push(span);
+        }
+
+        if parameters.arguments.len() == 1 && parameters.arguments[0] == "_" && parameters.defaults.is_empty() && parameters.rest.is_none() {
+            parameters.arguments = vec![];
+        }
+        Ok(parameters)
+    }
+
+    /// An anonymous function like `\x y -> x + y`, starting after the `\`, or like `fn(x, y) => x + y` when it is `bracketed`,
+    /// starting after the `fn`
+    fn lambda(&mut self, start: usize, bracketed: bool) -> Result<Rc<Tree<'a>>, Error> {
+        // The arguments can only be used within the lambda, the same as those of a declared function
+        let old_symbols = self.symbols.clone();
+        let lambda = if bracketed { self.bracketed_lambda_body(start) } else { self.lambda_body(start) };
+        self.symbols = old_symbols;
+        let (Parameters { arguments, spans, defaults, rest }, body) = lambda?;
+
+        let scope = Span::new(spans.first().or(rest.as_ref().map(|(_, span)| span)).map_or(body.span.start, |span| span.start), self.token.span.start);
+        for (argument, span) in arguments.iter().copied().zip(spans).chain(rest) {
+            self.definitions.push(Definition { symbol: Symbol::Variable(argument), span, scope: Some(scope) });
+        }
+
+        let end = body.span.end;
+        let rest = rest.map(|(name, _)| name);
+        Ok(Rc::new(
+            Tree::new(
+                AST::Lambda { arguments, defaults, rest, body },
+                Span::new(start, end)
+            )
+        ))
+    }
+
+    /// The arguments and the body of a lambda, after the `\`
+    fn lambda_body(&mut self, start: usize) -> Result<(Parameters<'a>, Rc<Tree<'a>>), Error> {
+        let parameters = self.parameters()?;
+        self.expect(TokenType::Arrow)?;
+        self.increment()?;
+        if self.eof {
+            return Err(Error::PUnexpectedEOF { 
+                message: "Unexpected end of file while creating a lambda! Help: Provide an expression to the right of the `->` symbol".to_string(), 
+                span: Span::new(start, self.token.span.end),
+            });
+        }
+        let body = self.final_stage()?;
+        Ok((parameters, body))
+    }
+
+    /// The arguments and the body of a lambda like `fn(x, y = 2, ...rest) => x + y`, after the `fn`.
+    /// The arguments are separated by commas as in a call, and their default values follow an `=`
+    fn bracketed_lambda_body(&mut self, start: usize) -> Result<(Parameters<'a>, Rc<Tree<'a>>), Error> {
+        self.expect(TokenType::OpeningBracket)?;
+        self.increment()?;
+        let mut parameters = Parameters { arguments: vec![], spans: vec![], defaults: vec![], rest: None };
+        while self.token.token_type != TokenType::ClosingBracket {
+            if self.eof {
+                return Err(Error::PUnexpectedEOF { 
+                    message: "Unexpected end of file in the arguments of a lambda! Did you mean to type `)` to close them?".to_string(), 
+                    span: Span::new(start, self.token.span.end),
+                });
+            }
+            if let Some((name, _)) = parameters.rest {
+                return Err(Error::PError { message: format!("The rest argument `{name}` must be the last argument!"), span: self.token.span });
+            }
+
+            let rest = self.token.token_type == TokenType::Ellipsis;
+            if rest {
                 self.increment()?;
             }
+            self.expect(TokenType::Identifier)?;
+            let span = self.token.span;
+            let name = &self.lexer.source[span.as_range()];
+            Self::not_constant(name, span)?;
             self.increment()?;
-            let end = self.token.span.end - 1;
-            result = Rc::new(
-                Tree::new(
-                    AST::FunctionCall{ name: result, expressions },
-                    Span::new(start, end)
-                )
-            );
+
+            if rest {
+                parameters.rest = Some((name, span));
+            } else {
+                if self.token.token_type == TokenType::Equal {
+                    self.increment()?;
+                    if self.eof {
+                        return Err(Error::PUnexpectedEOF { 
+                            message: format!("Unexpected end of file in the default value of the argument `{name}`!"), 
+                            span: Span::new(span.start, self.token.span.end),
+                        });
+                    }
+                    parameters.defaults.push(self.final_stage()?);
+                } else if !parameters.defaults.is_empty() {
+                    return Err(Error::PError { 
+                        message: format!("The argument `{name}` needs a default value, since it comes after an argument with one!"), 
+                        span,
+                    });
+                }
+                parameters.arguments.push(name);
+                parameters.spans.push(span);
+            }
+            // Added after its default value, so that the default value can only use the arguments before it
+            self.symbols.insert(Symbol::Variable(name), 0);
+
+            if self.token.token_type == TokenType::ClosingBracket {
+                break;
+            }
+            self.expect(TokenType::Comma)?;
+            self.increment()?;
+        }
+        self.increment()?;
+
+        self.expect(TokenType::FatArrow)?;
+        self.increment()?;
+        if self.eof {
+            return Err(Error::PUnexpectedEOF { 
+                message: "Unexpected end of file while creating a lambda! Help: Provide an expression to the right of the `=>` symbol".to_string(), 
+                span: Span::new(start, self.token.span.end),
+            });
+        }
+        let body = self.final_stage()?;
+        Ok((parameters, body))
+    }
+
+    /// `let [a, [b, c]] = value`, after the `let` keyword
+    fn declare_pattern(&mut self, start: usize) -> Result<Rc<Tree<'a>>, Error> {
+        let pattern_start = self.token.span.start;
+        let mut bindings = vec![];
+        let pattern = self.pattern(&mut bindings)?;
+        if !pattern.is_destructuring() {
+            return Err(Error::PError { 
+                message: "Only names, `_` and arrays of them can be declared by `let`!".to_string(), 
+                span: Span::new(pattern_start, self.token.span.start),
+            });
+        }
+
+        self.expect(TokenType::Equal)?;
+        self.increment()?;
+        if self.eof {
+            return Err(Error::PUnexpectedEOF { 
+                message: "Unexpected end of file while declaring variables! Help: Provide an expression to the right of the `=` symbol".to_string(), 
+                span: Span::new(start, self.token.span.end),
+            });
+        }
+        let value = self.final_stage()?;
+        let end = value.span.end;
+
+        for (name, span) in bindings {
+            self.symbols.entry(Symbol::Variable(name)).or_insert(0);
+            self.definitions.push(Definition { symbol: Symbol::Variable(name), span, scope: None });
+        }
+
+        Ok(Rc::new(
+            Tree::new(
+                AST::DeclarePattern { pattern, value },
+                Span::new(start, end)
+            )
+        ))
+    }
+
+    /// Reads an array like `[a, [b, _]]` as a pattern to assign to, giving `None` when it has anything other than names
+    fn assignment_pattern(tree: &Tree<'a>) -> Option<Pattern<'a>> {
+        match tree.ast {
+            AST::Identifier { name: "_" } => Some(Pattern::Wildcard),
+            AST::Identifier { name } => Some(Pattern::Binding { name }),
+            AST::Array { ref expressions } => {
+                let patterns = expressions.iter().map(|expression| Self::assignment_pattern(expression)).collect::<Option<_>>()?;
+                Some(Pattern::Array { patterns })
+            }
+            _ => None,
+        }
+    }
+
+    /// Checks that none of the names in an array that is assigned to are constants
+    fn assigned_constants(tree: &Tree<'a>) -> Result<(), Error> {
+        match &tree.ast {
+            AST::Identifier { name } => Self::not_constant(name, tree.span),
+            AST::Array { expressions } => expressions.iter().try_for_each(|expression| Self::assigned_constants(expression)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Constants like `pi` are protected the same way as the builtin functions, so they cannot be declared or assigned to
+    fn not_constant(name: &str, span: Span) -> Result<(), Error> {
+        if get_constant(name).is_ok() {
+            return Err(Error::PError { message: format!("The constant `{name}` cannot be assigned to!"), span });
+        }
+        Ok(())
+    }
+
+    /// A pattern of a match arm. The names it binds are added to the bindings along with their spans
+    fn pattern(&mut self, bindings: &mut Vec<(&'a str, Span)>) -> Result<Pattern<'a>, Error> {
+        let span = self.token.span;
+        let pattern = match self.token.token_type {
+            TokenType::Identifier => {
+                let name = &self.lexer.source[span.as_range()];
+                self.increment()?;
+                match name {
+                    "_" => Pattern::Wildcard,
+                    "Null" => Pattern::Literal { value: Value::Null },
+                    _ if KEYWORDS.contains(&name) => {
+                        return Err(Error::PError { message: format!("The keyword `{name}` cannot be used as a name in a pattern!"), span });
+                    }
+                    _ if bindings.iter().any(|(bound, _)| *bound == name) => {
+                        return Err(Error::PError { message: format!("The name `{name}` is bound more than once in the pattern!"), span });
+                    }
+                    _ => {
+                        Self::not_constant(name, span)?;
+                        bindings.push((name, span));
+                        Pattern::Binding { name }
+                    }
+                }
+            }
+
+            // Numbers can be negative, so they are read along with their sign
+            TokenType::Number { .. } | TokenType::String | TokenType::Subtract => {
+                let literal = self.unary()?;
+                let value = match &literal.ast {
+                    AST::Number { value } => Some(Value::Number(*value)),
+                    AST::String { contents } => Some(Value::String(contents.clone())),
+                    AST::UnaryOp { rhs, op: Operator::Minus } => match rhs.ast {
+                        AST::Number { value } => Some(Value::Number(-value)),
+                        _ => None,
+                    },
+                    _ => None,
+                };
+                match value {
+                    Some(value) => Pattern::Literal { value },
+                    None => return Err(Error::PError { 
+                        message: "Only numbers, strings and `Null` can be used as values in a pattern!".to_string(), 
+                        span: literal.span,
+                    }),
+                }
+            }
+
+            TokenType::OpenArray => {
+                self.increment()?;
+                let mut patterns = vec![];
+                while self.token.token_type != TokenType::CloseArray {
+                    if self.eof {
+                        return Err(Error::PUnexpectedEOF { 
+                            message: "Unexpected end of file within an array pattern! Did you mean to type `]` to close it?".to_string(), 
+                            span: Span::new(span.start, self.token.span.end),
+                        });
+                    }
+
+                    patterns.push(self.pattern(bindings)?);
+
+                    if self.token.token_type == TokenType::CloseArray {
+                        break;
+                    }
+
+                    self.expect(TokenType::Comma)?;
+                    self.increment()?;
+                }
+                self.increment()?;
+                Pattern::Array { patterns }
+            }
+
+            _ if self.eof => {
+                return Err(Error::PUnexpectedEOF { message: "Expected a pattern but found an unexpected end of file!".to_string(), span });
+            }
+
+            ref token_type => {
+                return Err(Error::PError { message: format!("Expected a pattern but found `{token_type}`!"), span });
+            }
+        };
+
+        // Any pattern can be limited to a type, like `n: Number`
+        if self.token.token_type != TokenType::Colon {
+            return Ok(pattern);
+        }
+        self.increment()?;
+        let type_span = self.token.span;
+        let type_name = &self.lexer.source[type_span.as_range()];
+        if self.token.token_type != TokenType::Identifier || !Pattern::TYPES.contains(&type_name) {
+            let message = format!("Expected a type after `:` in the pattern, which is one of: {}!", Pattern::TYPES.join(", "));
+            if self.eof {
+                return Err(Error::PUnexpectedEOF { message, span: type_span });
+            }
+            return Err(Error::PError { message, span: type_span });
+        }
+        self.increment()?;
+        Ok(Pattern::Type { pattern: Box::new(pattern), type_name })
+    }
+
+    fn partial(&mut self) -> Result<Rc<Tree<'a>>, Error> {
+        let start = self.token.span.start;
+        let mut result = self.base()?;
+
+        while self.token.token_type == TokenType::OpeningBracket {
+            result = self.call(start, result)?;
         }
 
         Ok(result)
     }
 
+    /// The arguments of a call to the function, starting at the `(`
+    fn call(&mut self, start: usize, function: Rc<Tree<'a>>) -> Result<Rc<Tree<'a>>, Error> {
+        self.increment()?;
+        let expr_start = self.token.span.start;
+        let mut expressions = vec![];
+        
+        while self.token.token_type != TokenType::ClosingBracket {
+            
+            if self.eof {
+                return Err(Error::PUnexpectedEOF { 
+                    message: format!("Unexpected end of file while calling function! Did you mean to type `)` to close the function call?"), 
+                    span: Span::new(expr_start, self.token.span.end),
+                })
+            }
+
+            expressions.push(self.argument(&expressions)?);
+
+            if self.token.token_type == TokenType::ClosingBracket {
+                break;
+            }
+
+            self.expect(TokenType::Comma)?;
+            self.increment()?;
+        }
+        self.increment()?;
+        let end = self.token.span.end - 1;
+        Ok(Rc::new(
+            Tree::new(
+                AST::FunctionCall{ name: function, expressions },
+                Span::new(start, end)
+            )
+        ))
+    }
+
     fn base(&mut self) -> Result<Rc<Tree<'a>>, Error> {
         let span = self.token.span;
         match self.token.token_type.clone() {
@@ -373,9 +1211,13 @@ impl<'a> Parser<'a> {
                 let ident_end = self.token.span.end;
                 self.increment()?;
                 if name == "let" {
+                    if self.token.token_type == TokenType::OpenArray {
+                        return self.declare_pattern(start);
+                    }
                     self.expect(TokenType::Identifier)?;
                     let identifier_span = self.token.span;
                     let name = &self.lexer.source[self.token.span.as_range()];
+                    Self::not_constant(name, identifier_span)?;
                     self.increment()?;
                     match self.token.token_type {
                         // Declare a variable while assigning a value to it
@@ -383,7 +1225,7 @@ impl<'a> Parser<'a> {
                             self.increment()?;
 
                             if self.eof {
-                                return Err(Error::PError { 
+                                return Err(Error::PUnexpectedEOF { 
                                     message: format!("Unexpected end of file while declaring variable! Help: Provide an expression to the right of the `=` symbol"), 
                                     span: Span::new(start, self.token.span.end),
                                 });
@@ -396,6 +1238,7 @@ impl<'a> Parser<'a> {
                                 None => { self.symbols.insert(Symbol::Variable(name), 0); },
                                 Some(..) => (),
                             };
+                            self.definitions.push(Definition { symbol: Symbol::Variable(name), span: identifier_span, scope: None });
 
                             Ok(Rc::new(
                                 Tree::new(
@@ -406,78 +1249,50 @@ impl<'a> Parser<'a> {
                         }
 
                         // A function declaration
-                        TokenType::Identifier => {
+                        TokenType::Identifier | TokenType::OpeningBracket | TokenType::Ellipsis => {
 
-                            if let Ok(..) = get_function(name) {
+                            if let Ok(..) = get_builtin_arguments(name) {
                                 return Err(Error::PError { 
                                     message: format!("The function `{name}` is a built in function and cannot be overwritten!"), 
                                     span: identifier_span,
                                 });
-                            } else if name == "print" {
+                            } else if name == "print" || name == "diff" {
                                 return Err(Error::PError { 
-                                    message: format!("The function `print` is a built in function and cannot be overwritten!"), 
+                                    message: format!("The function `{name}` is a built in function and cannot be overwritten!"), 
                                     span: identifier_span,
                                 });
                             }
 
-                            let mut arguments = vec![];
-                            while self.token.token_type == TokenType::Identifier {
-                                let name = &self.lexer.source[self.token.span.as_range()];
-                                arguments.push(name);
-                                self.increment()?;
-                            }
+                            let old_symbols = self.symbols.clone();
+                            let function = self.function_body(start, name, identifier_span);
+                            // Revert back to the previous state, which also removes the symbols of the arguments
+                            self.symbols = old_symbols;
+                            let (Parameters { arguments, spans: argument_spans, defaults, rest }, body) = function?;
 
-                            if arguments.len() == 1 && arguments[0] == "_" {
-                                arguments = vec![];
+                            // Create function symbol
+                            match self.symbols.get_mut(&Symbol::Function(name)) {
+                                Some(arg_len) => *arg_len = arguments.len(),
+                                None => { self.symbols.insert(Symbol::Function(name), arguments.len()); }
                             }
-
-                            if self.token.token_type == TokenType::Equal {
-                                self.increment()?;
-                                let old_symbols = self.symbols.clone();
-
-                                // Create symbols for arguments
-                                for symbol in &arguments {
-                                    self.symbols.insert(Symbol::Variable(symbol), 0);
-                                }
-
-                                if self.eof {
-                                    return Err(Error::PError { 
-                                        message: format!("Unexpected end of file while declaring function! Help: Provide an expression to the right of the `=` symbol"), 
-                                        span: Span::new(start, self.token.span.end),
-                                    });
-                                }
-                                                 
-                                let body = 
-                                    match self.final_stage() {
-                                        Ok(value) => value,
-                                        error => {
-                                            // Revert back to the previous state if the function is of invalid grammar
-                                            self.symbols = old_symbols;
-                                            return error;
-                                        },
-                                    };
-
-                                self.symbols = old_symbols;
-
-                                // Create function symbol
-                                match self.symbols.get_mut(&Symbol::Function(name)) {
-                                    Some(arg_len) => *arg_len = arguments.len(),
-                                    None => { self.symbols.insert(Symbol::Function(name), arguments.len()); }
-                                }
-
-                                return Ok(Rc::new(
-                                    Tree::new(
-                                        AST::FunctionDecl { name, arguments, body },
-                                        Span::new(start, self.token.span.end)
-                                    )
-                                ));
+                            // A function with a rest argument has no limit on how many arguments it can be given
+                            if rest.is_some() {
+                                self.symbols.insert(Symbol::Variadic(name), 0);
                             } else {
-                                return Err(Error::PError 
-                                    { 
-                                        message: format!("Expected an expression for the function `{name}`"), 
-                                        span: identifier_span,
-                                    });
+                                self.symbols.remove(&Symbol::Variadic(name));
                             }
+                            self.definitions.push(Definition { symbol: Symbol::Function(name), span: identifier_span, scope: None });
+                            let scope = Span::new(argument_spans.first().or(rest.as_ref().map(|(_, span)| span)).map_or(body.span.start, |span| span.start), self.token.span.start);
+                            for (argument, span) in arguments.iter().copied().zip(argument_spans).chain(rest) {
+                                self.definitions.push(Definition { symbol: Symbol::Variable(argument), span, scope: Some(scope) });
+                            }
+
+                            let rest = rest.map(|(name, _)| name);
+                            Ok(Rc::new(
+                                Tree::new(
+                                    AST::FunctionDecl { name, arguments, defaults, rest, body },
+                                    Span::new(start, self.token.span.end)
+                                )
+                            ))
                         },
 
                         // Just declare a variable
@@ -488,6 +1303,7 @@ impl<'a> Parser<'a> {
                                 Some(..) => (),
                                 None => { self.symbols.insert(Symbol::Variable(name), 0); }
                             }
+                            self.definitions.push(Definition { symbol: Symbol::Variable(name), span: identifier_span, scope: None });
 
                             Ok(Rc::new(
                                 Tree::new(
@@ -513,15 +1329,21 @@ impl<'a> Parser<'a> {
                             }
                             if self.symbols.contains_key(&Symbol::Function(value)) {
                                 self.symbols.remove(&Symbol::Function(value));
+                                self.symbols.remove(&Symbol::Variadic(value));
                                 removed = true;
                             }
 
                             // Check for builtin functions here rather than at the VM step
-                            if let Ok(..) = get_function(value) {
+                            if let Ok(..) = get_builtin_arguments(value) {
                                 return Err(Error::PError { 
                                     message: format!("The builtin function `{value}` cannot be deleted!"), 
                                     span: token_span 
                                 });
+                            } else if get_constant(value).is_ok() {
+                                return Err(Error::PError { 
+                                    message: format!("The constant `{value}` cannot be deleted!"), 
+                                    span: token_span 
+                                });
                             }
 
                             if !removed {
@@ -539,6 +1361,12 @@ impl<'a> Parser<'a> {
                             ))
                         },
 
+                        TokenType::EOF => 
+                            Err(Error::PUnexpectedEOF { 
+                                    message: "Expected an identifer / function to delete but found an unexpected end of file".to_string(), 
+                                    span: token_span 
+                                }),
+
                         tt => 
                             Err(Error::PError { 
                                     message: format!("Expected an identifer / function to delete but found `{tt}`"), 
@@ -546,6 +1374,15 @@ impl<'a> Parser<'a> {
                                 })
                     }
                 }
+                else if name == "import" {
+                    Err(Error::PError { 
+                        message: "An import has to be a statement of its own, like `import \"path\" as name;`".to_string(), 
+                        span: Span::new(start, ident_end),
+                    })
+                }
+                else if name == "solve" && self.solve_ahead() {
+                    self.solve(start)
+                }
                 else if name == "Null" {
                     Ok(Rc::new(
                         Tree::new(
@@ -554,7 +1391,19 @@ impl<'a> Parser<'a> {
                         )
                     ))
                 }
+                else if name == "match" {
+                    self.match_expression(start)
+                }
+                else if name == "fn" {
+                    self.lambda(start, true)
+                }
                 else if name == "typeof" {
+                    if self.eof {
+                        return Err(Error::PUnexpectedEOF { 
+                            message: "Unexpected end of file after `typeof`! Help: Provide an expression to check the type of".to_string(), 
+                            span: Span::new(start, self.token.span.end),
+                        });
+                    }
                     let expression = self.final_stage()?;
                     Ok(Rc::new(
                         Tree::new(
@@ -566,12 +1415,14 @@ impl<'a> Parser<'a> {
                 else {
 
                     let token = self.token.token_type.clone();
+                    let equation = self.equation;
 
                     let mut assign_type = |operator| -> Result<Rc<Tree<'a>>, Error> {
+                        Self::not_constant(name, Span::new(start, ident_end))?;
                         self.increment()?;
 
                         if self.eof {
-                            return Err(Error::PError { 
+                            return Err(Error::PUnexpectedEOF { 
                                 message: format!("Unexpected end of file while performing `{operator}` on a variable! Help: Provide an expression to the right of the `{operator}` symbol"), 
                                 span: Span::new(start, self.token.span.end),
                             });
@@ -580,18 +1431,25 @@ impl<'a> Parser<'a> {
                         let result = self.final_stage()?;
                         let end = result.span.end;
 
-                        return Ok(Rc::new(
+                        Ok(Rc::new(
                             Tree::new(
                                 AST::AssignOp { operator, identifier: name, identifier_span: Span::new(start, ident_end), value: result },
                                 Span::new(start, end)
                             )
-                        ));
+                        ))
                     };
 
                     match token {
                         // Change variable assignment
-                        TokenType::Equal => {
+                        TokenType::Equal if !equation => {
+                            Self::not_constant(name, Span::new(start, ident_end))?;
                             self.increment()?;
+                            if self.eof {
+                                return Err(Error::PUnexpectedEOF { 
+                                    message: "Unexpected end of file while assigning to a variable! Help: Provide an expression to the right of the `=` symbol".to_string(), 
+                                    span: Span::new(start, self.token.span.end),
+                                });
+                            }
                             let result = self.final_stage()?;
                             let end = result.span.end;
 
@@ -612,13 +1470,13 @@ impl<'a> Parser<'a> {
                             while self.token.token_type != TokenType::ClosingBracket {
                                 
                                 if self.eof {
-                                    return Err(Error::PError { 
+                                    return Err(Error::PUnexpectedEOF { 
                                         message: format!("Unexpected end of file while calling function! Did you mean to type `)` to close the function call?"), 
                                         span: Span::new(expr_start, self.token.span.end),
                                     })
                                 }
 
-                                expressions.push(self.final_stage()?);
+                                expressions.push(self.argument(&expressions)?);
 
                                 if self.token.token_type == TokenType::ClosingBracket {
                                     break;
@@ -631,6 +1489,14 @@ impl<'a> Parser<'a> {
                             self.increment()?;
                             let end = self.token.span.end - 1;
 
+                            let named = expressions.iter().find(|argument| matches!(argument.ast, AST::NamedArgument { .. }));
+                            if let Some(argument) = named.filter(|_| name == "print" || name == "diff" || get_builtin_arguments(name).is_ok()) {
+                                return Err(Error::PError { 
+                                    message: format!("The builtin function `{name}` cannot be given named arguments!"), 
+                                    span: argument.span,
+                                });
+                            }
+
                             // Print is a special function that can accept any number of arguments
                             if name == "print" {
                                 return Ok(Rc::new(
@@ -640,10 +1506,34 @@ impl<'a> Parser<'a> {
                                     )
                                 ));
                             }
+
+                            // The expression given to `diff` is not run, but differentiated when parsing
+                            if name == "diff" {
+                                let [expression, variable] = &expressions[..] else {
+                                    return Err(Error::PError { 
+                                        message: format!("The function `diff` expected 2 argument(s) but {} argument(s) were found!", expressions.len()), 
+                                        span: Span::new(expr_start, end - 1),
+                                    });
+                                };
+                                let AST::Identifier { name: variable } = variable.ast else {
+                                    return Err(Error::PError { 
+                                        message: "The function `diff` expected the name of a variable to differentiate by!".to_string(), 
+                                        span: variable.span,
+                                    });
+                                };
+                                let result = symbolic::differentiate(expression, variable)
+                                    .map_err(|message| Error::PError { message, span: expression.span })?;
+                                return Ok(Rc::new(
+                                    Tree::new(
+                                        AST::Derivative { expression: expression.clone(), variable, result },
+                                        Span::new(start, end)
+                                    )
+                                ));
+                            }
     
 
-                            match get_function(name) {
-                                Ok((arg_len, _)) => {
+                            match get_builtin_arguments(name) {
+                                Ok(arg_len) => {
                                     if arg_len != expressions.len() {
                                         return Err(Error::PError { 
                                             message: format!("The function `{name}` expected {arg_len} argument(s) but {} argument(s) were found!", expressions.len()), 
@@ -653,20 +1543,13 @@ impl<'a> Parser<'a> {
                                 },
     
                                 Err(()) => {
-                                    if self.symbols.contains_key(&Symbol::Function(name)) {
+                                    if self.symbols.contains_key(&Symbol::Function(name)) && !self.symbols.contains_key(&Symbol::Variadic(name)) {
                                         let arg_len = self.symbols.get(&Symbol::Function(name)).unwrap();
                                         if expressions.len() > *arg_len {
                                             return Err(Error::PError { 
                                                 message: format!("The function `{name}` expected {arg_len} argument(s) but {} argument(s) were found!", expressions.len()), 
                                                 span: Span::new(expr_start, end - 1),
                                             });
-                                        } else if expressions.len() < *arg_len {
-                                            return Ok(Rc::new(
-                                                Tree::new(
-                                                    AST::PartialCall{ name, expressions },
-                                                    Span::new(start, end)
-                                                )
-                                            ));
                                         }
                                     }
                                 }
@@ -707,9 +1590,22 @@ impl<'a> Parser<'a> {
 
             TokenType::OpeningBracket => {
                 self.increment()?;
+                if self.eof {
+                    return Err(Error::PUnexpectedEOF { 
+                        message: "Unexpected end of file after `(`! Did you mean to type `)` to close the bracket?".to_string(), 
+                        span: Span::new(span.start, self.token.span.end),
+                    });
+                }
                 let result = self.final_stage()?;
                 match self.token.token_type {
                     TokenType::ClosingBracket => self.increment()?,
+
+                    _ if self.eof => {
+                        return Err(Error::PUnexpectedEOF { 
+                            message: "Expected closing bracket `)`! Found an unexpected end of file".to_string(), 
+                            span: Span::new(span.start, self.token.span.end),
+                        });
+                    }
                      
                     _ => {
                         let span = Span::new(self.token.span.start, self.token.span.start);
@@ -731,7 +1627,7 @@ impl<'a> Parser<'a> {
                 while self.token.token_type != TokenType::CloseArray {
                     
                     if self.eof {
-                        return Err(Error::PError { 
+                        return Err(Error::PUnexpectedEOF { 
                             message: format!("Unexpected end of file while creating array! Did you mean to type `]` to close the array?"), 
                             span: Span::new(expr_start, self.token.span.end),
                         })
@@ -757,37 +1653,115 @@ impl<'a> Parser<'a> {
                 ))
             }
 
-            TokenType::String => {
+            TokenType::Backslash => {
+                let start = self.token.span.start;
                 self.increment()?;
-                let unprocessed_contents = &self.lexer.source[(span.start + 1)..(span.end - 1)];
-                let mut contents = String::new();
-                let mut check_next = false;
-                // Very basic string processing
-                for character in unprocessed_contents.chars() {
-                    if character == '\\' {
-                        check_next = true;
-                        continue;
+                self.lambda(start, false)
+            }
+
+            TokenType::OpenMap => {
+                self.increment()?;
+                let expr_start = self.token.span.start;
+                let mut entries = vec![];
+
+                while self.token.token_type != TokenType::CloseMap {
+                    
+                    if self.eof {
+                        return Err(Error::PUnexpectedEOF { 
+                            message: "Unexpected end of file while creating map! Did you mean to type `}` to close the map?".to_string(), 
+                            span: Span::new(expr_start, self.token.span.end),
+                        })
                     }
 
-                    if check_next {
-                        check_next = false;
-                        match character {
-                            '\\' => contents.push(character),
-                            'n' => contents.push('\n'),
-                            'r' => contents.push('\r'),
-                            't' => contents.push('\t'),
-                            '0' => contents.push('\0'),
-                            '\'' => contents.push('\''),
-                            '"' => contents.push('"'),
-                            c => return Err(Error::PError { message: format!("Unknown character escape sequence \\{c}"), span })
-                        }
-                        continue;
+                    let key = self.final_stage()?;
+                    self.expect(TokenType::Colon)?;
+                    self.increment()?;
+                    entries.push((key, self.final_stage()?));
+
+                    if self.token.token_type == TokenType::CloseMap {
+                        break;
                     }
-                    contents.push(character);
+
+                    self.expect(TokenType::Comma)?;
+                    self.increment()?;
                 }
+
+                let end = self.token.span.end;
+                self.increment()?;
+                Ok(Rc::new(
+                    Tree::new(
+                        AST::Map { entries },
+                        Span::new(span.start, end)
+                    )
+                ))
+            }
+
+            TokenType::String => {
+                self.increment()?;
+                let contents = Self::unescape(&self.lexer.source[(span.start + 1)..(span.end - 1)], span)?;
                 Ok(Rc::new(Tree::new(AST::String { contents }, span)))
             }
 
+            TokenType::FormatStart => {
+                self.increment()?;
+                let mut parts = vec![];
+                loop {
+                    match self.token.token_type {
+                        TokenType::FormatText => {
+                            let text = Self::braces(&self.lexer.source[self.token.span.as_range()], self.token.span)?;
+                            let contents = Self::unescape(&text, self.token.span)?;
+                            parts.push(Rc::new(Tree::new(AST::String { contents }, self.token.span)));
+                            self.increment()?;
+                        }
+
+                        TokenType::InterpolationStart => {
+                            let start = self.token.span.start;
+                            self.increment()?;
+                            if self.eof {
+                                return Err(Error::PUnexpectedEOF { 
+                                    message: "Unexpected end of file after `{` in a format string!".to_string(), 
+                                    span: Span::new(start, self.token.span.end),
+                                });
+                            }
+                            let value = self.final_stage()?;
+                            let spec = if self.token.token_type == TokenType::FormatSpec {
+                                let spec_span = self.token.span;
+                                let spec = FormatSpec::parse(&self.lexer.source[(spec_span.start + 1)..spec_span.end])
+                                    .map_err(|message| Error::PError { message, span: spec_span })?;
+                                self.increment()?;
+                                spec
+                            } else {
+                                FormatSpec::default()
+                            };
+                            self.expect(TokenType::InterpolationEnd)?;
+                            let end = self.token.span.end;
+                            self.increment()?;
+                            parts.push(Rc::new(Tree::new(AST::Format { value, spec }, Span::new(start, end))));
+                        }
+
+                        TokenType::FormatEnd => {
+                            let end = self.token.span.end;
+                            self.increment()?;
+                            return Ok(Rc::new(Tree::new(AST::FormatString { parts }, Span::new(span.start, end))));
+                        }
+
+                        _ if self.eof => {
+                            return Err(Error::PUnexpectedEOF { 
+                                message: "Unexpected end of file in a format string!".to_string(), 
+                                span: Span::new(span.start, self.token.span.end),
+                            });
+                        }
+
+                        _ => {
+                            return Err(Error::PError { 
+                                message: format!("Expected `}}` to end the expression in the format string! Found `{}`", self.token.token_type), 
+                                span: self.token.span,
+                            });
+                        }
+                    }
+                }
+            }
+
             TokenType::EOF => Err(Error::NoResult),
 
             token => {
@@ -802,7 +1776,91 @@ impl<'a> Parser<'a> {
     
 
 
+    /// Processes the escape sequences within the text of a string
+    /// Writes the `{{` and `}}` of the text of a format string as single braces. A single `}` is an error, like in Rust,
+    /// since it does not end an expression
+    fn braces(text: &str, span: Span) -> Result<String, Error> {
+        let mut result = String::new();
+        let mut characters = text.char_indices().peekable();
+        while let Some((index, character)) = characters.next() {
+            match character {
+                // An escaped character is left for `unescape`
+                '\\' => {
+                    result.push(character);
+                    if let Some((_, next)) = characters.next() {
+                        result.push(next);
+                    }
+                }
+                '{' | '}' => {
+                    if characters.next_if(|(_, next)| *next == character).is_none() && character == '}' {
+                        return Err(Error::PError {
+                            message: "Unexpected `}` in a format string! Help: Write `}}` for a brace".to_string(),
+                            span: Span::new(span.start + index, span.start + index + 1),
+                        });
+                    }
+                    result.push(character);
+                }
+                _ => result.push(character),
+            }
+        }
+        Ok(result)
+    }
+
+    fn unescape(unprocessed_contents: &str, span: Span) -> Result<String, Error> {
+        let mut contents = String::new();
+        let mut check_next = false;
+        // Very basic string processing
+        let mut characters = unprocessed_contents.chars();
+        while let Some(character) = characters.next() {
+            if character == '\\' && !check_next {
+                check_next = true;
+                continue;
+            }
+
+            if check_next {
+                check_next = false;
+                match character {
+                    '\\' => contents.push(character),
+                    'n' => contents.push('\n'),
+                    'r' => contents.push('\r'),
+                    't' => contents.push('\t'),
+                    '0' => contents.push('\0'),
+                    '\'' => contents.push('\''),
+                    '"' => contents.push('"'),
+                    // A unicode code point written in hexadecimal, such as \u{1F600}
+                    'u' => {
+                        if characters.next() != Some('{') {
+                            return Err(Error::PError { message: "Expected `{` after the escape sequence \\u".to_string(), span });
+                        }
+                        let mut code = String::new();
+                        loop {
+                            match characters.next() {
+                                Some('}') => break,
+                                Some(c) => code.push(c),
+                                None => return Err(Error::PError { message: format!("Invalid unicode escape sequence \\u{{{code}, the closing `}}` is missing"), span }),
+                            }
+                        }
+                        match u32::from_str_radix(&code, 16).ok().and_then(char::from_u32) {
+                            Some(c) => contents.push(c),
+                            None => return Err(Error::PError { message: format!("Invalid unicode escape sequence \\u{{{code}}}"), span }),
+                        }
+                    },
+                    c => return Err(Error::PError { message: format!("Unknown character escape sequence \\{c}"), span })
+                }
+                continue;
+            }
+            contents.push(character);
+        }
+        Ok(contents)
+    }
+
     fn expect(&mut self, token_type: TokenType) -> Result<(), Error> {
+        if self.eof && token_type != TokenType::EOF {
+            return Err(Error::PUnexpectedEOF { 
+                message: format!("Expected a token of type: `{token_type}` but found an unexpected end of file"), 
+                span: self.token.span,
+            })
+        }
         if self.token.token_type != token_type {
             return Err(Error::PError { 
                 message: format!("Expected a token of type: `{token_type}` but found token of type: {}", self.token.token_type), 
@@ -815,7 +1873,7 @@ impl<'a> Parser<'a> {
     fn parse_number(&mut self, span: Span, radix: u32, number_type: &str) -> Result<Rc<Tree<'a>>, Error> {
         let binary_str = &self.lexer.source[(span.start + 2)..span.end];
         let number = 
-            match u64::from_str_radix(binary_str, radix) {
+            match Self::parse_radix(binary_str, radix) {
                 Ok(value) => value,
                 // This *should* never trigger, but here it is
                 Err(parse_error) => {
@@ -824,10 +1882,31 @@ impl<'a> Parser<'a> {
                         span,
                     });
                 }
-            } as f64;
+            };
         let number_ast = AST::Number {
             value: number,
         };
         Ok(Rc::new(Tree::new(number_ast, span)))
     }
+
+    /// Converts the digits of a binary, octal or hexadecimal number without its prefix
+    pub fn parse_radix(digits: &str, radix: u32) -> Result<f64, ParseIntError> {
+        u64::from_str_radix(digits, radix).map(|value| value as f64)
+    }
+
+    /// Converts text to a number in any of the number systems supported, ie. `0x1f`, `-0b11`, `1.5`
+    pub fn parse_text_number(text: &str) -> Option<f64> {
+        let text = text.trim();
+        let (sign, digits) = match text.strip_prefix('-') {
+            Some(digits) => (-1.0, digits),
+            None => (1.0, text.strip_prefix('+').unwrap_or(text)),
+        };
+        let number = match digits.get(..2) {
+            Some("0x") => Self::parse_radix(&digits[2..], 16).ok()?,
+            Some("0o") => Self::parse_radix(&digits[2..], 8).ok()?,
+            Some("0b") => Self::parse_radix(&digits[2..], 2).ok()?,
+            _ => digits.parse().ok()?,
+        };
+        Some(sign * number)
+    }
 }
\ No newline at end of file
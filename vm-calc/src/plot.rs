@@ -0,0 +1,220 @@
+use crate::{functions::{expect_number, expect_string, ValueFunction}, instruction::Value, matrix::vector, vm::VM};
+
+pub const FUNCTIONS: [(&str, (usize, ValueFunction)); 1] = [
+    ("chart", (4, chart)),
+];
+
+/// The size of the area that the series are drawn in, in characters
+const WIDTH: usize = 60;
+const HEIGHT: usize = 15;
+/// How many times a function is called across the chart
+const SAMPLES: usize = 240;
+/// The characters that draw each series in the terminal, and their colours in an SVG file
+const MARKERS: [char; 6] = ['*', '+', 'o', 'x', '#', '@'];
+const COLOURS: [&str; 6] = ["#1f77b4", "#d62728", "#2ca02c", "#ff7f0e", "#9467bd", "#8c564b"];
+/// The size of an SVG file and the space around the chart in it, for the labels
+const SVG_WIDTH: f64 = 640.0;
+const SVG_HEIGHT: f64 = 400.0;
+const SVG_MARGIN: f64 = 60.0;
+
+/// The points of one series, where a y that is not finite leaves a gap in the line
+type Series = Vec<(f64, f64)>;
+
+/// The bounds of the chart, as the smallest and largest x and y
+struct Bounds {
+    x: (f64, f64),
+    y: (f64, f64),
+}
+
+impl Bounds {
+    /// Where a point is within the bounds, from 0 to 1 along each axis
+    fn scale(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        ((x - self.x.0) / (self.x.1 - self.x.0), (y - self.y.0) / (self.y.1 - self.y.0))
+    }
+}
+
+/// `chart(series, a, b, svg)` draws a function from `a` to `b`, an array of numbers, or an array of several of them.
+/// The chart is returned as text, unless `svg` is the path of a file to write it to instead. `a` and `b` can be
+/// `Null` for arrays, which are then drawn by their indices. The prelude function `plot` prints it
+fn chart(vm: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    let given = series(&values[0])?;
+    let bounds = match (&values[1], &values[2]) {
+        (Value::Null, Value::Null) => None,
+        (a, b) => {
+            let (a, b) = (expect_number("plot", a)?, expect_number("plot", b)?);
+            if a >= b || !a.is_finite() || !b.is_finite() {
+                return Err(format!("Cannot plot from {a} to {b}, the start and end should be finite numbers with the start smaller than the end!"));
+            }
+            Some((a, b))
+        }
+    };
+
+    let mut all = vec![];
+    for value in &given {
+        all.push(match value {
+            Value::Function(..) => {
+                let Some((a, b)) = bounds else {
+                    return Err("The function `plot` needs the start and end to plot a function, as in `plot(f, a, b)`!".to_string());
+                };
+                sample(vm, value, a, b)?
+            }
+            array => {
+                let numbers = vector("plot", array)?;
+                let (a, b) = bounds.unwrap_or((0.0, numbers.len().saturating_sub(1) as f64));
+                let step = if numbers.len() > 1 { (b - a) / (numbers.len() - 1) as f64 } else { 0.0 };
+                numbers.into_iter().enumerate().map(|(index, y)| (a + step * index as f64, y)).collect()
+            }
+        });
+    }
+    let bounds = limits(&all, bounds)?;
+
+    match &values[3] {
+        Value::Null => Ok(Value::String(text(&all, &bounds))),
+        path => {
+            let path = expect_string("plot", path)?;
+            std::fs::write(&path, svg(&all, &bounds)).map_err(|error| format!("Could not write the plot to `{path}`: {error}!"))?;
+            Ok(Value::String(format!("The plot was written to `{path}`")))
+        }
+    }
+}
+
+/// The series given, where an array of functions or arrays holds several series and any other array is one
+fn series(value: &Value) -> Result<Vec<Value>, String> {
+    match value {
+        Value::Function(..) => Ok(vec![value.clone()]),
+        Value::Array(array) if !array.iter().any(|value| matches!(value, Value::Function(..) | Value::Array(..))) => Ok(vec![value.clone()]),
+        Value::Array(array) => array.iter().map(|value| match value {
+            Value::Function(..) | Value::Array(..) => Ok(value.clone()),
+            value => Err(format!("The function `plot` can plot functions and arrays of numbers, but found a value of type {} to plot!", value.type_of())),
+        }).collect(),
+        value => Err(format!("The function `plot` can plot functions and arrays of numbers, but found a value of type {}!", value.type_of())),
+    }
+}
+
+/// Calls the function at evenly spaced points from `a` to `b`
+fn sample(vm: &mut VM, function: &Value, a: f64, b: f64) -> Result<Series, String> {
+    (0..SAMPLES).map(|index| {
+        let x = a + (b - a) * index as f64 / (SAMPLES - 1) as f64;
+        match vm.call(function, vec![Value::Number(x)])? {
+            Value::Number(y) => Ok((x, y)),
+            value => Err(format!("The function given to `plot` should return a value of type {{Number}} but returned a value of type {}!", value.type_of())),
+        }
+    }).collect()
+}
+
+/// The bounds that fit every point, which are widened when all of the points have the same y
+fn limits(all: &[Series], x: Option<(f64, f64)>) -> Result<Bounds, String> {
+    let points = all.iter().flatten();
+    let (mut low, mut high) = points.clone().filter(|(_, y)| y.is_finite()).fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), (_, y)| (low.min(*y), high.max(*y)));
+    if low > high {
+        return Err("There is nothing to plot, since none of the values are finite numbers!".to_string());
+    }
+    if low == high {
+        (low, high) = (low - 1.0, high + 1.0);
+    }
+    let x = x.unwrap_or_else(|| points.fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), (x, _)| (low.min(*x), high.max(*x))));
+    let x = if x.0 == x.1 { (x.0 - 1.0, x.1 + 1.0) } else { x };
+    Ok(Bounds { x, y: (low, high) })
+}
+
+/// A number for the labels of the axes, rounded so that it is short
+fn label(number: f64) -> String {
+    let rounded = (number * 1000.0).round() / 1000.0;
+    if rounded == 0.0 { "0".to_string() } else { rounded.to_string() }
+}
+
+/// The chart as lines of characters, with the largest y at the top
+fn text(all: &[Series], bounds: &Bounds) -> String {
+    let mut grid = vec![vec![' '; WIDTH]; HEIGHT];
+    let cell = |point| {
+        let (x, y) = bounds.scale(point);
+        ((x * (WIDTH - 1) as f64).round() as isize, ((1.0 - y) * (HEIGHT - 1) as f64).round() as isize)
+    };
+    for (series, marker) in all.iter().zip(MARKERS.iter().cycle()) {
+        let mut last: Option<(isize, isize)> = None;
+        for point in series {
+            if !point.1.is_finite() {
+                last = None;
+                continue;
+            }
+            let (column, row) = cell(*point);
+            // The cells between two points are filled in as well, so that steep lines do not have gaps
+            let (from_column, from_row) = last.unwrap_or((column, row));
+            let steps = (column - from_column).abs().max((row - from_row).abs()).max(1);
+            for step in 1..=steps {
+                let c = from_column + (column - from_column) * step / steps;
+                let r = from_row + (row - from_row) * step / steps;
+                if (0..WIDTH as isize).contains(&c) && (0..HEIGHT as isize).contains(&r) {
+                    grid[r as usize][c as usize] = *marker;
+                }
+            }
+            last = Some((column, row));
+        }
+    }
+
+    let labels = [(0, label(bounds.y.1)), (HEIGHT / 2, label((bounds.y.0 + bounds.y.1) / 2.0)), (HEIGHT - 1, label(bounds.y.0))];
+    let margin = labels.iter().map(|(_, label)| label.len()).max().unwrap_or(0);
+    let mut lines = grid.into_iter().enumerate().map(|(row, cells)| {
+        let label = labels.iter().find(|(label_row, _)| *label_row == row).map_or("", |(_, label)| label);
+        format!("{label:>margin$} |{}", cells.into_iter().collect::<String>().trim_end())
+    }).collect::<Vec<_>>();
+    lines.push(format!("{:margin$} +{}", "", "-".repeat(WIDTH)));
+
+    // The labels of the x axis are at the start, middle and end of it
+    let (start, middle, end) = (label(bounds.x.0), label((bounds.x.0 + bounds.x.1) / 2.0), label(bounds.x.1));
+    let mut axis = format!("{start:<width$}", width = WIDTH / 2 - middle.len() / 2);
+    axis.push_str(&middle);
+    let padding = WIDTH.saturating_sub(axis.len() + end.len()).max(1);
+    axis.push_str(&format!("{:padding$}{end}", ""));
+    lines.push(format!("{:margin$}  {axis}", ""));
+
+    if all.len() > 1 {
+        let legend = (0..all.len()).zip(MARKERS.iter().cycle()).map(|(index, marker)| format!("{marker} {index}")).collect::<Vec<_>>();
+        lines.push(format!("{:margin$}  {}", "", legend.join("  ")));
+    }
+    lines.join("\n")
+}
+
+/// The chart as an SVG image, where each series is a line of its own colour
+fn svg(all: &[Series], bounds: &Bounds) -> String {
+    let (width, height) = (SVG_WIDTH - 2.0 * SVG_MARGIN, SVG_HEIGHT - 2.0 * SVG_MARGIN);
+    let position = |point| {
+        let (x, y) = bounds.scale(point);
+        (SVG_MARGIN + x * width, SVG_MARGIN + (1.0 - y) * height)
+    };
+
+    let mut elements = vec![
+        format!(r#"<rect width="{SVG_WIDTH}" height="{SVG_HEIGHT}" fill="white"/>"#),
+        format!(r#"<rect x="{SVG_MARGIN}" y="{SVG_MARGIN}" width="{width}" height="{height}" fill="none" stroke="black"/>"#),
+    ];
+    for (series, colour) in all.iter().zip(COLOURS.iter().cycle()) {
+        // A point that is not finite ends one line and the next point starts another
+        for line in series.split(|(_, y)| !y.is_finite()).filter(|line| !line.is_empty()) {
+            let points = line.iter().map(|point| {
+                let (x, y) = position(*point);
+                format!("{x:.2},{y:.2}")
+            }).collect::<Vec<_>>();
+            elements.push(format!(r#"<polyline points="{}" fill="none" stroke="{colour}" stroke-width="2"/>"#, points.join(" ")));
+        }
+    }
+
+    let text = |x: f64, y: f64, anchor: &str, contents: String| format!(r#"<text x="{x}" y="{y}" text-anchor="{anchor}" font-family="monospace" font-size="12">{contents}</text>"#);
+    for fraction in [0.0, 0.5, 1.0] {
+        let x = bounds.x.0 + (bounds.x.1 - bounds.x.0) * fraction;
+        elements.push(text(SVG_MARGIN + fraction * width, SVG_MARGIN + height + 20.0, "middle", label(x)));
+        let y = bounds.y.0 + (bounds.y.1 - bounds.y.0) * fraction;
+        elements.push(text(SVG_MARGIN - 8.0, SVG_MARGIN + (1.0 - fraction) * height + 4.0, "end", label(y)));
+    }
+    if all.len() > 1 {
+        for (index, colour) in (0..all.len()).zip(COLOURS.iter().cycle()) {
+            let x = SVG_MARGIN + index as f64 * 60.0;
+            elements.push(format!(r#"<rect x="{x}" y="{}" width="12" height="12" fill="{colour}"/>"#, SVG_MARGIN - 30.0));
+            elements.push(text(x + 18.0, SVG_MARGIN - 20.0, "start", index.to_string()));
+        }
+    }
+
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{SVG_WIDTH}\" height=\"{SVG_HEIGHT}\" viewBox=\"0 0 {SVG_WIDTH} {SVG_HEIGHT}\">\n{}\n</svg>\n",
+        elements.join("\n"),
+    )
+}
@@ -0,0 +1,355 @@
+use std::{collections::BTreeMap, ops::{Add, Div, Mul, Neg, Sub}};
+
+use crate::{functions::ValueFunction, instruction::Value, matrix::vector, vm::VM};
+
+pub const FUNCTIONS: [(&str, (usize, ValueFunction)); 1] = [
+    ("roots", (1, roots)),
+];
+
+/// The most steps of the Durand-Kerner method, for the polynomials of a higher degree than 4
+const ITERATIONS: usize = 500;
+
+/// A complex number, for the roots of polynomials. A root is only given as complex when it is not real
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Complex {
+    re: f64,
+    im: f64,
+}
+
+impl Complex {
+    fn new(re: f64, im: f64) -> Self {
+        Self { re, im }
+    }
+
+    fn real(re: f64) -> Self {
+        Self::new(re, 0.0)
+    }
+
+    fn abs(self) -> f64 {
+        self.re.hypot(self.im)
+    }
+
+    fn from_polar(radius: f64, angle: f64) -> Self {
+        Self::new(radius * angle.cos(), radius * angle.sin())
+    }
+
+    fn sqrt(self) -> Self {
+        Self::from_polar(self.abs().sqrt(), self.im.atan2(self.re) / 2.0)
+    }
+
+    fn cbrt(self) -> Self {
+        Self::from_polar(self.abs().cbrt(), self.im.atan2(self.re) / 3.0)
+    }
+}
+
+impl Add for Complex {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.re + rhs.re, self.im + rhs.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.re - rhs.re, self.im - rhs.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        Self::new(self.re * rhs.re - self.im * rhs.im, self.re * rhs.im + self.im * rhs.re)
+    }
+}
+
+impl Div for Complex {
+    type Output = Self;
+    fn div(self, rhs: Self) -> Self {
+        let denominator = rhs.re * rhs.re + rhs.im * rhs.im;
+        Self::new((self.re * rhs.re + self.im * rhs.im) / denominator, (self.im * rhs.re - self.re * rhs.im) / denominator)
+    }
+}
+
+impl Neg for Complex {
+    type Output = Self;
+    fn neg(self) -> Self {
+        Self::new(-self.re, -self.im)
+    }
+}
+
+/// The value of the polynomial with the coefficients from the highest power down, along with the value of its derivative
+fn evaluate(coefficients: &[Complex], x: Complex) -> (Complex, Complex) {
+    let (mut value, mut slope) = (Complex::real(0.0), Complex::real(0.0));
+    for coefficient in coefficients {
+        slope = slope * x + value;
+        value = value * x + *coefficient;
+    }
+    (value, slope)
+}
+
+/// The roots of `x² + bx + c`
+fn quadratic(b: Complex, c: Complex) -> Vec<Complex> {
+    let root = (b * b - Complex::real(4.0) * c).sqrt();
+    // The larger root is found first and the other one from it, so that nothing is lost by subtracting numbers that are close
+    let sign = if (b.re * root.re + b.im * root.im) >= 0.0 { 1.0 } else { -1.0 };
+    let first = -(b + Complex::real(sign) * root) / Complex::real(2.0);
+    if first.abs() == 0.0 {
+        return vec![first, first];
+    }
+    vec![first, c / first]
+}
+
+/// The roots of `x³ + ax² + bx + c`, by Cardano's formula
+fn cubic(a: Complex, b: Complex, c: Complex) -> Vec<Complex> {
+    // x = t - a / 3 gives t³ + pt + q
+    let third = a / Complex::real(3.0);
+    let p = b - a * third;
+    let q = Complex::real(2.0) * third * third * third - b * third + c;
+
+    let half_q = q / Complex::real(2.0);
+    let root = (half_q * half_q + p * p * p / Complex::real(27.0)).sqrt();
+    let mut u = (-half_q + root).cbrt();
+    if u.abs() < 1e-300 {
+        u = (-half_q - root).cbrt();
+    }
+    if u.abs() < 1e-300 {
+        return vec![-third; 3];
+    }
+    let turn = Complex::from_polar(1.0, std::f64::consts::TAU / 3.0);
+    let mut roots = vec![];
+    let mut rotated = u;
+    for _ in 0..3 {
+        roots.push(rotated - p / (Complex::real(3.0) * rotated) - third);
+        rotated = rotated * turn;
+    }
+    roots
+}
+
+/// The roots of `x⁴ + ax³ + bx² + cx + d`, by Ferrari's method
+fn quartic(a: Complex, b: Complex, c: Complex, d: Complex) -> Vec<Complex> {
+    // x = y - a / 4 gives y⁴ + py² + qy + r
+    let quarter = a / Complex::real(4.0);
+    let square = quarter * quarter;
+    let p = b - Complex::real(6.0) * square;
+    let q = c - b * Complex::real(2.0) * quarter + Complex::real(8.0) * square * quarter;
+    let r = d - c * quarter + b * square - Complex::real(3.0) * square * square;
+
+    let ys = if q.abs() < 1e-12 * (1.0 + p.abs() + r.abs()) {
+        // A quadratic in y²
+        quadratic(p, r).into_iter().flat_map(|z| [z.sqrt(), -z.sqrt()]).collect()
+    } else {
+        // (y² + p/2 + m)² = 2m (y - q / 4m)² for a root m of the resolvent cubic, which is not 0 since q is not
+        let m = cubic(p, p * p / Complex::real(4.0) - r, -(q * q) / Complex::real(8.0)).into_iter()
+            .max_by(|a, b| a.abs().total_cmp(&b.abs())).unwrap();
+        let s = (Complex::real(2.0) * m).sqrt();
+        let shift = s * q / (Complex::real(4.0) * m);
+        let half_p = p / Complex::real(2.0);
+        let mut ys = quadratic(-s, half_p + m + shift);
+        ys.extend(quadratic(s, half_p + m - shift));
+        ys
+    };
+    ys.into_iter().map(|y| y - quarter).collect()
+}
+
+/// The roots of a polynomial of any degree by the Durand-Kerner method, which moves every root closer at once.
+/// Nothing is returned when the roots do not settle, which is the case for roots that are repeated
+fn durand_kerner(coefficients: &[Complex]) -> Option<Vec<Complex>> {
+    let degree = coefficients.len() - 1;
+    let seed = Complex::new(0.4, 0.9);
+    let mut roots = (0..degree).scan(Complex::real(1.0), |power, _| { *power = *power * seed; Some(*power) }).collect::<Vec<_>>();
+    for _ in 0..ITERATIONS {
+        let mut change: f64 = 0.0;
+        for i in 0..degree {
+            let mut denominator = Complex::real(1.0);
+            for j in 0..degree {
+                if i != j {
+                    denominator = denominator * (roots[i] - roots[j]);
+                }
+            }
+            let step = evaluate(coefficients, roots[i]).0 / denominator;
+            if step.re.is_finite() && step.im.is_finite() {
+                roots[i] = roots[i] - step;
+                change = change.max(step.abs() / roots[i].abs().max(1.0));
+            }
+        }
+        if change < 1e-14 {
+            return Some(roots);
+        }
+    }
+    None
+}
+
+/// How small a remainder is, compared to the polynomial that was divided, to be taken as 0 when finding a common factor
+const TOLERANCE: f64 = 1e-10;
+
+/// The largest coefficient of a polynomial, to compare the sizes of polynomials
+fn size(polynomial: &[f64]) -> f64 {
+    polynomial.iter().fold(0.0, |size, coefficient| size.max(coefficient.abs()))
+}
+
+/// The polynomial divided by its first coefficient, so that its highest power has 1. The first coefficients are removed
+/// while they are tiny next to the others, since they are only left by the error of the divisions
+fn monic(polynomial: &[f64]) -> Vec<f64> {
+    let limit = TOLERANCE * size(polynomial);
+    let polynomial = polynomial.iter().copied().skip_while(|coefficient| coefficient.abs() <= limit).collect::<Vec<_>>();
+    polynomial.iter().map(|coefficient| coefficient / polynomial[0]).collect()
+}
+
+/// The derivative of the polynomial with the coefficients from the highest power down
+fn derivative(polynomial: &[f64]) -> Vec<f64> {
+    let degree = polynomial.len() - 1;
+    polynomial[..degree].iter().enumerate().map(|(i, coefficient)| coefficient * (degree - i) as f64).collect()
+}
+
+/// The quotient and the remainder of dividing one polynomial by another. The remainder has its first coefficients removed
+/// while they are tiny next to the polynomial that was divided, so that an exact division leaves nothing
+fn divide(dividend: &[f64], divisor: &[f64]) -> (Vec<f64>, Vec<f64>) {
+    if dividend.len() < divisor.len() {
+        return (vec![0.0], dividend.to_vec());
+    }
+    let mut remainder = dividend.to_vec();
+    let mut quotient = vec![0.0; dividend.len() - divisor.len() + 1];
+    for i in 0..quotient.len() {
+        quotient[i] = remainder[i] / divisor[0];
+        for (j, coefficient) in divisor.iter().enumerate() {
+            remainder[i + j] -= quotient[i] * coefficient;
+        }
+    }
+    let limit = TOLERANCE * size(dividend);
+    let remainder = remainder[quotient.len()..].iter().copied().skip_while(|coefficient| coefficient.abs() <= limit).collect();
+    (quotient, remainder)
+}
+
+/// The greatest common factor of two polynomials by Euclid's algorithm, with 1 as the coefficient of its highest power
+fn gcd(a: &[f64], b: &[f64]) -> Vec<f64> {
+    let (mut a, mut b) = (monic(a), monic(b));
+    while !b.is_empty() {
+        let (_, remainder) = divide(&a, &b);
+        a = b;
+        b = if remainder.is_empty() { remainder } else { monic(&remainder) };
+    }
+    a
+}
+
+/// Splits the polynomial into factors that have no repeated roots, along with how many times each one is repeated, by
+/// Yun's algorithm. The repeated roots are found from these factors, since the methods for the roots lose most of their
+/// accuracy on a root that is repeated. Roots that are closer than about 1e-5 times their size are taken as repeated.
+/// The polynomial is kept whole when the error of the divisions keeps its factors from adding up to it
+fn square_free(polynomial: &[f64]) -> Vec<(Vec<f64>, usize)> {
+    let degree = polynomial.len() - 1;
+    let slope = derivative(polynomial);
+    let common = gcd(polynomial, &slope);
+    let (mut b, _) = divide(polynomial, &common);
+    let (mut c, _) = divide(&slope, &common);
+    let mut factors = vec![];
+    let mut repeated = 1;
+    while b.len() > 1 && repeated <= degree {
+        let d = c.iter().zip(derivative(&b)).map(|(c, slope)| c - slope).collect::<Vec<_>>();
+        // Every root that is left is repeated this many times when the difference is 0
+        let factor = if size(&d) <= TOLERANCE * size(&c).max(1.0) { b.clone() } else { gcd(&b, &d) };
+        (b, _) = divide(&b, &factor);
+        (c, _) = divide(&d, &factor);
+        if factor.len() > 1 {
+            factors.push((monic(&factor), repeated));
+        }
+        repeated += 1;
+    }
+    if factors.iter().map(|(factor, repeated)| (factor.len() - 1) * repeated).sum::<usize>() != degree {
+        return vec![(monic(polynomial), 1)];
+    }
+    factors
+}
+
+/// The roots of a polynomial that has no repeated roots, with 1 as the coefficient of its highest power
+fn solve(polynomial: &[Complex]) -> Result<Vec<Complex>, String> {
+    let mut found = match polynomial[..] {
+        [_, b] => vec![-b],
+        [_, b, c] => quadratic(b, c),
+        [_, b, c, d] => cubic(b, c, d),
+        [_, b, c, d, e] => quartic(b, c, d, e),
+        _ => durand_kerner(polynomial).ok_or("The function `roots` could not find the roots of the polynomial, since the numerical method did not settle on them!")?,
+    };
+
+    for root in &mut found {
+        polish(polynomial, root);
+    }
+    Ok(found)
+}
+
+/// A few steps of Newton's method, which make up for the error of the formulas and of splitting the polynomial
+fn polish(polynomial: &[Complex], root: &mut Complex) {
+    for _ in 0..3 {
+        let (value, slope) = evaluate(polynomial, *root);
+        let next = *root - value / slope;
+        if slope.abs() == 0.0 || !next.re.is_finite() || !next.im.is_finite() || evaluate(polynomial, next).0.abs() >= value.abs() {
+            break;
+        }
+        *root = next;
+    }
+}
+
+/// Rounds away the error left by the formulas: parts that are within a tiny distance of 0 or of a whole number are moved to it
+fn clean(x: f64, scale: f64) -> f64 {
+    if x.abs() < 1e-10 * scale {
+        0.0
+    } else if (x - x.round()).abs() < 1e-10 * x.abs().max(1.0) {
+        x.round()
+    } else {
+        x
+    }
+}
+
+/// Every root of the polynomial with the given coefficients, from the highest power down like `roots([1, -3, 2])` for
+/// `x² - 3x + 2`. Real roots are numbers, and the others are maps of their real part `re` and imaginary part `im`
+fn roots(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    let mut coefficients = vector("roots", &values[0])?;
+    while coefficients.first() == Some(&0.0) {
+        coefficients.remove(0);
+    }
+    if coefficients.is_empty() {
+        return Err("The function `roots` was given the polynomial 0, which every number is a root of!".to_string());
+    }
+    if coefficients.iter().any(|coefficient| !coefficient.is_finite()) {
+        return Err("The function `roots` expected coefficients that are finite numbers!".to_string());
+    }
+
+    // The roots at 0 are taken out first, and the others are found from the polynomial with 1 as its first coefficient
+    let mut zeros = 0;
+    while coefficients.len() > 1 && coefficients.last() == Some(&0.0) {
+        coefficients.pop();
+        zeros += 1;
+    }
+    let polynomial = coefficients.iter().map(|coefficient| Complex::real(coefficient / coefficients[0])).collect::<Vec<_>>();
+    let mut found = vec![];
+    if coefficients.len() > 1 {
+        for (factor, repeated) in square_free(&coefficients) {
+            let factor = factor.into_iter().map(Complex::real).collect::<Vec<_>>();
+            for mut root in solve(&factor)? {
+                // Only a root that is not repeated can be found more exactly from the whole polynomial
+                if repeated == 1 {
+                    polish(&polynomial, &mut root);
+                }
+                found.extend(vec![root; repeated]);
+            }
+        }
+    }
+    found.extend(vec![Complex::real(0.0); zeros]);
+
+    let scale = found.iter().map(|root| root.abs()).fold(1.0, f64::max);
+    let mut found = found.into_iter().map(|root| Complex::new(clean(root.re, scale), clean(root.im, scale))).collect::<Vec<_>>();
+    // The roots that are not real come in conjugate pairs, which are made exactly alike so that they are kept together
+    let upper = found.iter().filter(|root| root.im > 0.0).copied().collect::<Vec<_>>();
+    if upper.len() == found.iter().filter(|root| root.im < 0.0).count() {
+        found.retain(|root| root.im == 0.0);
+        found.extend(upper.iter().flat_map(|root| [Complex::new(root.re, -root.im), *root]));
+    }
+    // The real roots come first, from the smallest, followed by the others
+    found.sort_by(|a, b| (a.im != 0.0).cmp(&(b.im != 0.0)).then(a.re.total_cmp(&b.re)).then(a.im.total_cmp(&b.im)));
+    let roots = found.into_iter().map(|root| if root.im == 0.0 {
+        Value::Number(root.re)
+    } else {
+        Value::Map(BTreeMap::from([("re".to_string(), Value::Number(root.re)), ("im".to_string(), Value::Number(root.im))]))
+    }).collect();
+    Ok(Value::Array(roots))
+}
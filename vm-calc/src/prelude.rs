@@ -0,0 +1,57 @@
+use std::collections::HashMap;
+
+use crate::instruction::{Instruction, Symbol};
+
+/// The bytecode of `prelude.txt`, compiled by the build script
+static PRELUDE: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/prelude.bin"));
+
+/// The instructions of the prelude, which declare its functions
+pub fn instructions() -> Vec<Instruction<'static>> {
+    bincode::deserialize(PRELUDE).expect("The bytecode of the prelude is invalid!")
+}
+
+/// Puts the prelude before the instructions, unless they could not be compiled
+pub fn with_prelude<'a>(instructions: Vec<Instruction<'a>>) -> Vec<Instruction<'a>> {
+    if instructions.first() == Some(&Instruction::CompileError) {
+        return instructions;
+    }
+    let mut all = self::instructions();
+    all.extend(instructions);
+    all
+}
+
+/// The names of the functions in the prelude, which modules can use along with their own declarations
+pub fn names() -> Vec<&'static str> {
+    symbols().into_keys().filter_map(|symbol| match symbol {
+        Symbol::Function(name) => Some(name),
+        _ => None,
+    }).collect()
+}
+
+/// The symbols of the functions in the prelude, for the parser to check the calls to them
+pub fn symbols() -> HashMap<Symbol<'static>, usize> {
+    let instructions = instructions();
+    let mut symbols = HashMap::new();
+    let mut address = 0;
+    while let Some(instruction) = instructions.get(address) {
+        let (Instruction::FunctionDecl { name }, Some(Instruction::UData { number: arguments }), Some(Instruction::UData { number: end })) =
+            (instruction, instructions.get(address + 1), instructions.get(address + 2)) else {
+            address += 1;
+            continue;
+        };
+
+        // A rest argument comes after the others and their default values
+        let mut parameter = address + 3;
+        for _ in 0..*arguments {
+            match instructions.get(parameter) {
+                Some(Instruction::DefaultArgument { len, .. }) => parameter += 1 + len,
+                Some(Instruction::RestArgument { .. }) => { symbols.insert(Symbol::Variadic(name), 0); }
+                _ => parameter += 1,
+            }
+        }
+        let fixed = if symbols.contains_key(&Symbol::Variadic(name)) { arguments - 1 } else { *arguments };
+        symbols.insert(Symbol::Function(name), fixed);
+        address += end + 3;
+    }
+    symbols
+}
\ No newline at end of file
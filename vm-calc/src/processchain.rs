@@ -1,4 +1,6 @@
-use crate::{bytecode::Bytecode, lexer::Lexer, parser::Parser, vm::VM};
+use serde_json::json;
+
+use crate::{bytecode::Bytecode, errors::Error, formatter::Formatter, grammar, lexer::Lexer, parser::Parser, prelude, random::Random, vm::VM};
 
 // Could have been a module
 pub struct ProcessChain { }
@@ -12,38 +14,39 @@ impl ProcessChain {
                 return Err(());
             }
         };
-        Self::store_bytecode_from_text(source, path_out)?;
-        Ok(())
-    }
-
-    pub fn store_bytecode_from_text(source: &str, path: &str) -> Result<(), ()> {
         let lexer = Lexer::new(source)?;
-        let parser = Parser::new(lexer);
-        let mut bytecode_gen = Bytecode::new(parser);
+        let parser = Parser::new_fn_symbols(lexer, prelude::symbols());
+        // The imports are compiled into the bytecode, so it does not need the files of the modules to run
+        let mut bytecode_gen = Bytecode::new_in_file(parser, path_in);
         let instructions = bytecode_gen.generate_bytecode();
         let bytecode = match bincode::serialize(&instructions) {
             Ok(result) => result,
-            Err(error) => panic!("An error occured while trying to read from path {path}: {error}"),
+            Err(error) => panic!("An error occured while trying to read from path {path_out}: {error}"),
         };
-        match std::fs::write(path, bytecode) {
-            Ok(()) => println!("Wrote bytecode to path: {path}"),
-            Err(error) => panic!("An error occured while trying to read from path {path}: {error}"),
+        match std::fs::write(path_out, bytecode) {
+            Ok(()) => println!("Wrote bytecode to path: {path_out}"),
+            Err(error) => panic!("An error occured while trying to read from path {path_out}: {error}"),
         };
         Ok(())
     }
 
-    pub fn run_from_text(source: &str) -> Result<(), ()> {
+    /// The generator for the random builtins, which uses the seed when one is given
+    pub fn random(seed: Option<u64>) -> Random {
+        seed.map_or_else(Random::from_time, Random::new)
+    }
+
+    pub fn run_from_text(source: &str, seed: Option<u64>) -> Result<(), ()> {
         let lexer = Lexer::new(source)?;
-        let parser = Parser::new(lexer);
+        let parser = Parser::new_fn_symbols(lexer, prelude::symbols());
         let mut bytecode_gen = Bytecode::new(parser);
         let instructions = bytecode_gen.generate_bytecode();
-        let mut vm = VM::new(instructions);
+        let mut vm = VM::new(prelude::with_prelude(instructions)).with_prelude(prelude::names()).with_random(Self::random(seed));
         vm.execute_all();
         vm.print_output();
         Ok(())
     }
 
-    pub fn run_from_bytecode(path: &str) -> Result<(), ()>{
+    pub fn run_from_bytecode(path: &str, seed: Option<u64>) -> Result<(), ()>{
         let bytecode = match std::fs::read(path) {
             Ok(result) => result,
             Err(error) => {
@@ -59,13 +62,13 @@ impl ProcessChain {
                 return Err(());
             },
         };
-        let mut vm = VM::new(instructions);
+        let mut vm = VM::new(prelude::with_prelude(instructions)).with_prelude(prelude::names()).with_random(Self::random(seed));
         vm.execute_all();
         vm.print_output();
         Ok(())
     }
 
-    pub fn run_from_file(path: &str) -> Result<(), ()> {
+    pub fn run_from_file(path: &str, seed: Option<u64>) -> Result<(), ()> {
         let source = &match std::fs::read_to_string(path) {
             Ok(result) => result,
             Err(error) => {
@@ -74,10 +77,10 @@ impl ProcessChain {
             }
         };
         let lexer = Lexer::new(source)?;
-        let parser = Parser::new(lexer);
-        let mut bytecode_gen = Bytecode::new(parser);
+        let parser = Parser::new_fn_symbols(lexer, prelude::symbols());
+        let mut bytecode_gen = Bytecode::new_in_file(parser, path);
         let instructions = bytecode_gen.generate_bytecode();
-        let mut vm = VM::new(instructions);
+        let mut vm = VM::new(prelude::with_prelude(instructions)).with_prelude(prelude::names()).with_random(Self::random(seed));
         vm.execute_all();
         vm.print_output();
         Ok(())
@@ -92,7 +95,7 @@ impl ProcessChain {
             }
         };
         let lexer = Lexer::new(source)?;
-        let mut parser = Parser::new(lexer);
+        let mut parser = Parser::new_fn_symbols(lexer, prelude::symbols());
         loop {
             if parser.eof { break; }
             match parser.next_expression() {
@@ -112,12 +115,85 @@ impl ProcessChain {
             }
         };
         let lexer = Lexer::new(source)?;
-        let parser = Parser::new(lexer);
-        let mut bytecode_gen = Bytecode::new(parser);
+        let parser = Parser::new_fn_symbols(lexer, prelude::symbols());
+        let mut bytecode_gen = Bytecode::new_in_file(parser, path);
         let instructions = bytecode_gen.generate_bytecode();
         for instruction in instructions {
             println!("{instruction:?}");
         }
         Ok(())
     }
+
+    /// Prints the tokens of a file as JSON, including the errors the lexer ran into along the way
+    pub fn show_tokens_from_file(path: &str) -> Result<(), ()> {
+        let source = &match std::fs::read_to_string(path) {
+            Ok(result) => result,
+            Err(error) => {
+                println!("An error has occured while reading file from path {path}: {error}");
+                return Err(());
+            }
+        };
+        let mut lexer = Lexer::new(source)?;
+        let mut tokens = vec![];
+        loop {
+            match lexer.next() {
+                Ok(token) => tokens.push(json!({ 
+                    "type": token.token_type, 
+                    "text": &source[token.span.as_range()], 
+                    "span": token.span,
+                })),
+                Err(Error::TEOF) => break,
+                Err(error) => tokens.push(json!({ "error": format!("{error}"), "span": error.span() })),
+            }
+        }
+        match serde_json::to_string_pretty(&tokens) {
+            Ok(result) => println!("{result}"),
+            Err(error) => panic!("An error occured while trying to convert the tokens to JSON: {error}"),
+        }
+        Ok(())
+    }
+
+    /// Prints a TextMate grammar for the language, which editors can use to highlight code
+    pub fn show_grammar() -> Result<(), ()> {
+        match serde_json::to_string_pretty(&grammar::textmate()) {
+            Ok(result) => println!("{result}"),
+            Err(error) => panic!("An error occured while trying to convert the grammar to JSON: {error}"),
+        }
+        Ok(())
+    }
+
+    /// Formats a file and prints the result, or writes it back to the file. 
+    /// When checking, the file is only compared with the formatted code, failing if they differ
+    pub fn format_file(path: &str, check: bool, write: bool) -> Result<(), ()> {
+        let source = &match std::fs::read_to_string(path) {
+            Ok(result) => result,
+            Err(error) => {
+                println!("An error has occured while reading file from path {path}: {error}");
+                return Err(());
+            }
+        };
+        let formatted = match Formatter::new(source).format() {
+            Ok(result) => result,
+            Err(error) => {
+                println!("{error}");
+                return Err(());
+            }
+        };
+
+        if check {
+            if formatted != *source {
+                println!("The file {path} is not formatted");
+                return Err(());
+            }
+            println!("The file {path} is formatted");
+        } else if write {
+            if let Err(error) = std::fs::write(path, formatted) {
+                println!("An error occured while trying to write to path {path}: {error}");
+                return Err(());
+            }
+        } else {
+            print!("{formatted}");
+        }
+        Ok(())
+    }
 }
\ No newline at end of file
@@ -0,0 +1,98 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{functions::{expect_array, expect_number, expect_whole, ValueFunction}, instruction::Value, vm::VM};
+
+/// Whole numbers above this are not all exact as numbers, so `rand_int` cannot choose between more of them
+const MAX_RANGE: f64 = 9007199254740992.0;
+
+pub const FUNCTIONS: [(&str, (usize, ValueFunction)); 5] = [
+    ("rand", (0, rand)),
+    ("rand_int", (2, rand_int)),
+    ("choice", (1, choice)),
+    ("shuffle", (1, shuffle)),
+    ("normal", (2, normal)),
+];
+
+/// A small generator of random numbers (SplitMix64). The same seed always gives the same numbers,
+/// so that code using random numbers can be run again with the same results
+#[derive(Debug, Clone)]
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// A generator seeded by the current time, for when no seed is given
+    pub fn from_time() -> Self {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_nanos() as u64);
+        Self::new(nanos)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    /// A number from 0 up to, but not including, 1
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// A whole number from 0 up to, but not including, the bound
+    pub fn below(&mut self, bound: u64) -> u64 {
+        ((self.next_u64() as u128 * bound as u128) >> 64) as u64
+    }
+}
+
+fn rand(vm: &mut VM, _: Vec<Value>) -> Result<Value, String> {
+    Ok(Value::Number(vm.random.next_f64()))
+}
+
+/// A whole number from `a` to `b`, including both of them
+fn rand_int(vm: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    let low = expect_whole("rand_int", &values[0])?;
+    let high = expect_whole("rand_int", &values[1])?;
+    if low > high {
+        return Err(format!("The function `rand_int` expected the first number to be at most the second, but found {low} and {high}!"));
+    }
+    if high - low >= MAX_RANGE {
+        return Err(format!("The function `rand_int` can choose between at most 2^53 whole numbers, but {low} to {high} has more!"));
+    }
+    Ok(Value::Number(low + vm.random.below((high - low) as u64 + 1) as f64))
+}
+
+fn choice(vm: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    let array = expect_array("choice", &values[0])?;
+    if array.is_empty() {
+        return Err("The function `choice` cannot choose from an empty array!".to_string());
+    }
+    Ok(array[vm.random.below(array.len() as u64) as usize].clone())
+}
+
+/// The array in a random order, by the Fisher-Yates shuffle
+fn shuffle(vm: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    let mut array = expect_array("shuffle", &values[0])?.clone();
+    for index in (1..array.len()).rev() {
+        let other = vm.random.below(index as u64 + 1) as usize;
+        array.swap(index, other);
+    }
+    Ok(Value::Array(array))
+}
+
+/// A number from the normal distribution with the mean `mu` and the standard deviation `sigma`, by the Box-Muller transform
+fn normal(vm: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    let mu = expect_number("normal", &values[0])?;
+    let sigma = expect_number("normal", &values[1])?;
+    if sigma < 0.0 {
+        return Err(format!("The function `normal` expected a standard deviation that is not negative but found {sigma}!"));
+    }
+    // 1 - x is never 0, so its logarithm is never infinite
+    let (u, v) = (1.0 - vm.random.next_f64(), vm.random.next_f64());
+    Ok(Value::Number(mu + sigma * (-2.0 * u.ln()).sqrt() * (std::f64::consts::TAU * v).cos()))
+}
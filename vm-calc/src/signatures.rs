@@ -0,0 +1,124 @@
+// The names of the builtins, which the compiler checks the code against. They are kept apart from the functions
+// themselves, so that the build script can compile the prelude without the VM
+
+/// Constants that can be used like variables, but cannot be assigned to or deleted
+pub const CONSTANTS: [(&str, f64); 6] = [
+    ("pi", std::f64::consts::PI),
+    ("e", std::f64::consts::E),
+    ("tau", std::f64::consts::TAU),
+    ("inf", f64::INFINITY),
+    ("nan", f64::NAN),
+    ("phi", 1.618033988749895),
+];
+
+pub fn get_constant(key: &str) -> Result<f64, ()> {
+    for element in CONSTANTS {
+        if element.0 == key {
+            return Ok(element.1);
+        }
+    }
+    Err(())
+}
+
+/// The names and number of arguments of every builtin function, other than `print`.
+/// Each of them is given by `functions::get_function` or `functions::get_value_function`, with the same number of arguments
+pub const BUILTINS: [(&str, usize); 88] = [
+    ("cos", 1),
+    ("sin", 1),
+    ("tan", 1),
+    ("abs", 1),
+    ("acos", 1),
+    ("cosh", 1),
+    ("acosh", 1),
+    ("asin", 1),
+    ("atan", 1),
+    ("asinh", 1),
+    ("atanh", 1),
+    ("cbrt", 1),
+    ("ceil", 1),
+    ("floor", 1),
+    ("log10", 1),
+    ("log2", 1),
+    ("ln", 1),
+    ("round", 1),
+    ("sqrt", 1),
+    ("to_radians", 1),
+    ("to_degrees", 1),
+    ("exp", 1),
+    ("trunc", 1),
+    ("sign", 1),
+    ("log", 2),
+    ("floor_div", 2),
+    ("is_prime", 1),
+    ("len", 1),
+    ("upper", 1),
+    ("lower", 1),
+    ("trim", 1),
+    ("split", 2),
+    ("join", 2),
+    ("replace", 3),
+    ("find", 2),
+    ("slice", 3),
+    ("num", 1),
+    ("str", 1),
+    ("push", 2),
+    ("pop", 1),
+    ("insert", 3),
+    ("remove", 2),
+    ("concat", 2),
+    ("reverse", 1),
+    ("sort", 1),
+    ("range", 3),
+    ("map", 2),
+    ("filter", 2),
+    ("reduce", 3),
+    ("zip", 2),
+    ("keys", 1),
+    ("values", 1),
+    ("has", 2),
+    ("gcd", 2),
+    ("lcm", 2),
+    ("factorial", 1),
+    ("choose", 2),
+    ("dot", 2),
+    ("matmul", 2),
+    ("transpose", 1),
+    ("det", 1),
+    ("inv", 1),
+    ("identity", 1),
+    ("linsolve", 2),
+    ("mean", 1),
+    ("median", 1),
+    ("mode", 1),
+    ("variance", 1),
+    ("stddev", 1),
+    ("percentile", 2),
+    ("min", 1),
+    ("max", 1),
+    ("sum", 1),
+    ("product", 1),
+    ("cumsum", 1),
+    ("histogram", 2),
+    ("linreg", 2),
+    ("rand", 0),
+    ("rand_int", 2),
+    ("choice", 1),
+    ("shuffle", 1),
+    ("normal", 2),
+    ("deriv", 2),
+    ("integrate", 3),
+    ("solve", 2),
+    ("minimize", 3),
+    ("roots", 1),
+    ("chart", 4),
+];
+
+/// The number of arguments a builtin function takes, whether it works with numbers or any value
+pub fn get_builtin_arguments(key: &str) -> Result<usize, ()> {
+    for element in BUILTINS {
+        if element.0 == key {
+            return Ok(element.1);
+        }
+    }
+    Err(())
+}
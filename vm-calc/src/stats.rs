@@ -0,0 +1,170 @@
+use std::collections::BTreeMap;
+
+use crate::{functions::{expect_number, expect_position, ValueFunction}, instruction::Value, matrix::vector, vm::VM};
+
+pub const FUNCTIONS: [(&str, (usize, ValueFunction)); 13] = [
+    ("mean", (1, mean)),
+    ("median", (1, median)),
+    ("mode", (1, mode)),
+    ("variance", (1, variance)),
+    ("stddev", (1, stddev)),
+    ("percentile", (2, percentile)),
+    ("min", (1, min)),
+    ("max", (1, max)),
+    ("sum", (1, sum)),
+    ("product", (1, product)),
+    ("cumsum", (1, cumsum)),
+    ("histogram", (2, histogram)),
+    ("linreg", (2, linreg)),
+];
+
+/// How many bins `histogram` can have for each number, which keeps a mistyped number of bins from using all of the memory
+const BINS_PER_NUMBER: usize = 1000;
+
+/// Reads an array of numbers that has at least one element, for the functions that have no answer for an empty array
+fn sample(function: &str, value: &Value) -> Result<Vec<f64>, String> {
+    let numbers = vector(function, value)?;
+    if numbers.is_empty() {
+        return Err(format!("The function `{function}` cannot be used on an empty array!"));
+    }
+    Ok(numbers)
+}
+
+/// The numbers in order, with NaN last
+fn sorted(mut numbers: Vec<f64>) -> Vec<f64> {
+    numbers.sort_by(f64::total_cmp);
+    numbers
+}
+
+fn average(numbers: &[f64]) -> f64 {
+    numbers.iter().sum::<f64>() / numbers.len() as f64
+}
+
+/// The variance of the population, the mean of the squared distances from the mean
+fn population_variance(numbers: &[f64]) -> f64 {
+    let mean = average(numbers);
+    numbers.iter().map(|number| (number - mean).powi(2)).sum::<f64>() / numbers.len() as f64
+}
+
+fn mean(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    Ok(Value::Number(average(&sample("mean", &values[0])?)))
+}
+
+/// The middle number, or the mean of the two middle numbers when there is an even amount of them
+fn median(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    let numbers = sorted(sample("median", &values[0])?);
+    let middle = numbers.len() / 2;
+    if numbers.len() % 2 == 1 {
+        Ok(Value::Number(numbers[middle]))
+    } else {
+        Ok(Value::Number((numbers[middle - 1] + numbers[middle]) / 2.0))
+    }
+}
+
+/// The most common number. When several are as common, the smallest of them is used
+fn mode(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    let numbers = sorted(sample("mode", &values[0])?);
+    let (mut best, mut best_count) = (numbers[0], 0);
+    for run in numbers.chunk_by(|a, b| a == b) {
+        if run.len() > best_count {
+            (best, best_count) = (run[0], run.len());
+        }
+    }
+    Ok(Value::Number(best))
+}
+
+fn variance(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    Ok(Value::Number(population_variance(&sample("variance", &values[0])?)))
+}
+
+fn stddev(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    Ok(Value::Number(population_variance(&sample("stddev", &values[0])?).sqrt()))
+}
+
+/// The number below which the given percent of the numbers fall, interpolating between the two closest numbers
+fn percentile(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    let numbers = sorted(sample("percentile", &values[0])?);
+    let percent = expect_number("percentile", &values[1])?;
+    if !(0.0..=100.0).contains(&percent) {
+        return Err(format!("The function `percentile` expected a percent from 0 to 100 but found {percent}!"));
+    }
+    let position = percent / 100.0 * (numbers.len() - 1) as f64;
+    let (below, above) = (numbers[position.floor() as usize], numbers[position.ceil() as usize]);
+    Ok(Value::Number(below + (above - below) * position.fract()))
+}
+
+fn min(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    Ok(Value::Number(sample("min", &values[0])?.into_iter().fold(f64::INFINITY, f64::min)))
+}
+
+fn max(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    Ok(Value::Number(sample("max", &values[0])?.into_iter().fold(f64::NEG_INFINITY, f64::max)))
+}
+
+/// The total of the numbers, which starts from 0 rather than the -0 that `Iterator::sum` gives for an empty array
+fn sum(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    Ok(Value::Number(vector("sum", &values[0])?.into_iter().fold(0.0, |total, number| total + number)))
+}
+
+fn product(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    Ok(Value::Number(vector("product", &values[0])?.iter().product()))
+}
+
+/// The running totals of the numbers, so that `cumsum([1, 2, 3])` is `[1, 3, 6]`
+fn cumsum(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    let totals = vector("cumsum", &values[0])?.into_iter()
+        .scan(0.0, |total, number| { *total += number; Some(Value::Number(*total)) })
+        .collect();
+    Ok(Value::Array(totals))
+}
+
+/// Counts the numbers in bins of the same width from the smallest number to the largest. A number on the edge
+/// between two bins is counted in the higher one, other than the largest number, which is in the last bin
+fn histogram(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    let numbers = sample("histogram", &values[0])?;
+    let bins = expect_position("histogram", &values[1])?;
+    if bins == 0 {
+        return Err("The function `histogram` needs at least one bin!".to_string());
+    }
+    if bins > numbers.len() * BINS_PER_NUMBER {
+        return Err(format!("The function `histogram` can have at most {} bins for {} numbers, but was given {bins}!", numbers.len() * BINS_PER_NUMBER, numbers.len()));
+    }
+    let low = numbers.iter().copied().fold(f64::INFINITY, f64::min);
+    let high = numbers.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let width = (high - low) / bins as f64;
+
+    let mut counts = vec![0.0; bins];
+    for number in numbers {
+        let bin = if width == 0.0 { 0 } else { (((number - low) / width) as usize).min(bins - 1) };
+        counts[bin] += 1.0;
+    }
+    Ok(Value::Array(counts.into_iter().map(Value::Number).collect()))
+}
+
+/// Fits a line to the points by least squares, giving a map with its `slope` and `intercept`, along with `r2`,
+/// the part of the variance of `ys` that the line explains
+fn linreg(_: &mut VM, values: Vec<Value>) -> Result<Value, String> {
+    let xs = sample("linreg", &values[0])?;
+    let ys = sample("linreg", &values[1])?;
+    if xs.len() != ys.len() {
+        return Err(format!("The function `linreg` expected arrays of the same length but found lengths {} and {}!", xs.len(), ys.len()));
+    }
+    let (mean_x, mean_y) = (average(&xs), average(&ys));
+    let covariance = xs.iter().zip(&ys).map(|(x, y)| (x - mean_x) * (y - mean_y)).sum::<f64>();
+    let spread_x = xs.iter().map(|x| (x - mean_x).powi(2)).sum::<f64>();
+    let spread_y = ys.iter().map(|y| (y - mean_y).powi(2)).sum::<f64>();
+    if spread_x == 0.0 {
+        return Err("The function `linreg` cannot fit a line when every x is the same!".to_string());
+    }
+
+    let slope = covariance / spread_x;
+    let intercept = mean_y - slope * mean_x;
+    // A flat line through points that are all the same fits them exactly
+    let r2 = if spread_y == 0.0 { 1.0 } else { covariance * covariance / (spread_x * spread_y) };
+    let map = BTreeMap::from([
+        ("slope".to_string(), Value::Number(slope)),
+        ("intercept".to_string(), Value::Number(intercept)),
+        ("r2".to_string(), Value::Number(r2)),
+    ]);
+    Ok(Value::Map(map))
+}
@@ -0,0 +1,350 @@
+use std::rc::Rc;
+
+use crate::{ast::{Operator, Tree, AST}, utils::Span};
+
+/// The derivative of the expression by the variable, simplified. Only numbers, names, arithmetic and the builtin
+/// functions of one number (along with `log`) can be differentiated, the other names are taken as constants
+pub fn differentiate<'a>(tree: &Rc<Tree<'a>>, variable: &'a str) -> Result<Rc<Tree<'a>>, String> {
+    Ok(simplify(&derivative(&simplify(tree), variable)?))
+}
+
+fn node(ast: AST<'_>) -> Rc<Tree<'_>> {
+    Rc::new(Tree::new(ast, Span::null()))
+}
+
+fn number<'a>(value: f64) -> Rc<Tree<'a>> {
+    node(AST::Number { value })
+}
+
+fn binary<'a>(lhs: Rc<Tree<'a>>, op: Operator, rhs: Rc<Tree<'a>>) -> Rc<Tree<'a>> {
+    node(AST::BinaryOp { lhs, rhs, op })
+}
+
+fn negate(rhs: Rc<Tree<'_>>) -> Rc<Tree<'_>> {
+    node(AST::UnaryOp { rhs, op: Operator::Minus })
+}
+
+fn call<'a>(name: &'a str, expressions: Vec<Rc<Tree<'a>>>) -> Rc<Tree<'a>> {
+    node(AST::FunctionCall { name: node(AST::Name { value: name }), expressions })
+}
+
+/// Whether the expression changes with the variable
+fn depends(tree: &Tree, variable: &str) -> bool {
+    match &tree.ast {
+        AST::Identifier { name } => *name == variable,
+        AST::BinaryOp { lhs, rhs, .. } => depends(lhs, variable) || depends(rhs, variable),
+        AST::UnaryOp { rhs, .. } => depends(rhs, variable),
+        AST::FunctionCall { expressions, .. } => expressions.iter().any(|expression| depends(expression, variable)),
+        _ => false,
+    }
+}
+
+fn derivative<'a>(tree: &Rc<Tree<'a>>, variable: &'a str) -> Result<Rc<Tree<'a>>, String> {
+    if !depends(tree, variable) {
+        return match &tree.ast {
+            AST::Number { .. } | AST::Identifier { .. } | AST::BinaryOp { .. } | AST::UnaryOp { .. } | AST::FunctionCall { .. } => Ok(number(0.0)),
+            _ => Err(format!("Cannot differentiate `{tree}`, only numbers, names, arithmetic and builtin functions can be differentiated!")),
+        };
+    }
+
+    let result = match &tree.ast {
+        AST::Identifier { .. } => number(1.0),
+        AST::UnaryOp { rhs, op } => match op {
+            Operator::Minus => negate(derivative(rhs, variable)?),
+            _ => derivative(rhs, variable)?,
+        },
+
+        AST::BinaryOp { lhs, rhs, op } => {
+            let (u, v) = (lhs.clone(), rhs.clone());
+            match op {
+                Operator::Plus | Operator::Minus => binary(derivative(&u, variable)?, *op, derivative(&v, variable)?),
+                // (uv)' = u'v + uv'
+                Operator::Multiply => binary(
+                    binary(derivative(&u, variable)?, Operator::Multiply, v.clone()),
+                    Operator::Plus,
+                    binary(u.clone(), Operator::Multiply, derivative(&v, variable)?),
+                ),
+                // (u/c)' = u'/c when the divisor is constant
+                Operator::Divide if !depends(&v, variable) => binary(derivative(&u, variable)?, Operator::Divide, v.clone()),
+                // (u/v)' = (u'v - uv') / v²
+                Operator::Divide => binary(
+                    binary(
+                        binary(derivative(&u, variable)?, Operator::Multiply, v.clone()),
+                        Operator::Minus,
+                        binary(u.clone(), Operator::Multiply, derivative(&v, variable)?),
+                    ),
+                    Operator::Divide,
+                    binary(v.clone(), Operator::Exponent, number(2.0)),
+                ),
+                // (u^n)' = n u^(n - 1) u' when the exponent is constant, (a^v)' = a^v ln(a) v' when the base is,
+                // and otherwise (u^v)' = u^v (v' ln(u) + v u' / u)
+                Operator::Exponent if !depends(&v, variable) => binary(
+                    binary(v.clone(), Operator::Multiply, binary(u.clone(), Operator::Exponent, binary(v.clone(), Operator::Minus, number(1.0)))),
+                    Operator::Multiply,
+                    derivative(&u, variable)?,
+                ),
+                Operator::Exponent if !depends(&u, variable) => binary(
+                    binary(tree.clone(), Operator::Multiply, call("ln", vec![u.clone()])),
+                    Operator::Multiply,
+                    derivative(&v, variable)?,
+                ),
+                Operator::Exponent => binary(
+                    tree.clone(),
+                    Operator::Multiply,
+                    binary(
+                        binary(derivative(&v, variable)?, Operator::Multiply, call("ln", vec![u.clone()])),
+                        Operator::Plus,
+                        binary(v.clone(), Operator::Multiply, binary(derivative(&u, variable)?, Operator::Divide, u.clone())),
+                    ),
+                ),
+                op => return Err(format!("Cannot differentiate the operator `{op}`!")),
+            }
+        }
+
+        AST::FunctionCall { name, expressions } => {
+            let AST::Name { value: function } = name.ast else {
+                return Err(format!("Cannot differentiate `{tree}`, only builtin functions can be differentiated!"));
+            };
+            if function == "log" && expressions.len() == 2 {
+                // log(b, u) = ln(u) / ln(b)
+                let quotient = binary(call("ln", vec![expressions[1].clone()]), Operator::Divide, call("ln", vec![expressions[0].clone()]));
+                return derivative(&quotient, variable);
+            }
+            let [u] = &expressions[..] else {
+                return Err(format!("Cannot differentiate the function `{function}`!"));
+            };
+            // The chain rule: f(u)' = f'(u) u'
+            binary(outer_derivative(function, u.clone())?, Operator::Multiply, derivative(u, variable)?)
+        }
+
+        _ => return Err(format!("Cannot differentiate `{tree}`, only numbers, names, arithmetic and builtin functions can be differentiated!")),
+    };
+    Ok(result)
+}
+
+/// The derivative of a builtin function of one number, at `u`
+fn outer_derivative<'a>(function: &str, u: Rc<Tree<'a>>) -> Result<Rc<Tree<'a>>, String> {
+    let one_over = |denominator| binary(number(1.0), Operator::Divide, denominator);
+    let square = |u: &Rc<Tree<'a>>| binary(u.clone(), Operator::Exponent, number(2.0));
+    let result = match function {
+        "sin" => call("cos", vec![u]),
+        "cos" => negate(call("sin", vec![u])),
+        "tan" => one_over(square(&call("cos", vec![u]))),
+        "asin" => one_over(call("sqrt", vec![binary(number(1.0), Operator::Minus, square(&u))])),
+        "acos" => negate(one_over(call("sqrt", vec![binary(number(1.0), Operator::Minus, square(&u))]))),
+        "atan" => one_over(binary(number(1.0), Operator::Plus, square(&u))),
+        "exp" => call("exp", vec![u]),
+        "ln" => one_over(u),
+        "log10" => one_over(binary(u, Operator::Multiply, call("ln", vec![number(10.0)]))),
+        "log2" => one_over(binary(u, Operator::Multiply, call("ln", vec![number(2.0)]))),
+        "sqrt" => one_over(binary(number(2.0), Operator::Multiply, call("sqrt", vec![u]))),
+        "cbrt" => one_over(binary(number(3.0), Operator::Multiply, square(&call("cbrt", vec![u])))),
+        "abs" => call("sign", vec![u]),
+        _ => return Err(format!("Cannot differentiate the function `{function}`!")),
+    };
+    Ok(result)
+}
+
+/// Whether two expressions are written the same way, regardless of where they are in the code
+fn same(a: &Tree, b: &Tree) -> bool {
+    a.to_string() == b.to_string()
+}
+
+fn value(tree: &Tree) -> Option<f64> {
+    match tree.ast {
+        AST::Number { value } => Some(value),
+        _ => None,
+    }
+}
+
+/// Simplifies the expression from the bottom up: numbers are worked out, and adding 0, multiplying by 1 or 0
+/// and the like are removed. Numbers are moved in front of products, so that `x * 2 * 3` becomes `6 * x`
+pub fn simplify<'a>(tree: &Rc<Tree<'a>>) -> Rc<Tree<'a>> {
+    match &tree.ast {
+        AST::BinaryOp { lhs, rhs, op } => simplify_binary(simplify(lhs), *op, simplify(rhs)),
+        AST::UnaryOp { rhs, op: Operator::Minus } => {
+            let rhs = simplify(rhs);
+            match &rhs.ast {
+                AST::Number { value } => number(-value),
+                AST::UnaryOp { rhs, op: Operator::Minus } => rhs.clone(),
+                // -(a * b) is written as (-a) * b when a is a number
+                AST::BinaryOp { lhs, rhs: inner, op: Operator::Multiply } if value(lhs).is_some() => {
+                    binary(number(-value(lhs).unwrap()), Operator::Multiply, inner.clone())
+                }
+                _ => negate(rhs),
+            }
+        }
+        AST::UnaryOp { rhs, .. } => simplify(rhs),
+        AST::FunctionCall { name, expressions } => node(AST::FunctionCall { name: name.clone(), expressions: expressions.iter().map(simplify).collect() }),
+        _ => tree.clone(),
+    }
+}
+
+fn simplify_binary<'a>(lhs: Rc<Tree<'a>>, op: Operator, rhs: Rc<Tree<'a>>) -> Rc<Tree<'a>> {
+    let (a, b) = (value(&lhs), value(&rhs));
+    if let (Some(a), Some(b)) = (a, b) {
+        let result = match op {
+            Operator::Plus => Some(a + b),
+            Operator::Minus => Some(a - b),
+            Operator::Multiply => Some(a * b),
+            Operator::Divide => Some(a / b),
+            Operator::Exponent => Some(a.powf(b)),
+            _ => None,
+        };
+        // Divisions like 1 / 3 are kept as they are rather than written as a long decimal
+        if let Some(result) = result.filter(|result| result.is_finite() && (op != Operator::Divide || result.fract() == 0.0)) {
+            return number(result);
+        }
+    }
+
+    match op {
+        Operator::Plus if a == Some(0.0) => rhs,
+        Operator::Plus | Operator::Minus if b == Some(0.0) => lhs,
+        Operator::Minus if a == Some(0.0) => simplify(&negate(rhs)),
+        Operator::Plus if same(&lhs, &rhs) => simplify_binary(number(2.0), Operator::Multiply, lhs),
+        Operator::Minus if same(&lhs, &rhs) => number(0.0),
+        // a + (-b) = a - b and a - (-b) = a + b
+        Operator::Plus | Operator::Minus if matches!(rhs.ast, AST::UnaryOp { op: Operator::Minus, .. }) => {
+            let AST::UnaryOp { rhs: inner, .. } = &rhs.ast else { unreachable!() };
+            let op = if op == Operator::Plus { Operator::Minus } else { Operator::Plus };
+            simplify_binary(lhs, op, inner.clone())
+        }
+        Operator::Plus | Operator::Minus if b.is_some_and(|b| b < 0.0) => {
+            let op = if op == Operator::Plus { Operator::Minus } else { Operator::Plus };
+            binary(lhs, op, number(-b.unwrap()))
+        }
+
+        Operator::Multiply if a == Some(0.0) || b == Some(0.0) => number(0.0),
+        Operator::Multiply if a == Some(1.0) => rhs,
+        Operator::Multiply if b == Some(1.0) => lhs,
+        Operator::Multiply if a == Some(-1.0) => simplify(&negate(rhs)),
+        Operator::Multiply if b == Some(-1.0) => simplify(&negate(lhs)),
+        // Numbers go in front, and are combined with the number in front of the other side
+        Operator::Multiply if b.is_some() => simplify_binary(rhs, op, lhs),
+        Operator::Multiply if a.is_some() => match &rhs.ast {
+            AST::BinaryOp { lhs: inner, rhs: rest, op: Operator::Multiply } if value(inner).is_some() => {
+                simplify_binary(number(a.unwrap() * value(inner).unwrap()), Operator::Multiply, rest.clone())
+            }
+            _ => binary(lhs, op, rhs),
+        },
+        Operator::Multiply if matches!(&lhs.ast, AST::BinaryOp { lhs: inner, op: Operator::Multiply, .. } if value(inner).is_some()) => {
+            let AST::BinaryOp { lhs: inner, rhs: rest, .. } = &lhs.ast else { unreachable!() };
+            simplify_binary(inner.clone(), Operator::Multiply, simplify_binary(rest.clone(), Operator::Multiply, rhs))
+        }
+        Operator::Multiply if same(&lhs, &rhs) => binary(lhs, Operator::Exponent, number(2.0)),
+        // a * (1 / b) = a / b
+        Operator::Multiply if matches!(&rhs.ast, AST::BinaryOp { lhs: one, op: Operator::Divide, .. } if value(one) == Some(1.0)) => {
+            let AST::BinaryOp { rhs: divisor, .. } = &rhs.ast else { unreachable!() };
+            simplify_binary(lhs, Operator::Divide, divisor.clone())
+        }
+        Operator::Multiply if matches!(&lhs.ast, AST::BinaryOp { lhs: one, op: Operator::Divide, .. } if value(one) == Some(1.0)) => {
+            let AST::BinaryOp { rhs: divisor, .. } = &lhs.ast else { unreachable!() };
+            simplify_binary(rhs, Operator::Divide, divisor.clone())
+        }
+
+        Operator::Divide if a == Some(0.0) => number(0.0),
+        Operator::Divide if b == Some(1.0) => lhs,
+        Operator::Divide if same(&lhs, &rhs) => number(1.0),
+        // (n * a) / m = (n / m) * a when n and m are numbers
+        Operator::Divide if b.is_some() && matches!(&lhs.ast, AST::BinaryOp { lhs: factor, op: Operator::Multiply, .. } if value(factor).is_some()) => {
+            let AST::BinaryOp { lhs: factor, rhs: rest, .. } = &lhs.ast else { unreachable!() };
+            simplify_binary(number(value(factor).unwrap() / b.unwrap()), Operator::Multiply, rest.clone())
+        }
+        // (n / a) / b = n / (a * b) when n is a number
+        Operator::Divide if matches!(&lhs.ast, AST::BinaryOp { lhs: numerator, op: Operator::Divide, .. } if value(numerator).is_some()) => {
+            let AST::BinaryOp { lhs: numerator, rhs: divisor, .. } = &lhs.ast else { unreachable!() };
+            binary(numerator.clone(), Operator::Divide, simplify_binary(divisor.clone(), Operator::Multiply, rhs))
+        }
+
+        Operator::Exponent if b == Some(0.0) => number(1.0),
+        Operator::Exponent if b == Some(1.0) => lhs,
+        Operator::Exponent if a == Some(1.0) => number(1.0),
+        // (u^a)^b = u^(ab) when both exponents are numbers
+        Operator::Exponent if b.is_some() && matches!(&lhs.ast, AST::BinaryOp { rhs: inner, op: Operator::Exponent, .. } if value(inner).is_some()) => {
+            let AST::BinaryOp { lhs: base, rhs: inner, .. } = &lhs.ast else { unreachable!() };
+            simplify_binary(base.clone(), Operator::Exponent, number(value(inner).unwrap() * b.unwrap()))
+        }
+
+        op => binary(lhs, op, rhs),
+    }
+}
+
+/// The highest power of a polynomial that can be solved for, which keeps the expanded expressions small
+const MAX_DEGREE: usize = 64;
+
+/// The coefficients of the expression as a polynomial in the variable, from the lowest power up.
+/// The coefficients are expressions themselves, since they can use other names
+pub fn polynomial<'a>(tree: &Rc<Tree<'a>>, variable: &'a str) -> Result<Vec<Rc<Tree<'a>>>, String> {
+    if !depends(tree, variable) {
+        return Ok(vec![simplify(tree)]);
+    }
+    let not_polynomial = || format!("`{tree}` is not a polynomial in `{variable}`!");
+    let too_high = || format!("`{tree}` has a higher degree than {MAX_DEGREE} in `{variable}`!");
+
+    let coefficients = match &tree.ast {
+        AST::Identifier { .. } => vec![number(0.0), number(1.0)],
+        AST::UnaryOp { rhs, op: Operator::Minus } => polynomial(rhs, variable)?.iter().map(|coefficient| simplify(&negate(coefficient.clone()))).collect(),
+        AST::UnaryOp { rhs, .. } => polynomial(rhs, variable)?,
+
+        AST::BinaryOp { lhs, rhs, op: op @ (Operator::Plus | Operator::Minus) } => {
+            let (lhs, rhs) = (polynomial(lhs, variable)?, polynomial(rhs, variable)?);
+            (0..lhs.len().max(rhs.len())).map(|power| {
+                let term = |side: &[Rc<Tree<'a>>]| side.get(power).cloned().unwrap_or_else(|| number(0.0));
+                simplify_binary(term(&lhs), *op, term(&rhs))
+            }).collect()
+        }
+        AST::BinaryOp { lhs, rhs, op: Operator::Multiply } => multiply(&polynomial(lhs, variable)?, &polynomial(rhs, variable)?),
+        AST::BinaryOp { lhs, rhs, op: Operator::Divide } if !depends(rhs, variable) => {
+            polynomial(lhs, variable)?.into_iter().map(|coefficient| simplify_binary(coefficient, Operator::Divide, simplify(rhs))).collect()
+        }
+        AST::BinaryOp { lhs, rhs, op: Operator::Exponent } => {
+            let power = match simplify(rhs).ast {
+                AST::Number { value } if value >= 0.0 && value.fract() == 0.0 => value as usize,
+                _ => return Err(not_polynomial()),
+            };
+            // The degree is checked before the power is expanded, which would take too long for a high one
+            let base = polynomial(lhs, variable)?;
+            if (base.len() - 1).saturating_mul(power) > MAX_DEGREE {
+                return Err(too_high());
+            }
+            (0..power).fold(vec![number(1.0)], |product, _| multiply(&product, &base))
+        }
+        _ => return Err(not_polynomial()),
+    };
+    if coefficients.len() > MAX_DEGREE + 1 {
+        return Err(too_high());
+    }
+    Ok(coefficients)
+}
+
+/// The coefficients of the product of two polynomials
+fn multiply<'a>(lhs: &[Rc<Tree<'a>>], rhs: &[Rc<Tree<'a>>]) -> Vec<Rc<Tree<'a>>> {
+    let mut product = vec![number(0.0); lhs.len() + rhs.len() - 1];
+    for (i, a) in lhs.iter().enumerate() {
+        for (j, b) in rhs.iter().enumerate() {
+            product[i + j] = simplify_binary(product[i + j].clone(), Operator::Plus, simplify_binary(a.clone(), Operator::Multiply, b.clone()));
+        }
+    }
+    product
+}
+
+/// The coefficients of a linear expression in the variables, followed by the part that does not use any of them
+pub fn linear<'a>(tree: &Rc<Tree<'a>>, variables: &[&'a str]) -> Result<Vec<Rc<Tree<'a>>>, String> {
+    let mut coefficients = vec![];
+    let mut constant = tree.clone();
+    for variable in variables {
+        let terms = polynomial(&constant, variable)?;
+        let [rest, coefficient] = match &terms[..] {
+            [rest] => [rest.clone(), number(0.0)],
+            [rest, coefficient] => [rest.clone(), coefficient.clone()],
+            _ => return Err(format!("`{tree}` is not linear in `{variable}`!")),
+        };
+        if let Some(other) = variables.iter().find(|other| depends(&coefficient, other)) {
+            return Err(format!("`{tree}` is not linear, since it multiplies `{variable}` by `{other}`!"));
+        }
+        coefficients.push(coefficient);
+        constant = rest;
+    }
+    coefficients.push(constant);
+    Ok(coefficients)
+}
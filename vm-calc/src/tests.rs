@@ -1,2 +1,5 @@
 mod test_lexer;
-mod test_parser;
\ No newline at end of file
+mod test_parser;
+mod test_formatter;
+mod test_lsp;
+mod test_vm;
\ No newline at end of file
@@ -0,0 +1,44 @@
+#[cfg(test)]
+mod tests {
+    use crate::formatter::Formatter;
+
+    fn format(input: &str) -> String {
+        Formatter::new(input).format().unwrap()
+    }
+
+    #[test]
+    fn brackets() {
+        assert_eq!(format("((1+2)*3);"), "(1 + 2) * 3;\n");
+        assert_eq!(format("1+(2*3):"), "1 + 2 * 3:\n");
+        assert_eq!(format("(1-2)-3;1-(2-3);"), "1 - 2 - 3;\n1 - (2 - 3);\n");
+        assert_eq!(format("-(1+2)**2;"), "-(1 + 2) ** 2;\n");
+        assert_eq!(format("(a[0])(1)(2);(a)(1);"), "a[0](1)(2);\n(a)(1);\n");
+        assert_eq!(format("let  a=(let b=5)+1;"), "let a = (let b = 5) + 1;\n");
+    }
+
+    #[test]
+    fn literals() {
+        assert_eq!(format("0xff+0b1;\"a\\n\\\"b\":"), "0xff + 0b1;\n\"a\\n\\\"b\":\n");
+        assert_eq!(format("let f _=print(1,2,);f():"), "let f _ = print(1, 2);\nf():\n");
+        assert_eq!(format("{\"a\":1,\"b\" :{}};m . a=m[\"b\"].c;"), "{\"a\": 1, \"b\": {}};\nm.a = m[\"b\"].c;\n");
+        assert_eq!(format("match x{[a,b] if a<b=>1,n:Number=>-1,_=>(1==2)+1};"), "match x { [a, b] if a < b => 1, n: Number => -1, _ => (1 == 2) + 1 };\n");
+        assert_eq!(format("map(\\x->x*2,a);(\\_ ->1)();(\\x y->x)+1;"), "map(\\x -> x * 2, a);\n(\\_ -> 1)();\n(\\x y -> x) + 1;\n");
+        assert_eq!(format("let f x(y=x+1)=x*y;f(1,y :2);"), "let f x (y = x + 1) = x * y;\nf(1, y: 2);\n");
+        assert_eq!(format("let f x...xs=xs;(\\...xs->xs)(1,2);"), "let f x ...xs = xs;\n(\\...xs -> xs)(1, 2);\n");
+        assert_eq!(format("import\"lib/a.txt\"as m;import  \"b.txt\";m.f (1):"), "import \"lib/a.txt\" as m;\nimport \"b.txt\";\nm.f(1):\n");
+        assert_eq!(format("let [a,[b ,_]]=x;[a,b]=[b,a];"), "let [a, [b, _]] = x;\n[a, b] = [b, a];\n");
+        assert_eq!(format("a[ 1 :-1 ];a[::2];a[i+1:] = [1];"), "a[1:-1];\na[::2];\na[i + 1:] = [1];\n");
+        assert_eq!(format("f\"a\\n{{ {x+1:>03} {f\"{y:}\"}\";"), "f\"a\\n{{ {x + 1:>03} {f\"{y}\"}\";\n");
+        assert_eq!(format("diff((x**2)+sin(x),x):"), "diff(x ** 2 + sin(x), x):\n");
+        assert_eq!(format("solve x**2=4 for x:solve[x+y=1,x-y=1]for[x,y];"), "solve x ** 2 = 4 for x:\nsolve [x + y = 1, x - y = 1] for [x, y];\n");
+    }
+
+    #[test]
+    fn comments() {
+        let input = "// Start\n\n1+1:   // Two\n\n\n// Between\nlet a=\"// not a comment\";\n2 + // inside\n 3;\n// End";
+        let output = "// Start\n\n1 + 1: // Two\n\n// Between\nlet a = \"// not a comment\";\n2 + // inside\n 3;\n// End\n";
+        assert_eq!(format(input), output);
+        // Formatting is idempotent
+        assert_eq!(format(output), output);
+    }
+}
\ No newline at end of file
@@ -1,7 +1,6 @@
-#[cfg(test)]
-
 // These are just tests. Nothing to see here, that is if physical laws are still the same. I hope they are, at least.
 // If not, this place is gonna need renovation.
+#[cfg(test)]
 mod tests {
     use crate::{tokens::{NumberType, Token, TokenType}, errors::Error, lexer, utils::Span};
     
@@ -109,7 +108,7 @@ mod tests {
 
     #[test]
     fn test_operators_and_delimiters() -> Result<(), ()>{
-        let mut lexer = lexer::Lexer::new("* *= ** **= + += - -= = [ ( ) ] ; / /= << <<= >> >>= & &= | |= ^ ^=")?;
+        let mut lexer = lexer::Lexer::new("* *= ** **= + += - -= = [ ( ) ] ; / /= << <<= >> >>= & &= | |= ^ ^= == != < <= > >= => { } . \\ -> ...")?;
         use TokenType::*;
         let list = 
             [
@@ -139,6 +138,19 @@ mod tests {
                 (BitOrEqual, 2),
                 (BitXor, 1),
                 (BitXorEqual, 2),
+                (IsEqual, 2),
+                (NotEqual, 2),
+                (Less, 1),
+                (LessEqual, 2),
+                (Greater, 1),
+                (GreaterEqual, 2),
+                (FatArrow, 2),
+                (OpenMap, 1),
+                (CloseMap, 1),
+                (Dot, 1),
+                (Backslash, 1),
+                (Arrow, 2),
+                (Ellipsis, 3),
             ];
         let mut idx = 0;
         for (item, len) in list {
@@ -153,7 +165,7 @@ mod tests {
 
     #[test]
     fn test_invalid() -> Result<(), ()>{
-        let mut lexer = lexer::Lexer::new("<\\!>")?;
+        let mut lexer = lexer::Lexer::new("?'!?")?;
         assert_eq!(
             lexer.next(), 
             Err(Error::TInvalidCharacter { location: 0 })
@@ -181,4 +193,40 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_symbols() -> Result<(), ()> {
+        // The grammar relies on every symbol being written the way it is displayed
+        for token_type in TokenType::SYMBOLS {
+            let source = format!("{token_type}");
+            let mut lexer = lexer::Lexer::new(&source)?;
+            assert_eq!(lexer.next(), Ok(Token::new(token_type, Span::new(0, source.len()))));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_strings() -> Result<(), ()> {
+        let source = r#"f"a {x[1]:>5} {{b}} {f"{y}"}" f"{"#;
+        let mut lexer = lexer::Lexer::new(source)?;
+        let expected = [
+            (TokenType::FormatStart, 0, 2), (TokenType::FormatText, 2, 4), (TokenType::InterpolationStart, 4, 5),
+            (TokenType::Identifier, 5, 6), (TokenType::OpenArray, 6, 7), (TokenType::Number { number_type: NumberType::Real }, 7, 8),
+            (TokenType::CloseArray, 8, 9), (TokenType::FormatSpec, 9, 12), (TokenType::InterpolationEnd, 12, 13),
+            (TokenType::FormatText, 13, 20), (TokenType::InterpolationStart, 20, 21), (TokenType::FormatStart, 21, 23),
+            (TokenType::InterpolationStart, 23, 24), (TokenType::Identifier, 24, 25), (TokenType::InterpolationEnd, 25, 26),
+            (TokenType::FormatEnd, 26, 27), (TokenType::InterpolationEnd, 27, 28), (TokenType::FormatEnd, 28, 29),
+            (TokenType::FormatStart, 30, 32), (TokenType::InterpolationStart, 32, 33),
+        ];
+        for (token_type, start, end) in expected {
+            assert_eq!(lexer.next(), Ok(Token::new(token_type, Span::new(start, end))));
+        }
+        assert_eq!(lexer.next(), Err(Error::TEOF));
+
+        let mut lexer = lexer::Lexer::new(r#"f"abc"#)?;
+        assert_eq!(lexer.next(), Ok(Token::new(TokenType::FormatStart, Span::new(0, 2))));
+        assert_eq!(lexer.next(), Ok(Token::new(TokenType::FormatText, Span::new(2, 5))));
+        assert_eq!(lexer.next(), Err(Error::TIncompleteString { span: Span::new(5, 5) }));
+        Ok(())
+    }
+
 }
\ No newline at end of file
@@ -0,0 +1,62 @@
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+    use crate::lsp::LanguageServer;
+
+    fn open(server: &mut LanguageServer, text: &str) -> Vec<Value> {
+        server.handle(&json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/didOpen",
+            "params": { "textDocument": { "uri": "file:///test.txt", "text": text } },
+        }))
+    }
+
+    fn request(server: &mut LanguageServer, method: &str, line: u32, character: u32) -> Value {
+        let response = server.handle(&json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": { "textDocument": { "uri": "file:///test.txt" }, "position": { "line": line, "character": character } },
+        }));
+        response[0]["result"].clone()
+    }
+
+    #[test]
+    fn diagnostics() {
+        let mut server = LanguageServer::new();
+        let notifications = open(&mut server, "let a = 1;\nlet b = (a +");
+        let diagnostics = &notifications[0]["params"]["diagnostics"];
+        assert_eq!(diagnostics.as_array().unwrap().len(), 1);
+        assert_eq!(diagnostics[0]["range"]["start"]["line"], 1);
+
+        let notifications = open(&mut server, "let a = 1;");
+        assert_eq!(notifications[0]["params"]["diagnostics"], json!([]));
+    }
+
+    #[test]
+    fn definition() {
+        let mut server = LanguageServer::new();
+        open(&mut server, "let x = 1;\nlet f x y = x + y;\nf(x, 2):");
+        // The argument `x` within the function
+        let result = request(&mut server, "textDocument/definition", 1, 12);
+        assert_eq!(result["range"]["start"], json!({ "line": 1, "character": 6 }));
+        // The variable `x` outside of it
+        let result = request(&mut server, "textDocument/definition", 2, 2);
+        assert_eq!(result["range"]["start"], json!({ "line": 0, "character": 4 }));
+        // The function `f`
+        let result = request(&mut server, "textDocument/definition", 2, 0);
+        assert_eq!(result["range"]["start"], json!({ "line": 1, "character": 4 }));
+    }
+
+    #[test]
+    fn hover() {
+        let mut server = LanguageServer::new();
+        open(&mut server, "sqrt(4):");
+        let result = request(&mut server, "textDocument/hover", 0, 2);
+        assert!(result["contents"]["value"].as_str().unwrap().contains("Builtin function taking 1 argument(s)"));
+
+        open(&mut server, "tau:");
+        let result = request(&mut server, "textDocument/hover", 0, 1);
+        assert!(result["contents"]["value"].as_str().unwrap().contains("tau = 6.283185307179586"));
+    }
+}
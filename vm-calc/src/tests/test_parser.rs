@@ -1,7 +1,6 @@
-#[cfg(test)]
-
 // These are just tests. Nothing to see here, that is if physical laws are still the same. I hope they are, at least.
 // If not, this place is gonna need renovation.
+#[cfg(test)]
 mod tests {
     use std::{ops::Range, rc::Rc};
     use crate::{ast::{Tree, AST}, errors::Error, lexer::Lexer, parser::Parser, utils::Span};
@@ -117,6 +116,115 @@ mod tests {
         generate_and_test("let a a b c=a+b+c;a(1,2,3);print(1,2,3);", &tests)
     }
         
+    #[test]
+    fn slices() {
+        let tests = [
+            "(a[1:3])",
+            "(a[:(-1)])",
+            "(a[::2])",
+            "((a[1:])[0])",
+            "(a[0][1:] = 5)",
+        ];
+        generate_and_test("a[1:3];a[:-1];a[::2];a[1:][0];a[0][1:]=5;", &tests);
+
+        expect_error(&generate_tree("a[1:][0] = 5;")[0]);
+    }
+
+    #[test]
+    fn maps() {
+        let tests = [
+            "{\"a\": 1, \"b\": [1, 2]}",
+            "((m[\"a\"])[\"b\"])",
+            "(m[\"a\"] += 1)",
+        ];
+        generate_and_test("{\"a\":1,\"b\":[1,2]};m.a.b;m.a+=1;", &tests);
+
+        expect_error(&generate_tree("m.1;")[0]);
+        expect_error(&generate_tree("{\"a\" 1};")[0]);
+    }
+
+    #[test]
+    fn matches() {
+        let tests = [
+            "(match x {0 => 1, [a, _] if (a > 1) => a, s: String => s, _ => {Null}})",
+            "((1 + 2) == (3 | 0))",
+        ];
+        generate_and_test("match x { 0 => 1, [a, _] if a > 1 => a, s: String => s, _ => Null };1+2==3|0;", &tests);
+
+        expect_error(&generate_tree("match x { [a, a] => a };")[0]);
+        expect_error(&generate_tree("match x { n: Text => n };")[0]);
+        expect_error(&generate_tree("match x { 1 + 1 => 1 };")[0]);
+        expect_error(&generate_tree("match x {};")[0]);
+    }
+
+    #[test]
+    fn destructuring() {
+        let tests = [
+            "(let [a, [b, _]] = x)",
+            "([a, b] = [b, a])",
+        ];
+        generate_and_test("let [a, [b, _]] = x;[a,b]=[b,a];", &tests);
+
+        expect_error(&generate_tree("let [a, 1] = x;")[0]);
+        expect_error(&generate_tree("let [a, a] = x;")[0]);
+        expect_error(&generate_tree("[a, 1] = x;")[0]);
+    }
+
+    #[test]
+    fn lambdas() {
+        let tests = [
+            "*map((\\x -> (x * 2)), a)*",
+            "(let f = (\\_ -> 1))",
+            "*(\\x y -> (x + y))(1, 2)*",
+        ];
+        generate_and_test("map(\\x -> x * 2, a):let f = \\_ -> 1;(\\x y -> x + y)(1, 2):", &tests);
+
+        expect_error(&generate_tree("\\1 -> 1;")[0]);
+        expect_error(&generate_tree("\\x + 1;")[0]);
+    }
+
+    #[test]
+    fn default_and_named_arguments() {
+        let tests = [
+            "(let f x (y = (x * 2)) = (x + y))",
+            "*f(1, y: 3)*",
+            "(let g = (\\x (y = 1) -> x))",
+        ];
+        generate_and_test("let f x (y = x * 2) = x + y;f(1, y: 3):let g = \\x (y = 1) -> x;", &tests);
+
+        expect_error(&generate_tree("let f (x = 1) y = y;")[0]);
+        expect_error(&generate_tree("f(y: 1, 2);")[0]);
+        expect_error(&generate_tree("f(y: 1, y: 2);")[0]);
+        expect_error(&generate_tree("len(x: [1]);")[0]);
+    }
+
+    #[test]
+    fn rest_arguments() {
+        let tests = [
+            "(let f x ...xs = xs)",
+            "*f(1, 2, 3)*",
+            "(\\...xs -> xs)",
+        ];
+        generate_and_test("let f x ...xs = xs;f(1, 2, 3):\\...xs -> xs;", &tests);
+
+        expect_error(&generate_tree("let f ...xs y = y;")[0]);
+        expect_error(&generate_tree("let f ...xs = xs; delete f; let f x = x; f(1, 2);")[3]);
+    }
+
+    #[test]
+    fn imports() {
+        let tests = [
+            "(import \"lib/a.txt\" as m)",
+            "*(m[\"f\"])(1)*",
+            "(import \"b.txt\")",
+        ];
+        generate_and_test("import \"lib/a.txt\" as m;m.f(1):import \"b.txt\";", &tests);
+
+        expect_error(&generate_tree("1 + import \"a.txt\";")[0]);
+        expect_error(&generate_tree("import a;")[0]);
+        expect_error(&generate_tree("m.f(1) = 2;")[0]);
+    }
+
     #[test]
     fn delete() {
         let tests = [
@@ -125,4 +233,45 @@ mod tests {
         ];
         generate_and_test("let a;delete a;", &tests)
     }
+
+    #[test]
+    fn derivatives() {
+        generate_and_test("diff(x ** 2, x):", &["*diff((x ** 2), x)*"]);
+
+        for input in ["diff(x, 1):", "diff(x):", "diff(floor(x), x):", "diff(f(x), x):", "diff(\"a\" + x, x):", "diff([x], x):", "diff(x, x: 1):", "let diff x = x;"] {
+            expect_error(&generate_tree(input)[0]);
+        }
+    }
+
+    #[test]
+    fn equations() {
+        generate_and_test("solve x ** 2 = 4 for x:", &["*(solve (x ** 2) = 4 for x)*"]);
+        generate_and_test("solve [x + y = 3, x = 1] for [x, y]:", &["*(solve [(x + y) = 3, x = 1] for [x, y])*"]);
+
+        for input in ["solve x = 1 for pi:", "solve [x = 1, y = 2] for [x, x]:", "solve [x + y = 2] for [x, y]:", "solve x = 1 for [x]:", "solve x for x:", "solve sin(x) = 0 for x:", "solve x = for x:"] {
+            expect_error(&generate_tree(input)[0]);
+        }
+    }
+
+    #[test]
+    fn constants() {
+        generate_and_test("pi * 2:", &["*(pi * 2)*"]);
+
+        for input in ["let pi = 3;", "let e;", "pi = 3;", "tau += 1;", "delete inf;", "let f nan = 1;", "let f (phi = 1) = 1;", "let f ...e = e;", "let [e, x] = [1, 2];", "[pi, x] = [1, 2];", "match 1 { pi => 1 };", "pi[0] = 1;", "import \"a.txt\" as e;"] {
+            expect_error(&generate_tree(input)[0]);
+        }
+    }
+
+    #[test]
+    fn incomplete() {
+        for input in ["(1 + 2", "1 +", "[1, 2", "print(1,", "let a =", "let f x", "arr[0", "{\"a\": 1", "m.", "match x { 1 =>", "\\x ->", "let f (x =", "f(x:", "let f ...", "import \"a.txt\" as", "1 + \"abc"] {
+            let tree = generate_tree(input);
+            assert!(matches!(tree.last(), Some(Err(error)) if error.is_incomplete()), "`{input}` should be incomplete");
+        }
+
+        for input in ["(1 + 2);", "1 + ;", "1 ) 2;"] {
+            let tree = generate_tree(input);
+            assert!(!tree.iter().any(|result| matches!(result, Err(error) if error.is_incomplete())), "`{input}` should not be incomplete");
+        }
+    }
 }
\ No newline at end of file
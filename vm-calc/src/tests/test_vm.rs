@@ -0,0 +1,668 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::{bytecode::Bytecode, functions, instruction::Value, lexer::Lexer, parser::Parser, prelude, random::Random, signatures, vm::VM};
+
+    /// Runs the source and returns everything that was output. A runtime error stops the VM,
+    /// so the outputs after it are missing
+    fn run(source: &str) -> Vec<Value> {
+        let lexer = Lexer::new(source).unwrap();
+        let parser = Parser::new(lexer);
+        let mut bytecode = Bytecode::new(parser);
+        let mut vm = VM::new(bytecode.generate_bytecode());
+        vm.execute_all();
+        vm.outputs
+    }
+
+    fn string(contents: &str) -> Value {
+        Value::String(contents.to_string())
+    }
+
+    fn numbers(numbers: &[f64]) -> Value {
+        Value::Array(numbers.iter().map(|number| Value::Number(*number)).collect())
+    }
+
+    #[test]
+    fn string_functions() {
+        let outputs = run(r#"len("héllo"): upper("abc"): lower("ABC"): trim("  a b  "): find("héllo", "l"): find("abc", "z"):"#);
+        assert_eq!(outputs, vec![Value::Number(5.0), string("ABC"), string("abc"), string("a b"), Value::Number(2.0), Value::Number(-1.0)]);
+
+        let outputs = run(r#"split("a,b,c", ","): join(["a", 1, "b"], "-"): replace("aXbX", "X", "y"): slice("héllo", 1, 3):"#);
+        assert_eq!(outputs, vec![
+            Value::Array(vec![string("a"), string("b"), string("c")]),
+            string("a-1-b"),
+            string("ayby"),
+            string("él"),
+        ]);
+    }
+
+    #[test]
+    fn number_conversion() {
+        assert_eq!(run(r#"num("0x1f"): num("-2.5"): num(" 0b101 "): str(4) + "!":"#), vec![
+            Value::Number(31.0), Value::Number(-2.5), Value::Number(5.0), string("4!"),
+        ]);
+        assert_eq!(run(r#"num("abc"): 1:"#), vec![]);
+    }
+
+    #[test]
+    fn string_operations() {
+        assert_eq!(run(r#""ab" * 3: 2 * "-": "héllo"[1]: "\u{48}\u{1F600}":"#), vec![
+            string("ababab"), string("--"), string("é"), string("H😀"),
+        ]);
+        assert_eq!(run(r#""ab" * -1: 1:"#), vec![]);
+        assert_eq!(run(r#""ab" * 1.5: 1:"#), vec![]);
+        assert_eq!(run(r#""ab"[2]: 1:"#), vec![]);
+        assert_eq!(run(r#""\u{D800}": 1:"#), vec![]);
+        assert_eq!(run(r#""a\u{41": 1:"#), vec![]);
+    }
+
+    #[test]
+    fn format_strings() {
+        let outputs = run(r#"let x = 3.14159; f"x = {x:.3}!": f"{255:x}|{255:X}|{5:08b}|{8:o}|{-3:+05}|{2:+}|{1234.5:.2e}":"#);
+        assert_eq!(outputs, vec![string("x = 3.142!"), string("ff|FF|00000101|10|-0003|+2|1.23e3")]);
+
+        let outputs = run(r#"f"[{"ab":>4}|{"ab":*^6}|{7:<3}|{"abcdef":.3}|{[1, "a"]}]": f"{{{1 + 1}}} {f"{"in"}"}\n": f"":"#);
+        assert_eq!(outputs, vec![string("[  ab|**ab**|7  |abc|<Array> [1, a]]"), string("{2} in\n"), string("")]);
+
+        // Only whole numbers can be written in another radix, and strings cannot have a radix
+        assert_eq!(run(r#"f"{1.5:x}": 1:"#), vec![]);
+        assert_eq!(run(r#"f"{"a":x}": 1:"#), vec![]);
+        // Numbers past the range of 64 bits keep their digits, up to 2**128
+        assert_eq!(run(r#"f"{2**70:x}": f"{-(2**100):b}":"#), vec![string("400000000000000000"), string(&format!("-1{}", "0".repeat(100)))]);
+        assert_eq!(run(r#"f"{2**128:x}": 1:"#), vec![]);
+        // Invalid specifiers are found while parsing
+        assert_eq!(run(r#"f"{1:.}": 1:"#), vec![]);
+        assert_eq!(run(r#"f"{1:.2x}": 1:"#), vec![]);
+        // The width and the precision are at most 1000
+        assert_eq!(run(r#"len(f"{1:1000}"): len(f"{1:.1000}"):"#), [1000.0, 1002.0].map(Value::Number));
+        assert_eq!(run(r#"f"{1:1001}": 1:"#), vec![]);
+        assert_eq!(run(r#"f"{1:.1001}": 1:"#), vec![]);
+        // A single `}` is not written as a brace, only `}}` is
+        assert_eq!(run(r#"f"a } b": 1:"#), vec![]);
+        assert_eq!(run(r#"f"{1}}": 1:"#), vec![]);
+        assert_eq!(run(r#"f"\\}}": f"}}}}":"#), vec![string("\\}"), string("}}")]);
+    }
+
+    #[test]
+    fn array_functions() {
+        let outputs = run("len([1, 2]): push([1], 2): pop([1, 2]): insert([1, 3], 1, 2): remove([1, 2, 3], 0): concat([1], [2]): slice([1, 2, 3], 1, 3):");
+        assert_eq!(outputs, vec![
+            Value::Number(2.0), numbers(&[1.0, 2.0]), numbers(&[1.0]), numbers(&[1.0, 2.0, 3.0]), numbers(&[2.0, 3.0]), numbers(&[1.0, 2.0]), numbers(&[2.0, 3.0]),
+        ]);
+
+        let outputs = run(r#"reverse([1, 2]): reverse("ab"): sort([3, 1, 2]): sort(["b", "a"]): range(0, 1, 0.25): range(5, 0, -2):"#);
+        assert_eq!(outputs, vec![
+            numbers(&[2.0, 1.0]), string("ba"), numbers(&[1.0, 2.0, 3.0]), Value::Array(vec![string("a"), string("b")]),
+            numbers(&[0.0, 0.25, 0.5, 0.75]), numbers(&[5.0, 3.0, 1.0]),
+        ]);
+
+        assert_eq!(run("pop([]): 1:"), vec![]);
+        assert_eq!(run(r#"sort([1, "a"]): 1:"#), vec![]);
+        assert_eq!(run("range(0, 1, 0): 1:"), vec![]);
+        assert_eq!(run("range(0, inf, 1): 1:"), vec![]);
+        assert_eq!(run("range(0, 100000000000, 1): 1:"), vec![]);
+        assert_eq!(run("len(range(0, 1000000, 1)): len(range(0, -1, 1)):"), vec![Value::Number(1e6), Value::Number(0.0)]);
+        assert_eq!(run("remove([1], 1): 1:"), vec![]);
+    }
+
+    #[test]
+    fn higher_order_functions() {
+        let source = "let double x = x * 2; let add a b = a + b; let more a b = b - a;
+            map(double, [1, 2]): map(add(10), [1, 2]): filter(more(1), [0, 1, 2, 3]): reduce(add, [1, 2, 3], 10): zip([1, 2, 3], [4, 5]):";
+        assert_eq!(run(source), vec![
+            numbers(&[2.0, 4.0]), numbers(&[11.0, 12.0]), numbers(&[0.0, 2.0, 3.0]), Value::Number(16.0),
+            Value::Array(vec![numbers(&[1.0, 4.0]), numbers(&[2.0, 5.0])]),
+        ]);
+
+        // Functions that take functions can themselves be partially called
+        let source = "let double x = x * 2; let apply f arr = map(f, arr); map(apply(double), [[1], [2, 3]]):";
+        assert_eq!(run(source), vec![Value::Array(vec![numbers(&[2.0]), numbers(&[4.0, 6.0])])]);
+
+        // The arguments of a partial call keep their order
+        assert_eq!(run("let a x y z = x + y * 10 + z * 100; a(1, 2)(3): a(1)(2)(3):"), vec![Value::Number(321.0), Value::Number(321.0)]);
+
+        assert_eq!(run("map(1, [1]): 1:"), vec![]);
+        assert_eq!(run(r#"let f x = "a"; filter(f, [1]): 1:"#), vec![]);
+    }
+
+    #[test]
+    fn broadcasting() {
+        let outputs = run(r#"[1, 2, 3] + [4, 5, 6]: [1, 2] * 2: 10 - [1, 2]: [[1, 2], [3, 4]] / [2, 4]: ["a", "b"] + "!": let v = [1, 2]; v += [10, 20]; v:"#);
+        assert_eq!(outputs, vec![
+            numbers(&[5.0, 7.0, 9.0]), numbers(&[2.0, 4.0]), numbers(&[9.0, 8.0]),
+            Value::Array(vec![numbers(&[0.5, 0.5]), numbers(&[1.5, 1.0])]),
+            Value::Array(vec![string("a!"), string("b!")]), numbers(&[11.0, 22.0]),
+        ]);
+
+        assert_eq!(run("[1, 2] + [1, 2, 3]: 1:"), vec![]);
+        assert_eq!(run("[[1, 2], [3, 4]] + [[1, 2], [3]]: 1:"), vec![]);
+        assert_eq!(run("[[1, 2], [3, 4]] + [1, 2, 3]: 1:"), vec![]);
+        assert_eq!(run("[1, 0] / 0: 1:"), vec![]);
+    }
+
+    #[test]
+    fn matrices() {
+        let outputs = run("let m = [[1, 2], [3, 4]]; dot([1, 2], [3, 4]): matmul(m, [[1], [1]]): transpose([[1, 2, 3]]): det(m): identity(2): inv([[2, 0], [0, 4]]):");
+        assert_eq!(outputs, vec![
+            Value::Number(11.0), Value::Array(vec![numbers(&[3.0]), numbers(&[7.0])]),
+            Value::Array(vec![numbers(&[1.0]), numbers(&[2.0]), numbers(&[3.0])]), Value::Number(-2.0),
+            Value::Array(vec![numbers(&[1.0, 0.0]), numbers(&[0.0, 1.0])]), Value::Array(vec![numbers(&[0.5, 0.0]), numbers(&[0.0, 0.25])]),
+        ]);
+        assert_eq!(run("det([[1, 2], [2, 4]]):"), vec![Value::Number(0.0)]);
+
+        assert_eq!(run("dot([1, 2], [1]): 1:"), vec![]);
+        assert_eq!(run("matmul([[1, 2, 3]], [[1, 2]]): 1:"), vec![]);
+        assert_eq!(run("inv([[1, 2], [2, 4]]): 1:"), vec![]);
+        assert_eq!(run("det([[1, 2, 3], [4, 5, 6]]): 1:"), vec![]);
+        assert_eq!(run("transpose([[1, 2], [3]]): 1:"), vec![]);
+    }
+
+    #[test]
+    fn negative_indices() {
+        let outputs = run(r#"let a = [1, 2, 3]; a[-1]: a[-3]: "héllo"[-4]: a[-1] = 9; a: let i = 0; a[i + 1] = 5; a:"#);
+        assert_eq!(outputs, vec![
+            Value::Number(3.0), Value::Number(1.0), string("é"), numbers(&[1.0, 2.0, 9.0]), numbers(&[1.0, 5.0, 9.0]),
+        ]);
+
+        assert_eq!(run("[1, 2, 3][-4]: 1:"), vec![]);
+        assert_eq!(run("[1, 2, 3][1.5]: 1:"), vec![]);
+        assert_eq!(run("let a = [1, 2]; a[0.5] = 1; 1:"), vec![]);
+    }
+
+    #[test]
+    fn slices() {
+        let outputs = run(r#"let a = [0, 1, 2, 3, 4]; a[1:3]: a[::2]: a[-2:]: a[:10]: a[::-1]: a[3:1]: a[4:0:-2]: "hello"[1:-1]:"#);
+        assert_eq!(outputs, vec![
+            numbers(&[1.0, 2.0]), numbers(&[0.0, 2.0, 4.0]), numbers(&[3.0, 4.0]), numbers(&[0.0, 1.0, 2.0, 3.0, 4.0]),
+            numbers(&[4.0, 3.0, 2.0, 1.0, 0.0]), numbers(&[]), numbers(&[4.0, 2.0]), string("ell"),
+        ]);
+
+        let outputs = run("let a = [0, 1, 2, 3]; a[1:3] = [9]; a: a[::2] = [7, 8]; a: a[:2] += 1; a: let m = [[1, 2], [3, 4]]; m[0][1:] = [5, 6]; m:");
+        assert_eq!(outputs, vec![
+            numbers(&[0.0, 9.0, 3.0]), numbers(&[7.0, 9.0, 8.0]), numbers(&[8.0, 10.0, 8.0]),
+            Value::Array(vec![numbers(&[1.0, 5.0, 6.0]), numbers(&[3.0, 4.0])]),
+        ]);
+
+        assert_eq!(run("[1, 2][::0]: 1:"), vec![]);
+        assert_eq!(run("[1, 2][0.5:]: 1:"), vec![]);
+        assert_eq!(run("let a = [1, 2, 3]; a[::2] = [1]; 1:"), vec![]);
+        assert_eq!(run("let a = [1, 2, 3]; a[:1] = 1; 1:"), vec![]);
+    }
+
+    #[test]
+    fn maps() {
+        let outputs = run(r#"let m = {"a": 1, "b": [1, 2], "c": {"d": "x"}}; m.a: m["b"][-1]: m.c.d: len(m): has(m, "a"): has(m, "z"):"#);
+        assert_eq!(outputs, vec![Value::Number(1.0), Value::Number(2.0), string("x"), Value::Number(3.0), Value::Number(1.0), Value::Number(0.0)]);
+
+        let outputs = run(r#"let m = {"b": 1}; m.b += 5; m["a"] = "new"; keys(m): values(m): let n = {"m": m}; n.m.b = [0]; n.m.b[0] = 7; n:"#);
+        let mut inner = BTreeMap::new();
+        inner.insert("a".to_string(), string("new"));
+        inner.insert("b".to_string(), numbers(&[7.0]));
+        assert_eq!(outputs, vec![
+            Value::Array(vec![string("a"), string("b")]), Value::Array(vec![string("new"), Value::Number(6.0)]),
+            Value::Map(BTreeMap::from([("m".to_string(), Value::Map(inner))])),
+        ]);
+
+        assert_eq!(run(r#"{"a": 1}.b: 1:"#), vec![]);
+        assert_eq!(run(r#"{1: 1}: 1:"#), vec![]);
+        assert_eq!(run(r#"let m = {}; m.a.b = 1; 1:"#), vec![]);
+        assert_eq!(run(r#"{"a": 1}[0]: 1:"#), vec![]);
+
+        // An operation on a key that is not in the map does not add it
+        let lexer = Lexer::new(r#"let m = {}; m.new += 1;"#).unwrap();
+        let mut vm = VM::new(Bytecode::new(Parser::new(lexer)).generate_bytecode());
+        vm.execute_all();
+        assert_eq!(vm.get_symbols().get("m"), Some(&Value::Map(BTreeMap::new())));
+    }
+
+    #[test]
+    fn maps_in_bytecode() {
+        let lexer = Lexer::new(r#"let m = {"a": [1]}; m.b = {"c": 2}; m:"#).unwrap();
+        let instructions = Bytecode::new(Parser::new(lexer)).generate_bytecode();
+        let bytes = bincode::serialize(&instructions).unwrap();
+        let mut vm = VM::new(bincode::deserialize(&bytes).unwrap());
+        vm.execute_all();
+
+        let mut map = BTreeMap::new();
+        map.insert("a".to_string(), numbers(&[1.0]));
+        map.insert("b".to_string(), Value::Map(BTreeMap::from([("c".to_string(), Value::Number(2.0))])));
+        assert_eq!(vm.outputs, vec![Value::Map(map)]);
+    }
+
+    #[test]
+    fn comparisons() {
+        let outputs = run(r#"1 == 1: 1 != 1: 2 < 1: 2 <= 2: 3 > 2: 1 >= 2: "a" < "b": [1, 2] == [1, 2]: [1, 2] == "a": [1, 5] > 2: 1 + 1 == 2:"#);
+        assert_eq!(outputs, vec![
+            Value::Number(1.0), Value::Number(0.0), Value::Number(0.0), Value::Number(1.0), Value::Number(1.0), Value::Number(0.0),
+            Value::Number(1.0), Value::Number(1.0), Value::Number(0.0), numbers(&[0.0, 1.0]), Value::Number(1.0),
+        ]);
+        assert_eq!(run(r#""a" < 1: 1:"#), vec![]);
+    }
+
+    #[test]
+    fn matches() {
+        let source = r#"
+            let describe x = match x {
+                0 => "zero",
+                -1 => "minus one",
+                [a, [b, _]] => a + b,
+                [_, _] => "pair",
+                n: Number if n > 3 => n * 2,
+                s: String => "string " + s,
+                Null => "null",
+                _ => "other",
+            };
+            describe(0): describe(-1): describe([1, [2, 3]]): describe([1, 2]): describe(10): describe(2): describe("a"): describe(Null):
+        "#;
+        assert_eq!(run(source), vec![
+            string("zero"), string("minus one"), Value::Number(3.0), string("pair"), Value::Number(20.0), string("other"), string("string a"), string("null"),
+        ]);
+
+        // The names bound by a pattern only last for their arm
+        assert_eq!(run("let n = 1; match 5 { n if n > 10 => 0, n => n * 2 }: n:"), vec![Value::Number(10.0), Value::Number(1.0)]);
+        assert_eq!(run("match 5 { n => n }; n: 1:"), vec![]);
+        assert_eq!(run(r#"match {"a": 1} { m: Map => m.a }: match [1, 2] { [a, b]: Array => a + b }:"#), vec![Value::Number(1.0), Value::Number(3.0)]);
+
+        assert_eq!(run("match 5 { 1 => 1, [a] => a }: 1:"), vec![]);
+        assert_eq!(run(r#"match 5 { n if "yes" => n }: 1:"#), vec![]);
+
+        // The symbols that are kept after a runtime error, as in the REPL, do not have the names bound by the arms
+        // or the arguments of the functions that were being run
+        let lexer = Lexer::new(r#"let a = 7; let f x = match 3 { a => a / x }; f("x");"#).unwrap();
+        let mut vm = VM::new(Bytecode::new(Parser::new(lexer)).generate_bytecode());
+        vm.execute_all();
+        let symbols = vm.get_symbols();
+        assert_eq!(symbols.get("a"), Some(&Value::Number(7.0)));
+        assert_eq!(symbols.get("x"), None);
+    }
+
+    #[test]
+    fn destructuring() {
+        let outputs = run("let arr = [1, [2, 3], 4]; let [a, [b, c], _] = arr; a: b: c: [a, b] = [b, a]; a: b: let [x] = [[5]]; x:");
+        assert_eq!(outputs, vec![
+            Value::Number(1.0), Value::Number(2.0), Value::Number(3.0), Value::Number(2.0), Value::Number(1.0), numbers(&[5.0]),
+        ]);
+        // The value being taken apart is the result
+        assert_eq!(run("(let [a, b] = [1, 2]) + 1:"), vec![numbers(&[2.0, 3.0])]);
+
+        assert_eq!(run("let [a, b] = [1, 2, 3]; 1:"), vec![]);
+        assert_eq!(run("let [a, [b, c]] = [1, 2]; 1:"), vec![]);
+        assert_eq!(run("let a = 1; [a, b] = [1, 2]; 1:"), vec![]);
+    }
+
+    #[test]
+    fn lambdas() {
+        assert_eq!(run("map(\\x -> x * 2, [1, 2, 3]):"), vec![numbers(&[2.0, 4.0, 6.0])]);
+        assert_eq!(run("reduce(\\a b -> a + b, [1, 2, 3], 0): filter(\\x -> x > 1, [1, 2, 3]):"), vec![Value::Number(6.0), numbers(&[2.0, 3.0])]);
+        assert_eq!(run("(\\x -> x ** 2)(4): let f = \\_ -> 42; f():"), vec![Value::Number(16.0), Value::Number(42.0)]);
+
+        // Lambdas can be partially called like declared functions
+        assert_eq!(run("let add = \\x y -> x + y; add(1, 2): add(10)(5): let inc = add(1); inc(2):"), vec![Value::Number(3.0), Value::Number(15.0), Value::Number(3.0)]);
+        assert_eq!(run("let apply f x = f(x); apply(\\y -> y - 1, 10):"), vec![Value::Number(9.0)]);
+        assert_eq!(run("typeof \\x -> x:"), vec![string("{Function}")]);
+
+        // A lambda can also have its arguments in brackets, and the array can come before the function
+        assert_eq!(run("let add = fn(x, y = 10) => x + y; add(1, 2): add(1): (fn() => 42)(): (fn(x, ...rest) => rest)(1, 2, 3):"), vec![
+            Value::Number(3.0), Value::Number(11.0), Value::Number(42.0), numbers(&[2.0, 3.0]),
+        ]);
+        assert_eq!(run("map([1, 2], fn(x) => x * 2): filter([1, 2, 3], \\x -> x > 1): reduce([1, 2, 3], \\a b -> a + b, 0):"), vec![
+            numbers(&[2.0, 4.0]), numbers(&[2.0, 3.0]), Value::Number(6.0),
+        ]);
+        assert_eq!(run("fn(x y) => x: 1:"), vec![]);
+        assert_eq!(run("fn(x = 1, y) => x: 1:"), vec![]);
+
+        // The arguments do not stay around after the call
+        assert_eq!(run("let x = 1; (\\x -> x)(5): x:"), vec![Value::Number(5.0), Value::Number(1.0)]);
+
+        // A lambda keeps the values of the arguments and bindings around it, but global variables are still looked up when it is called
+        assert_eq!(run("let adder n = \\x -> x + n; adder(1)(2): let f = \\x -> \\y -> x + y; f(1)(2):"), vec![Value::Number(3.0), Value::Number(3.0)]);
+        assert_eq!(run("let g = \\x -> \\y -> \\z -> [x, y, z]; g(1)(2)(3): let h = match 3 { a => \\x -> x + a }; h(1):"), vec![numbers(&[1.0, 2.0, 3.0]), Value::Number(4.0)]);
+        assert_eq!(run("let count = 1; let k = \\x -> x + count; count = 5; k(0):"), vec![Value::Number(5.0)]);
+
+        // A lambda in a variable can still be called on the next line of the repl, which is run with other instructions
+        let mut bytecode = Bytecode::new(Parser::new(Lexer::new("let sub = \\x y -> x - y;").unwrap()));
+        let (instructions, _) = bytecode.generate_fn_bytecode(vec![]);
+        let mut vm = VM::new(instructions);
+        vm.execute_all();
+        let kept = vm.take_functions();
+        let parser = Parser::new_fn_symbols(Lexer::new("sub(10)(3): sub(1, 2):").unwrap(), bytecode.get_symbols());
+        let (instructions, _) = Bytecode::new(parser).generate_fn_bytecode(vec![]);
+        let mut next = VM::new_with_symbols([kept, instructions].concat(), vm.get_symbols());
+        next.execute_all();
+        assert_eq!(next.outputs, [Value::Number(7.0), Value::Number(-1.0)]);
+    }
+
+    #[test]
+    fn default_and_named_arguments() {
+        let source = "let f x (y = 2) = x + y; f(1): f(1, 5): f(1, y: 10): f(y: 10, x: 3): f(y: 10)(1): let g = f(); g(4):";
+        assert_eq!(run(source), vec![
+            Value::Number(3.0), Value::Number(6.0), Value::Number(11.0), Value::Number(13.0), Value::Number(11.0), Value::Number(6.0),
+        ]);
+
+        // A default value can use the arguments before it, and is computed each time it is needed
+        let source = "let h x (y = x * 2) (z = y + 1) = [x, y, z]; h(1): h(1, z: 0): h(1, 5): (\\x (y = 3) -> x * y)(2):";
+        assert_eq!(run(source), vec![numbers(&[1.0, 2.0, 3.0]), numbers(&[1.0, 2.0, 0.0]), numbers(&[1.0, 5.0, 6.0]), Value::Number(6.0)]);
+        assert_eq!(run("let f x (y = 2) = x + y; map(f(y: 100), [1, 2]): map(f, [1, 2]):"), vec![numbers(&[101.0, 102.0]), numbers(&[3.0, 4.0])]);
+
+        assert_eq!(run("let f x (y = 2) = x + y; f(1, x: 2): 1:"), vec![]);
+        assert_eq!(run("let f x (y = 2) = x + y; f(1, z: 2): 1:"), vec![]);
+    }
+
+    #[test]
+    fn default_arguments_in_bytecode() {
+        let lexer = Lexer::new("let f x (y = x + 1) = x * y; f(2): f(y: 5)(2):").unwrap();
+        let instructions = Bytecode::new(Parser::new(lexer)).generate_bytecode();
+        let bytes = bincode::serialize(&instructions).unwrap();
+        let mut vm = VM::new(bincode::deserialize(&bytes).unwrap());
+        vm.execute_all();
+        assert_eq!(vm.outputs, vec![Value::Number(6.0), Value::Number(10.0)]);
+    }
+
+    #[test]
+    fn rest_arguments() {
+        let source = "let f x ...xs = [x, xs]; f(1): f(1, 2, 3): f()(1, 2):";
+        assert_eq!(run(source), vec![
+            Value::Array(vec![Value::Number(1.0), numbers(&[])]),
+            Value::Array(vec![Value::Number(1.0), numbers(&[2.0, 3.0])]),
+            Value::Array(vec![Value::Number(1.0), numbers(&[2.0])]),
+        ]);
+
+        // The rest argument comes after the ones with default values, and the others can still be named
+        let source = "let g a (b = 10) ...r = [a, b, len(r)]; g(1): g(1, 2, 3, 4): g(1, b: 5): (\\...xs -> xs)(1, 2):";
+        assert_eq!(run(source), vec![numbers(&[1.0, 10.0, 0.0]), numbers(&[1.0, 2.0, 2.0]), numbers(&[1.0, 5.0, 0.0]), numbers(&[1.0, 2.0])]);
+        assert_eq!(run("let f ...xs = xs; f(xs: 1): 1:"), vec![]);
+    }
+
+    #[test]
+    fn imports() {
+        let directory = std::env::temp_dir().join(format!("vm-calc-imports-{}", std::process::id()));
+        std::fs::create_dir_all(directory.join("lib")).unwrap();
+        std::fs::write(directory.join("lib/shapes.txt"), "let sq x = x * x; let area w h = sq(w) * h; let count = 0; count += 1;").unwrap();
+        // A module is found relative to the file that imports it
+        std::fs::write(directory.join("lib/more.txt"), "import \"shapes.txt\" as s; let cube x = s.sq(x) * x; s.count:").unwrap();
+        std::fs::write(directory.join("loop.txt"), "import \"loop.txt\";").unwrap();
+        std::fs::write(directory.join("lib/values.txt"), "let k = 5; let limit = clamp(k, 0, 2);").unwrap();
+        std::fs::write(directory.join("lib/outer.txt"), "let z = y + 1;").unwrap();
+        let main = directory.join("main.txt");
+        let path = main.to_str().unwrap();
+
+        let run_file = |source: &str| {
+            std::fs::write(&main, source).unwrap();
+            let source = std::fs::read_to_string(&main).unwrap();
+            let lexer = Lexer::new(&source).unwrap();
+            let mut bytecode = Bytecode::new_in_file(Parser::new_fn_symbols(lexer, prelude::symbols()), path);
+            let mut vm = VM::new(prelude::with_prelude(bytecode.generate_bytecode())).with_prelude(prelude::names());
+            vm.execute_all();
+            vm.outputs
+        };
+
+        // The functions of a module can use its other declarations, and each module is only run once
+        let source = "import \"lib/shapes.txt\" as shapes; import \"lib/more.txt\"; shapes.area(2, 3): cube(2): shapes.count: map(shapes.sq, [1, 2]):";
+        assert_eq!(run_file(source), vec![Value::Number(12.0), Value::Number(8.0), Value::Number(1.0), numbers(&[1.0, 4.0])]);
+        assert_eq!(run_file("import \"lib/shapes.txt\"; area(1, 2):"), vec![Value::Number(2.0)]);
+
+        // A module exports what it declares even when the importer has the same values, and only sees the prelude
+        assert_eq!(run_file("let k = 5; import \"lib/values.txt\" as m; m.k: m.limit:"), vec![Value::Number(5.0), Value::Number(2.0)]);
+        assert_eq!(run_file("let clamp x = x; import \"lib/values.txt\" as m; m.limit:"), vec![Value::Number(2.0)]);
+        assert_eq!(run_file("let y = 1; import \"lib/outer.txt\" as m; 1:"), vec![]);
+
+        // The modules that have been run are given to the next VM, as the repl does for each line, and are not run again even
+        // though their instructions have moved
+        std::fs::write(directory.join("lib/random.txt"), "let r = rand(); let f x = x + r;").unwrap();
+        let (first, second) = ("import \"lib/random.txt\" as m; m.r:", "1; 2; import \"lib/random.txt\" as m; m.f(0): m.r:");
+        let mut bytecode = Bytecode::new_in_file(Parser::new(Lexer::new(first).unwrap()), path);
+        let mut vm = VM::new(bytecode.generate_bytecode()).with_random(Random::new(1));
+        vm.execute_all();
+        let modules = vm.take_modules();
+        let mut bytecode = Bytecode::new_in_file(Parser::new(Lexer::new(second).unwrap()), path);
+        let mut next = VM::new(bytecode.generate_bytecode()).with_modules(modules).with_random(Random::new(2));
+        next.execute_all();
+        assert_eq!(next.outputs, [vm.outputs.clone(), vm.outputs].concat());
+
+        assert_eq!(run_file("import \"loop.txt\"; 1:"), vec![]);
+        assert_eq!(run_file("import \"lib/missing.txt\" as m; 1:"), vec![]);
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn calls_on_fields() {
+        assert_eq!(run("let m = {\"f\": \\x -> x + 1}; m.f(2): m[\"f\"](3): [\\x -> x * 2][0](4):"), vec![
+            Value::Number(3.0), Value::Number(4.0), Value::Number(8.0),
+        ]);
+    }
+
+    #[test]
+    fn prelude() {
+        let run_with_prelude = |source: &str| {
+            let lexer = Lexer::new(source).unwrap();
+            let mut bytecode = Bytecode::new(Parser::new_fn_symbols(lexer, prelude::symbols()));
+            let mut vm = VM::new(prelude::with_prelude(bytecode.generate_bytecode()));
+            vm.execute_all();
+            vm.outputs
+        };
+
+        assert_eq!(run_with_prelude("clamp(5, 0, 3): clamp(-1, 0, 3): lerp(2, 4, 0.5):"), [3.0, 0.0, 3.0].map(Value::Number));
+
+        // The functions of the prelude can be replaced, without changing the others
+        assert_eq!(run_with_prelude("let lerp a b t = 0; lerp(2, 4, 0.5): clamp(5, 0, 3):"), vec![Value::Number(0.0), Value::Number(3.0)]);
+        assert_eq!(run_with_prelude("clamp(1, 2, 3, 4):"), vec![]);
+    }
+
+    #[test]
+    fn constants() {
+        assert_eq!(run("pi: e: tau / pi: -inf: phi ** 2 - phi:"), vec![
+            Value::Number(std::f64::consts::PI), Value::Number(std::f64::consts::E), Value::Number(2.0), Value::Number(f64::NEG_INFINITY), Value::Number(1.0),
+        ]);
+        assert!(matches!(run("nan:")[..], [Value::Number(number)] if number.is_nan()));
+        // The constants are compiled into the bytecode, so they are the same within functions and lambdas
+        assert_eq!(run("let f x = x * pi; f(2) == 2 * pi: (\\_ -> tau)():"), vec![Value::Number(1.0), Value::Number(std::f64::consts::TAU)]);
+    }
+
+    #[test]
+    fn signatures() {
+        // The compiler only knows the names, so each of them needs a function with the same number of arguments
+        for (name, arguments) in signatures::BUILTINS {
+            let function = functions::get_function(name).map(|(arguments, _)| arguments);
+            assert_eq!(function.or(functions::get_value_function(name).map(|(arguments, _)| arguments)), Ok(arguments), "{name}");
+        }
+        for (name, (arguments, _)) in functions::FUNCTIONS {
+            assert_eq!(signatures::get_builtin_arguments(name), Ok(arguments), "{name}");
+        }
+        for (name, (arguments, _)) in functions::value_functions() {
+            assert_eq!(signatures::get_builtin_arguments(name), Ok(arguments), "{name}");
+        }
+    }
+
+    #[test]
+    fn number_functions() {
+        assert_eq!(run("log(2, 8): log(10, 1000): exp(0): floor_div(-7, 2): floor_div(7, 2): sign(-3): sign(0): sign(2.5): trunc(-2.7):"),
+            [3.0, 2.9999999999999996, 1.0, -4.0, 3.0, -1.0, 0.0, 1.0, -2.0].map(Value::Number));
+        assert_eq!(run("gcd(12, -18): gcd(0, 5): lcm(4, 6): lcm(0, 3): factorial(0): factorial(5): choose(5, 2): choose(5, 7): choose(50, 25):"),
+            [6.0, 5.0, 12.0, 0.0, 1.0, 120.0, 10.0, 0.0, 126410606437752.0].map(Value::Number));
+        // Results that are too large to be numbers are errors, found without going through every step
+        assert_eq!(run("factorial(170) < inf: choose(10 ** 12, 1):"), [1.0, 1e12].map(Value::Number));
+        for source in ["factorial(171):", "factorial(10 ** 10):", "choose(10 ** 12, 10 ** 11):", "choose(1030, 515):"] {
+            assert_eq!(run(source), vec![], "{source}");
+        }
+        assert_eq!(run("is_prime(2): is_prime(97): is_prime(1): is_prime(91): is_prime(7.5):"), [1.0, 1.0, 0.0, 0.0, 0.0].map(Value::Number));
+
+        assert_eq!(run("gcd(1.5, 2):"), vec![]);
+        assert_eq!(run("factorial(-1):"), vec![]);
+        assert_eq!(run("choose(-1, 2):"), vec![]);
+        // The same error as `/` by zero, rather than an infinite number
+        assert_eq!(run("floor_div(5, 0):"), vec![]);
+        assert_eq!(run("log(1, 5):"), vec![]);
+        assert_eq!(run("log(-2, 5):"), vec![]);
+    }
+
+    #[test]
+    fn statistics() {
+        let data = "[2, 4, 4, 4, 5, 5, 7, 9]";
+        assert_eq!(run(&format!("mean({data}): median({data}): mode({data}): variance({data}): stddev({data}): min({data}): max({data}):")),
+            [5.0, 4.5, 4.0, 4.0, 2.0, 2.0, 9.0].map(Value::Number));
+        assert_eq!(run("median([3, 1, 2]): mode([3, 1, 3, 1]): percentile([1, 2, 3, 4], 50): percentile([10, 20], 25): percentile([5], 100):"),
+            [2.0, 1.0, 2.5, 12.5, 5.0].map(Value::Number));
+        assert_eq!(run("sum([1, 2, 3]): sum([]): product([2, 3, 4]): product([]): cumsum([1, 2, 3]): cumsum([]):"), vec![
+            Value::Number(6.0), Value::Number(0.0), Value::Number(24.0), Value::Number(1.0), numbers(&[1.0, 3.0, 6.0]), numbers(&[]),
+        ]);
+        // The total of nothing is 0 rather than -0
+        assert_eq!(run("str(sum([])):"), vec![string("0")]);
+        assert_eq!(run("histogram([1, 2, 2, 3, 4, 5], 2): histogram([1, 1], 3):"), vec![numbers(&[3.0, 3.0]), numbers(&[2.0, 0.0, 0.0])]);
+
+        let line = BTreeMap::from([
+            ("slope".to_string(), Value::Number(2.0)), ("intercept".to_string(), Value::Number(1.0)), ("r2".to_string(), Value::Number(1.0)),
+        ]);
+        assert_eq!(run("linreg([0, 1, 2], [1, 3, 5]):"), vec![Value::Map(line)]);
+        assert_eq!(run("linreg([1, 2, 3], [2, 1, 3]).slope: linreg([1, 2, 3], [2, 1, 3]).r2:"), [0.5, 0.25].map(Value::Number));
+
+        // Elements that are not numbers and arrays without elements are errors
+        for source in ["mean([1, \"2\"]):", "sum([[1]]):", "median([]):", "max([]):", "percentile([1], 101):", "histogram([1], 0):", "histogram([1, 2, 3], 10 ** 12):", "linreg([1, 2], [1]):", "linreg([1, 1], [1, 2]):", "mean(1):"] {
+            assert_eq!(run(source), vec![], "{source}");
+        }
+    }
+
+    #[test]
+    fn random() {
+        let run_with_seed = |source: &str, seed: u64| {
+            let lexer = Lexer::new(source).unwrap();
+            let mut bytecode = Bytecode::new(Parser::new(lexer));
+            let mut vm = VM::new(bytecode.generate_bytecode()).with_random(Random::new(seed));
+            vm.execute_all();
+            vm.outputs
+        };
+
+        // The same seed gives the same numbers
+        let source = "rand(): rand_int(1, 6): choice([1, 2, 3]): shuffle(range(0, 10, 1)): normal(0, 1):";
+        assert_eq!(run_with_seed(source, 42), run_with_seed(source, 42));
+        assert_ne!(run_with_seed(source, 42), run_with_seed(source, 43));
+
+        let source = "
+            let xs = map(\\i -> rand(), range(0, 1000, 1));
+            min(xs) >= 0: max(xs) < 1:
+            let dice = map(\\i -> rand_int(1, 6), range(0, 1000, 1));
+            min(dice): max(dice): filter(\\x -> x % 1, dice):
+            sort(shuffle([3, 1, 2, 5, 4])): rand_int(2, 2): choice([7]): normal(5, 0):
+        ";
+        assert_eq!(run_with_seed(source, 1), vec![
+            Value::Number(1.0), Value::Number(1.0), Value::Number(1.0), Value::Number(6.0), numbers(&[]),
+            numbers(&[1.0, 2.0, 3.0, 4.0, 5.0]), Value::Number(2.0), Value::Number(7.0), Value::Number(5.0),
+        ]);
+
+        // The widest range that `rand_int` can choose from
+        assert_eq!(run_with_seed("let n = rand_int(-(2 ** 52), 2 ** 52 - 1); n % 1: n >= -(2 ** 52):", 3), [0.0, 1.0].map(Value::Number));
+
+        for source in ["choice([]):", "rand_int(3, 1):", "rand_int(1.5, 2):", "rand_int(0, 2 ** 64):", "rand_int(-(2 ** 63), 2 ** 63):", "normal(0, -1):", "shuffle(1):"] {
+            assert_eq!(run_with_seed(source, 0), vec![], "{source}");
+        }
+    }
+
+    #[test]
+    fn calculus() {
+        // Each result is compared within the language, since numerical methods are only close to the exact answers
+        let source = r"
+            let close a b = abs(a - b) < 0.000001;
+            close(deriv(\x -> x ** 3, 2), 12): close(deriv(\x -> sin(x), 0), 1):
+            close(integrate(\x -> x ** 2, 0, 3), 9): close(integrate(\x -> sin(x), 0, pi), 2): close(integrate(\x -> 1, 3, 1), -2): close(integrate(\x -> exp(x), 0, 30) / (exp(30) - 1), 1):
+            close(integrate(\x -> floor(x * 10), 0, 1), 4.5):
+            close(solve(\x -> x ** 2 - 2, 1), sqrt(2)): close(solve(\x -> cos(x) - x, 0), 0.7390851332):
+            close(solve(\x -> cbrt(x), 1), 0): close(solve(\x -> x ** 3 - x - 2, 0), 1.5213797068):
+            close(minimize(\x -> (x - 2) ** 2 + 1, 0, 5), 2): minimize(\x -> x, 0, 5):
+        ";
+        assert_eq!(run(source), [1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 0.0].map(Value::Number));
+
+        // Functions that are declared can be given as well, including partially called ones
+        assert_eq!(run("let line a b x = a * x + b; solve(line(2, -6), 0): integrate(line(2, 0), 0, 1):"), [3.0, 1.0].map(Value::Number));
+
+        for source in [r"solve(\x -> x ** 2 + 1, 0):", r#"deriv(\x -> "a", 1):"#, "deriv(1, 1):", r"minimize(\x -> x, 5, 0):", r"integrate(\x -> 1 / x, 0, 1):",
+            r"integrate(\x -> rand(), 0, 1):"] {
+            assert_eq!(run(source), vec![], "{source}");
+        }
+    }
+
+    #[test]
+    fn derivatives() {
+        let derivatives = [
+            ("diff(x ** 2, x):", "(2 * x)"),
+            ("diff(3 * x ** 3 + 2 * x - 5, x):", "((9 * (x ** 2)) + 2)"),
+            ("diff(sin(x) * x, x):", "((cos(x) * x) + sin(x))"),
+            ("diff(1 / x, x):", "(-1 / (x ** 2))"),
+            ("diff(exp(2 * x), x):", "(2 * exp((2 * x)))"),
+            ("diff(x * ln(x), x):", "(ln(x) + 1)"),
+            ("diff(2 ** x, x):", "((2 ** x) * ln(2))"),
+            ("diff(x ** x, x):", "((x ** x) * (ln(x) + 1))"),
+            ("diff(log(2, x), x):", "(1 / (x * ln(2)))"),
+            ("diff(cos(x ** 2), x):", "((-sin((x ** 2))) * (2 * x))"),
+            ("diff(x ** 2 / 4, x):", "(0.5 * x)"),
+            // Other names are constants
+            ("diff(a * x + b, x):", "a"),
+            ("diff(pi * y, x):", "0"),
+            ("diff(x - x, x):", "0"),
+        ];
+        for (source, derivative) in derivatives {
+            assert_eq!(run(source), vec![string(derivative)], "{source}");
+        }
+    }
+
+    #[test]
+    fn equations() {
+        let source = "
+            roots([1, -6, 11, -6]): roots([2, -4]): roots([0, 1, 0, 0]): roots([1, -10, 35, -50, 24]):
+            solve x ** 2 - 3 * x + 2 = 0 for x: solve (x - 1) * (x + 2) = 0 for x: solve 2 * x = 4 for x:
+            let a = 3; solve x - a = 0 for x:
+            linsolve([[1, 1], [1, -1]], [3, 1]): solve [x + y = 3, x - y = 1] for [x, y]:
+            solve [x + y + z = 6, x - y = -1, 2 * z = 6] for [x, y, z]:
+        ";
+        assert_eq!(run(source), vec![
+            numbers(&[1.0, 2.0, 3.0]), numbers(&[2.0]), numbers(&[0.0, 0.0]), numbers(&[1.0, 2.0, 3.0, 4.0]),
+            numbers(&[1.0, 2.0]), numbers(&[-2.0, 1.0]), numbers(&[2.0]),
+            numbers(&[3.0]),
+            numbers(&[2.0, 1.0]), numbers(&[2.0, 1.0]),
+            numbers(&[1.0, 2.0, 3.0]),
+        ]);
+
+        // Complex roots are maps of the real and imaginary parts, after the real roots
+        assert_eq!(run("let r = solve x ** 4 = 16 for x; [r[0], r[1], r[2].re, r[2].im, r[3].im]:"), vec![numbers(&[-2.0, 2.0, 0.0, -2.0, 2.0])]);
+        assert_eq!(run("len(solve x ** 5 - x - 1 = 0 for x):"), vec![Value::Number(5.0)]);
+
+        // Repeated roots are found from the factors of the polynomial without them, so they stay exact
+        assert_eq!(run("solve (x + 1) ** 5 = 0 for x: solve (x - 1) ** 2 * (x + 2) ** 3 = 0 for x: solve (x - 3) ** 10 * (x + 0.5) = 0 for x:"), vec![
+            numbers(&[-1.0; 5]), numbers(&[-2.0, -2.0, -2.0, 1.0, 1.0]), numbers(&[[-0.5].as_slice(), &[3.0; 10]].concat()),
+        ]);
+
+        // The builtin function `solve` is still called without `for`
+        assert_eq!(run(r"solve(\x -> x - 1, 0):"), vec![Value::Number(1.0)]);
+
+        for source in ["solve sin(x) = 0 for x:", "solve 0 = 0 for x:", "solve [x + y = 2, x + y = 3] for [x, y]:", "solve [x * y = 2, x = 1] for [x, y]:", "roots([]):", "linsolve([[1]], [1, 2]):",
+            "solve ((x + 1) ** 64) ** 64 = 0 for x:", "solve x ** 100 = 0 for x:", "solve (x + 1) ** 64 = 0 for x:"] {
+            assert_eq!(run(source), vec![], "{source}");
+        }
+    }
+
+    #[test]
+    fn charts() {
+        let [Value::String(chart)] = &run("chart([1, 2, 3], Null, Null, Null):")[..] else { panic!("Expected the chart as a string") };
+        let lines = chart.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 17);
+        assert!(lines[0].starts_with("3 |") && lines[0].ends_with('*'), "{chart}");
+        assert!(lines[7].starts_with("2 |"), "{chart}");
+        assert!(lines[14].starts_with("1 |*"), "{chart}");
+        assert_eq!(lines[15], format!("  +{}", "-".repeat(60)));
+        assert!(lines[16].trim().starts_with('0') && lines[16].ends_with('2'), "{chart}");
+
+        // Several series have a legend, and functions are called from the start to the end
+        let [Value::String(chart)] = &run(r"chart([\x -> x, [1, 0]], 0, 1, Null):")[..] else { panic!("Expected the chart as a string") };
+        assert!(chart.contains('*') && chart.contains('+') && chart.ends_with("* 0  + 1"), "{chart}");
+
+        let path = std::env::temp_dir().join("vm-calc-chart.svg");
+        let source = format!(r#"chart(\x -> x ** 2, -1, 1, "{}"):"#, path.display());
+        assert_eq!(run(&source), vec![string(&format!("The plot was written to `{}`", path.display()))]);
+        let svg = std::fs::read_to_string(&path).unwrap();
+        assert!(svg.starts_with("<svg") && svg.contains("<polyline"));
+        std::fs::remove_file(path).unwrap();
+
+        for source in [r"chart(\x -> x, Null, Null, Null):", "chart([], Null, Null, Null):", "chart(1, Null, Null, Null):", r#"chart([1, "a"], Null, Null, Null):"#, "chart([1, 2], 1, 0, Null):", r#"chart(\x -> "a", 0, 1, Null):"#] {
+            assert_eq!(run(source), vec![], "{source}");
+        }
+    }
+}
\ No newline at end of file
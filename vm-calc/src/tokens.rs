@@ -1,8 +1,13 @@
 use std::fmt::{Debug, Display};
 
+use serde::Serialize;
+
 use crate::utils::Span;
 
-#[derive(Debug, PartialEq, Clone)]
+/// Identifiers that have a special meaning to the parser
+pub const KEYWORDS: [&str; 10] = ["let", "delete", "typeof", "Null", "match", "if", "import", "as", "for", "fn"];
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub enum NumberType {
     /// 0b1001
     Binary,
@@ -14,7 +19,7 @@ pub enum NumberType {
     Hex,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub enum TokenType {
     // Basic elements
     /// Numbers of any type
@@ -27,6 +32,20 @@ pub enum TokenType {
     /// A string: "abc", "\n " etc.
     String,
 
+    // Format strings, which are split into the text and the expressions within `{}`: f"x = {x:.3}"
+    /// f"
+    FormatStart,
+    /// The text between the expressions of a format string
+    FormatText,
+    /// { to start an expression within a format string
+    InterpolationStart,
+    /// The specifier after an expression within a format string, along with the `:` before it
+    FormatSpec,
+    /// } to end an expression within a format string
+    InterpolationEnd,
+    /// " to end a format string
+    FormatEnd,
+
     // Delimiters
     /// Semicolon to seperate statements
     Semicolon,
@@ -44,6 +63,15 @@ pub enum TokenType {
     /// ]
     CloseArray,
 
+    /// {
+    OpenMap,
+    /// }
+    CloseMap,
+    /// . to access a field of a map
+    Dot,
+    /// ... before the argument of a function that collects the rest of the arguments
+    Ellipsis,
+
     // Operators
     /// *
     Multiply,
@@ -102,6 +130,26 @@ pub enum TokenType {
 
     /// =
     Equal,
+    /// => to separate a pattern from its expression in a match
+    FatArrow,
+    /// \ to start a lambda: `\x y -> x + y`
+    Backslash,
+    /// -> to separate the arguments of a lambda from its body
+    Arrow,
+
+    // Comparisons
+    /// ==
+    IsEqual,
+    /// !=
+    NotEqual,
+    /// <
+    Less,
+    /// <=
+    LessEqual,
+    /// >
+    Greater,
+    /// >=
+    GreaterEqual,
 
     /// Null token for the parser
     /// Could have used an Option, but too lazy
@@ -111,6 +159,20 @@ pub enum TokenType {
     EOF,
 }
 
+impl TokenType {
+    /// The tokens that are always written the same way, which are the delimiters and operators
+    pub const SYMBOLS: [TokenType; 43] = [
+        Self::Semicolon, Self::Colon, Self::Comma, Self::OpeningBracket, Self::ClosingBracket, Self::OpenArray, Self::CloseArray,
+        Self::OpenMap, Self::CloseMap, Self::Dot, Self::Ellipsis,
+        Self::Multiply, Self::MultiplyEqual, Self::Modulo, Self::ModuloEqual, Self::Divide, Self::DivideEqual,
+        Self::Add, Self::AddEqual, Self::Subtract, Self::SubtractEqual, Self::Exponent, Self::ExponentEqual,
+        Self::BitXor, Self::BitXorEqual, Self::BitAnd, Self::BitAndEqual, Self::BitOr, Self::BitOrEqual,
+        Self::BitLeftShift, Self::BitLeftShiftEqual, Self::BitRightShift, Self::BitRightShiftEqual, Self::Equal, Self::FatArrow,
+        Self::Backslash, Self::Arrow,
+        Self::IsEqual, Self::NotEqual, Self::Less, Self::LessEqual, Self::Greater, Self::GreaterEqual,
+    ];
+}
+
 impl Display for TokenType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let string = match self {
@@ -124,6 +186,12 @@ impl Display for TokenType {
             },
             Self::Identifier => "Identifier",
             Self::String => "String",
+            Self::FormatStart => "f\"",
+            Self::FormatText => "Format String Text",
+            Self::InterpolationStart => "{",
+            Self::FormatSpec => "Format Specifier",
+            Self::InterpolationEnd => "}",
+            Self::FormatEnd => "\"",
             Self::Semicolon => ";",
             Self::Colon => ":",
             Self::Comma => ",",
@@ -131,6 +199,10 @@ impl Display for TokenType {
             Self::ClosingBracket => ")",
             Self::OpenArray => "[",
             Self::CloseArray => "]",
+            Self::OpenMap => "{",
+            Self::CloseMap => "}",
+            Self::Dot => ".",
+            Self::Ellipsis => "...",
             Self::Multiply => "*",
             Self::MultiplyEqual => "*=",
             Self::Modulo => "%",
@@ -154,6 +226,15 @@ impl Display for TokenType {
             Self::BitRightShift => ">>",
             Self::BitRightShiftEqual => ">>=",
             Self::Equal => "=",
+            Self::FatArrow => "=>",
+            Self::Backslash => "\\",
+            Self::Arrow => "->",
+            Self::IsEqual => "==",
+            Self::NotEqual => "!=",
+            Self::Less => "<",
+            Self::LessEqual => "<=",
+            Self::Greater => ">",
+            Self::GreaterEqual => ">=",
             Self::EOF => "End Of File",
             Self::Null => "Null token. A bug has occured if this has been presented to the output.",
         };
@@ -1,6 +1,8 @@
 use std::{ops::Range, fmt::Display};
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+use serde::Serialize;
+
+#[derive(Debug, PartialEq, Clone, Copy, Serialize)]
 pub struct Span {
     pub(crate) start: usize,
     pub(crate) end: usize,
@@ -1,18 +1,42 @@
-use std::{collections::HashMap, io::Write};
+use std::{collections::{BTreeMap, HashMap}, io::Write, ops::Range};
 
-use crate::{ast::Operator, functions::get_function, instruction::{Function, Instruction, Value}};
+use crate::{ast::{Operator, Pattern}, functions::{get_function, get_value_function}, instruction::{Function, Instruction, Value}, matrix, random::Random};
 
 pub enum VMError {
     InvalidBytecode,
     ErrString(String)
 }
 
+/// The name of an argument of a function, along with the instructions of its default value when it has one
+type Parameter<'a> = (&'a str, Option<Range<usize>>);
+
 pub struct VM<'a> {
     instructions: Vec<Instruction<'a>>,
     stack: Vec<Value>,
     pc: usize,
     pub(crate) outputs: Vec<Value>,
     symbols: HashMap<&'a str, Value>,
+    /// The values that the names bound by the patterns of a match had before, for each arm that is being run
+    bindings: Vec<Vec<(&'a str, Option<Value>)>>,
+    /// The names that only exist within the function or module that is being run, whose values are captured by the lambdas
+    /// created in it. The names declared by a module are the ones that it exports
+    locals: Option<Vec<&'a str>>,
+    /// The functions of the prelude as it first declared them, which are the only symbols a module is run with
+    prelude: HashMap<&'a str, Option<Value>>,
+    /// The modules that have been run, by the full path of their file
+    modules: HashMap<String, Module<'a>>,
+    /// The module that is being run, if any
+    module: Option<String>,
+    /// The generator of the random builtins
+    pub(crate) random: Random,
+}
+
+/// The symbols declared by a module, along with where its instructions were when it was run. The repl puts the instructions
+/// of a module in a different place on each line, so its functions are moved along with them
+#[derive(Debug, Clone)]
+pub struct Module<'a> {
+    start: usize,
+    declarations: HashMap<&'a str, Value>,
 }
 
 impl<'a> VM<'a> {
@@ -22,6 +46,12 @@ impl<'a> VM<'a> {
             stack: vec![],
             outputs: vec![],
             symbols: HashMap::new(),
+            bindings: vec![],
+            locals: None,
+            prelude: HashMap::new(),
+            modules: HashMap::new(),
+            module: None,
+            random: Random::from_time(),
             instructions,
         }
     }
@@ -32,10 +62,38 @@ impl<'a> VM<'a> {
             stack: vec![],
             outputs: vec![],
             symbols,
+            bindings: vec![],
+            locals: None,
+            prelude: HashMap::new(),
+            modules: HashMap::new(),
+            module: None,
+            random: Random::from_time(),
             instructions,
         }
     }
 
+    /// Marks the functions of the prelude by their names, so that modules can use them
+    pub fn with_prelude(mut self, names: Vec<&'a str>) -> Self {
+        self.prelude = names.into_iter().map(|name| (name, None)).collect();
+        self
+    }
+
+    /// Uses the modules that have already been run, such as those of the previous lines of the repl, so they are not run again
+    pub fn with_modules(mut self, modules: HashMap<String, Module<'a>>) -> Self {
+        self.modules = modules;
+        self
+    }
+
+    pub fn take_modules(&mut self) -> HashMap<String, Module<'a>> {
+        std::mem::take(&mut self.modules)
+    }
+
+    /// Uses the generator for the random builtins, such as one with a seed, or the one of the last line of the repl
+    pub fn with_random(mut self, random: Random) -> Self {
+        self.random = random;
+        self
+    }
+
     pub fn print_output(&self) {
         if self.outputs.len() > 0 {
             println!("Results: {}", self.outputs.iter().map(|value| format!("{value}")).collect::<Vec<_>>().join(", "));
@@ -56,6 +114,10 @@ impl<'a> VM<'a> {
                 Err(error) => {
                     // Stop the vm since a runtime error has occured.
                     self.pc = self.instructions.len();
+                    // The names bound by the match arms that were being run get their values back, since the symbols are kept
+                    while !self.bindings.is_empty() {
+                        self.unbind();
+                    }
                     match error {
                         VMError::InvalidBytecode => println!("[RUNTIME ERROR]: The bytecode provided to the VM appears to be invalid, or containing a bug that causes the program to unexpectedly crash"),
                         VMError::ErrString(string) => println!("[RUNTIME ERROR]: {string}"),
@@ -81,59 +143,7 @@ impl<'a> VM<'a> {
                     None => return Err(VMError::InvalidBytecode),
                 };
 
-                match (lhs, rhs) {
-                    (Value::Number(a), Value::Number(b)) => {
-                        let res = match operator {
-                            Operator::Plus => a + b,
-                            Operator::Minus => a - b,
-                            Operator::Multiply => a * b,
-                            Operator::Modulo => {
-                                if b == 0.0 {
-                                    return Err(VMError::ErrString(format!("Cannot take the modulus of a number by 0!")));
-                                }
-                                a % b
-                            },
-                            Operator::Divide => {
-                                if b == 0.0 {
-                                    return Err(VMError::ErrString(format!("Cannot divide a number by zero!")));
-                                }
-                                a / b
-                            },
-                            Operator::Exponent => a.powf(b),
-                            Operator::BitAnd => (a as usize & b as usize) as f64,
-                            Operator::BitOr => (a as usize | b as usize) as f64,
-                            Operator::BitXor => (a as usize ^ b as usize) as f64,
-                            Operator::BitLeftShift => ((a as usize) << (b as usize)) as f64,
-                            Operator::BitRightShift => ((a as usize) >> (b as usize)) as f64,
-                            _ => unimplemented!()
-                        };
-                        self.stack.push(Value::Number(res));
-                    }
-
-                    (Value::String(a), Value::String(b)) => {
-                        let res = match operator {
-                            Operator::Plus => {
-                                let mut base = a;
-                                base.push_str(&b);
-                                base
-                            },
-                            _ => return Err(VMError::ErrString(format!("Cannot perform binary operation `{operator}` on strings!")))
-                        };
-                        self.stack.push(Value::String(res));
-                    }
-
-                    (a, b) => {
-                        return Err(
-                            VMError::ErrString(
-                                format!(
-                                    "Cannot perform binary operation `{operator}` on {} types: lhs `{}` and rhs `{}`!",
-                                    if a.type_of() != b.type_of() { "mismatched" } else { "incompatible" },
-                                    a.type_of(), b.type_of()
-                                )
-                            )
-                        );
-                    }
-                }
+                self.stack.push(Self::binary(*operator, lhs, rhs)?);
             },
 
             Instruction::Unary { operator } => {
@@ -160,6 +170,7 @@ impl<'a> VM<'a> {
             },
 
             Instruction::LoadSymbolName { name } => {
+                Self::local(&mut self.locals, name);
                 self.symbols.insert(name, Value::Null);
                 self.stack.push(Value::Null);
             },
@@ -170,6 +181,7 @@ impl<'a> VM<'a> {
                     None => return Err(VMError::InvalidBytecode), 
                 };
                 self.stack.push(value.clone());
+                Self::local(&mut self.locals, name);
                 self.symbols.insert(name, value);
             },
 
@@ -199,6 +211,25 @@ impl<'a> VM<'a> {
                 
             },
 
+            Instruction::LoadPattern { pattern } | Instruction::ReloadPattern { pattern } => {
+                let value = match self.stack.last() {
+                    Some(value) => value,
+                    None => return Err(VMError::InvalidBytecode),
+                };
+                let mut bound = vec![];
+                Self::destructure(pattern, value, &mut bound)?;
+                if let Instruction::ReloadPattern { .. } = self.instructions[self.pc - 1] {
+                    if let Some((name, _)) = bound.iter().find(|(name, _)| !self.symbols.contains_key(name)) {
+                        return Err(VMError::ErrString(format!("Cannot assign a value to variable {name} because it does not exist!")));
+                    }
+                } else {
+                    for (name, _) in &bound {
+                        Self::local(&mut self.locals, name);
+                    }
+                }
+                self.symbols.extend(bound);
+            },
+
             Instruction::ReloadSymbolOp { name } => {
                 let operator = match &self.instructions[self.pc] {
                     Instruction::OData { operator } => operator,
@@ -256,6 +287,12 @@ impl<'a> VM<'a> {
                                 self.stack.push(Value::String(b.clone()));
                             }
 
+                            // Arrays are changed element by element, the same way as binary operations on them
+                            (new_value, value) if matches!(new_value, Value::Array(..)) || matches!(value, Value::Array(..)) => {
+                                *value = Self::binary(operator.without_assignment(), value.clone(), new_value)?;
+                                self.stack.push(value.clone());
+                            }
+
                             (new_value, value) => {
                                 return Err(
                                             VMError::ErrString(
@@ -275,21 +312,30 @@ impl<'a> VM<'a> {
             },
 
             // Really slow?
-            Instruction::FunctionCall { name, len } => {
-                let mut arguments = vec![];
+            Instruction::FunctionCall { name, len, names } => {
+                if let Ok((length, function)) = get_value_function(name.unwrap_or("")) {
+                    if self.stack.len() < length {
+                        return Err(VMError::ErrString(format!("Failed to get arguments to function {} (Likely an internal error)!", name.unwrap())));
+                    }
+                    // The arguments are taken off together so that they stay in the order they were given
+                    let arguments = self.stack.split_off(self.stack.len() - length);
+                    let result = function(self, arguments).map_err(VMError::ErrString)?;
+                    self.stack.push(result);
+                    return Ok(());
+                }
+
                 match get_function(name.unwrap_or("")) {
                     Ok((length, function)) => {
-                        for _ in 0..length {
-                            let arg = match self.stack.pop() {
-                                Some(value) => {
-                                    match value {
-                                        Value::Number(num) => num,
-                                        _ => return Err(VMError::ErrString(format!("Functions that do not deal with values other than numbers are not yet supported!"))),
-                                    }
-                                },
-                                None => return Err(VMError::ErrString(format!("Failed to get arguments to function {} (Likely an internal error)!", name.unwrap()))),
-                            };
-                            arguments.push(arg);
+                        if self.stack.len() < length {
+                            return Err(VMError::ErrString(format!("Failed to get arguments to function {} (Likely an internal error)!", name.unwrap())));
+                        }
+                        // Taken off together like the arguments above, so that `log(2, 8)` is not `log(8, 2)`
+                        let mut arguments = vec![];
+                        for value in self.stack.split_off(self.stack.len() - length) {
+                            match value {
+                                Value::Number(num) => arguments.push(num),
+                                _ => return Err(VMError::ErrString(format!("Functions that do not deal with values other than numbers are not yet supported!"))),
+                            }
                         }
                         self.stack.push(Value::Number(function(arguments.as_slice())))        
                     },
@@ -305,7 +351,7 @@ impl<'a> VM<'a> {
                                     self.stack.pop();
                                     Some(function)
                                 } else {
-                                    return if let None = name {
+                                    return if name.is_none() {
                                         Err(VMError::ErrString(format!("Cannot call a non function!")))
                                     } else {
                                         Err(VMError::ErrString(format!("The function `{}` does not exist!", name.unwrap_or("<DIRECT_CALL>"))))
@@ -314,105 +360,21 @@ impl<'a> VM<'a> {
                             }
                             _ => return Err(VMError::ErrString(format!("The function `{}` does not exist!", name.unwrap_or("<DIRECT_CALL>")))),
                         };
-                        if let Some(function) = function {
-                            let fn_args_address = function.instructions.start - function.arguments;
-                            let fn_body_address = function.instructions.start;
-                            let fn_body_end = function.instructions.end;
-
-                            let args_len = fn_body_address - fn_args_address;
-
-                            // This is a partial function call
-                            if *len + function.is_partial.len() < args_len {
-                                match self.symbols.get(name.unwrap_or("")) {
-                                    // A partial function call behind a variable reference
-                                    // Ex: let a = func(5); a(10):
-                                    Some(Value::Function(function)) => {
-                                        let mut function = function.clone();
-                                        for _ in 0..*len {
-                                            function.is_partial.push(self.stack.pop().unwrap());
-                                        }
-                                        self.stack.push(Value::Function(function));
-                                    }
-
-                                    _ => {
-                                        // A direct partial function call
-                                        // Ex: let a = func(5)(10); a:
-                                        if let None = name {
-                                            let mut function = function.clone();
-                                            for _ in 0..*len {
-                                                function.is_partial.push(self.stack.pop().unwrap());
-                                            }
-                                            self.stack.push(Value::Function(function));    
-                                        } else {
-                                            return Err(VMError::ErrString(format!("The partially called function `{}` does not exist!", name.unwrap())))
-                                        }
-                                    },
-                                };                
-                            } 
-                            // Too many arguments provided to function
-                            else if  *len + function.is_partial.len() > args_len {
-                                return Err(VMError::ErrString(format!("The function `{}` was provided too many arguments! Expected: {args_len}, got: {} argument(s)", name.unwrap_or("<PARTIAL_FUNCTION>"), *len + function.is_partial.len())))
-                            }
-                            else {
-                                let orig_pc = self.pc;
-                                let orig_symbols = self.symbols.clone();
-                                self.pc = fn_args_address;
-                                let mut start = 0;
-                                let mut predefined_args = function.is_partial.clone();
-                                let offset = function.is_partial.len();
-    
-                                for i in (0..args_len).rev() {
-                                    let arg = &self.instructions[self.pc + i];
-                                    match arg {
-                                        Instruction::ArgumentName { name } => {
-                                            if args_len - start > offset {
-                                                let value = match self.stack.pop() {
-                                                    Some(value) => value,
-                                                    None => return Err(VMError::InvalidBytecode),
-                                                };
-                                                self.symbols.insert(name, value);
-                                                start += 1; 
-                                            } else {
-                                                self.symbols.insert(name, predefined_args.pop().unwrap());
-                                            }
-                                        }
-    
-                                        _ => return Err(VMError::InvalidBytecode),
-                                    }
-                                }
-                                
-                                self.pc = fn_body_address;
-                                
-                                for _ in 0..(fn_body_end - fn_body_address) {
-                                    self.execute_next()?;
-                                    // A stack overflow used to occur when a function declaration was used during initializaion of another 
-                                    // function like `let a _ = (let b _ = 1); a()();`, This would cause a stack overflow due to the function 
-                                    // escaping it's bounds and calling `a` again
-                                    if self.pc >= fn_body_end { break; }
-                                }
-    
-                                self.pc = orig_pc;
-                                self.symbols = orig_symbols;    
+                        if let Some(mut function) = function {
+                            let (name, len, names) = (*name, *len, names.clone());
+                            self.give_arguments(&mut function, len, &names, name).map_err(VMError::ErrString)?;
+                            if self.is_ready(&function)? {
+                                self.run_function(&function)?;
+                            } else {
+                                // This is a partial function call, which waits for the rest of the arguments
+                                // Ex: let a = func(5); a(10):
+                                self.stack.push(Value::Function(function));
                             }
                         }
                     } 
                 };
             }
 
-            Instruction::PartialCall { name, len } => {
-                match self.symbols.get(name) {
-                    Some(Value::Function(function)) => {
-                        let mut function = function.clone();
-                        for _ in 0..*len {
-                            function.is_partial.push(self.stack.pop().unwrap());
-                        }
-                        self.stack.push(Value::Function(function));
-                    }
-
-                    _ => (),
-                };
-            }
-
             Instruction::Null => self.stack.push(Value::Null),
 
             Instruction::Delete { name } => {
@@ -426,29 +388,87 @@ impl<'a> VM<'a> {
                 self.stack.push(Value::Null);
             }
 
+            Instruction::Import { module, alias, len } => {
+                let (module, alias, len) = (module.clone(), *alias, *len);
+                let start = self.pc;
+                // A module is only run the first time it is imported. It is run with only the functions of the prelude, so that
+                // it declares the same symbols wherever it is imported from, and the symbols that it declares are its own
+                if !self.modules.contains_key(&module) {
+                    let prelude = self.prelude.iter().filter_map(|(name, value)| Some((*name, value.clone()?))).collect();
+                    let symbols = std::mem::replace(&mut self.symbols, prelude);
+                    let locals = self.locals.replace(vec![]);
+                    let (depth, outputs, bindings) = (self.stack.len(), self.outputs.len(), self.bindings.len());
+                    let parent = self.module.replace(module.clone());
+                    let result = self.execute_range(start..(start + len));
+                    self.module = parent;
+                    self.bindings.truncate(bindings);
+                    let declared = std::mem::replace(&mut self.locals, locals).unwrap_or_default();
+                    let mut declarations = std::mem::replace(&mut self.symbols, symbols);
+                    result?;
+                    declarations.retain(|name, _| declared.contains(name));
+                    // Only the declarations of the module are kept, not the values of its statements
+                    self.stack.truncate(depth);
+                    self.outputs.truncate(outputs);
+                    self.modules.insert(module.clone(), Module { start, declarations });
+                }
+                self.move_module(&module, start, len);
+                self.pc = start + len;
+
+                let declarations = &self.modules[&module].declarations;
+                match alias {
+                    Some(alias) => {
+                        let map = declarations.iter().map(|(name, value)| (name.to_string(), value.clone())).collect();
+                        self.symbols.insert(alias, Value::Map(map));
+                        Self::local(&mut self.locals, alias);
+                    }
+                    None => {
+                        for name in declarations.keys() {
+                            Self::local(&mut self.locals, name);
+                        }
+                        self.symbols.extend(declarations.clone());
+                    }
+                }
+                self.stack.push(Value::Null);
+            }
+
             Instruction::Array { len } => {
                 let mut array = Vec::with_capacity(*len);
                 array.extend(self.stack.drain((self.stack.len() - *len)..));
                 self.stack.push(Value::Array(array));
             }
 
+            Instruction::Map { len } => {
+                let mut map = BTreeMap::new();
+                let mut entries = self.stack.split_off(self.stack.len() - *len * 2).into_iter();
+                while let (Some(key), Some(value)) = (entries.next(), entries.next()) {
+                    match key {
+                        Value::String(key) => { map.insert(key, value); }
+                        key => return Err(VMError::ErrString(format!("The keys of a map have to be strings, found a value of type {}!", key.type_of()))),
+                    }
+                }
+                self.stack.push(Value::Map(map));
+            }
+
             Instruction::FunctionDecl { name } => {
-                let args = match self.instructions[self.pc] {
-                    Instruction::UData { number } => number,
-                    _ => return Err(VMError::InvalidBytecode),
-                };
-                self.pc += 1;
+                let name = *name;
+                let function = self.function_body()?;
+                if let Some(prelude @ None) = self.prelude.get_mut(name).filter(|_| self.module.is_none()) {
+                    *prelude = Some(Value::Function(function.clone()));
+                }
+                Self::local(&mut self.locals, name);
+                self.symbols.insert(name, Value::Function(function.clone()));
+                self.stack.push(Value::Function(function));
+            }
 
-                let end = match self.instructions[self.pc] {
-                    Instruction::UData { number } => number,
-                    _ => return Err(VMError::InvalidBytecode),
-                };
-                self.pc += 1;
-                let fn_body_address = self.pc + args;
-                let fn_body_end = self.pc + end;
-                self.pc += end;
-                self.symbols.insert(name, Value::Function(Function::new(args, fn_body_address..fn_body_end)));
-                self.stack.push(Value::Function(Function::new(args, fn_body_address..fn_body_end)));
+            Instruction::Lambda { captures } => {
+                // Only the local names are captured, so that a lambda still sees the later values of global variables
+                let captured = captures.iter().map(|name| {
+                    let local = self.locals.iter().flatten().chain(self.bindings.iter().flatten().map(|(name, _)| name)).any(|local| local == name);
+                    self.symbols.get(name).filter(|_| local).cloned()
+                }).collect();
+                let mut function = self.function_body()?;
+                function.captured = captured;
+                self.stack.push(Value::Function(function));
             }
 
             Instruction::Print { depth } => {
@@ -462,30 +482,113 @@ impl<'a> VM<'a> {
                 self.stack.push(Value::Null);
             }
 
+            Instruction::Pop => {
+                self.stack.pop();
+            },
+
+            Instruction::Jump { offset } => self.pc += offset,
+
+            Instruction::JumpIfFalse { offset } => {
+                match self.stack.pop() {
+                    Some(Value::Number(number)) => {
+                        if number == 0.0 {
+                            self.pc += offset;
+                        }
+                    }
+                    Some(value) => return Err(VMError::ErrString(format!("A condition has to be a value of type {{Number}}, not {}!", value.type_of()))),
+                    None => return Err(VMError::InvalidBytecode),
+                }
+            },
+
+            Instruction::MatchPattern { pattern } => {
+                let value = match self.stack.last() {
+                    Some(value) => value,
+                    None => return Err(VMError::InvalidBytecode),
+                };
+                let mut bound = vec![];
+                let matched = Self::match_pattern(pattern, value, &mut bound);
+                if matched {
+                    self.bindings.push(bound.iter().map(|(name, _)| (*name, self.symbols.get(name).cloned())).collect());
+                    self.symbols.extend(bound);
+                }
+                self.stack.push(Value::Number(if matched { 1.0 } else { 0.0 }));
+            },
+
+            Instruction::Unbind => {
+                if self.bindings.is_empty() {
+                    return Err(VMError::InvalidBytecode);
+                }
+                self.unbind();
+            },
+
+            Instruction::NoMatch => {
+                let value = match self.stack.pop() {
+                    Some(value) => value,
+                    None => return Err(VMError::InvalidBytecode),
+                };
+                return Err(VMError::ErrString(format!("None of the patterns of the match matched the value `{value}` of type {}!", value.type_of())));
+            },
+
             Instruction::TypeOf => {
                 let string = self.stack.pop().unwrap().type_of().to_owned();
                 self.stack.push(Value::String(string));
             },
 
+            Instruction::Format { spec } => {
+                let value = match self.stack.pop() {
+                    Some(value) => value,
+                    None => return Err(VMError::InvalidBytecode),
+                };
+                self.stack.push(Value::String(spec.apply(&value).map_err(VMError::ErrString)?));
+            },
+
             Instruction::Index => {
                 let expression = self.stack.pop().unwrap();
                 let to_index = self.stack.pop().unwrap();
                 match (to_index, expression) {
                     (Value::Array(array), Value::Number(number)) => {
-                        let index = number as usize;
-                        if array.len() >= index + 1 {
-                            self.stack.push(array[index].clone());
-                        } else {
-                            return Err(VMError::ErrString(format!("Indexing out of bounds of the array!")));
+                        let index = Self::position(number, array.len())?;
+                        self.stack.push(array[index].clone());
+                    }
+
+                    (Value::Map(mut map), Value::String(key)) => {
+                        match map.remove(&key) {
+                            Some(value) => self.stack.push(value),
+                            None => return Err(VMError::ErrString(format!("The key `{key}` does not exist in the map!"))),
                         }
                     }
 
+                    // Strings are indexed by character rather than by byte
+                    (Value::String(string), Value::Number(number)) => {
+                        let index = Self::position(number, string.chars().count())?;
+                        self.stack.push(Value::String(string.chars().nth(index).unwrap().to_string()));
+                    }
+
                     (a, b) => return Err(VMError::ErrString(format!("Unable to index type {} by type {}", a.type_of(), b.type_of()))),
                 };
             },
+
+            Instruction::Slice => {
+                let [start, end, step] = Self::pop_bounds(&mut self.stack)?;
+                match self.stack.pop() {
+                    Some(Value::Array(array)) => {
+                        let positions = Self::slice_positions(array.len(), start, end, step)?;
+                        self.stack.push(Value::Array(positions.into_iter().map(|i| array[i].clone()).collect()));
+                    }
+
+                    Some(Value::String(string)) => {
+                        let characters = string.chars().collect::<Vec<_>>();
+                        let positions = Self::slice_positions(characters.len(), start, end, step)?;
+                        self.stack.push(Value::String(positions.into_iter().map(|i| characters[i]).collect()));
+                    }
+
+                    Some(value) => return Err(VMError::ErrString(format!("Cannot slice a value of type {}!", value.type_of()))),
+                    None => return Err(VMError::InvalidBytecode),
+                }
+            },
             
             // Change array values
-            Instruction::ReloadIndex { name, depth, operator } => {
+            Instruction::ReloadIndex { name, depth, operator, slice } => {
 
                 let value = 
                     match self.stack.pop() {
@@ -495,65 +598,109 @@ impl<'a> VM<'a> {
 
                 if let Some(item) = self.symbols.get_mut(name) {
                     let mut item = item;
-                    for _ in 0..*depth {
+                    for level in 0..*depth {
                         let index = match self.stack.pop() {
-                            Some(Value::Number(value)) => value as usize,
-                            Some(a) => return Err(VMError::ErrString(format!("Cannot index an array by type {}!", a.type_of()))),
+                            Some(value) => value,
                             None => return Err(VMError::InvalidBytecode),
                         };
-                        if let Value::Array(inside_item) = item {
-                            match inside_item.get_mut(index) {
-                                Some(inside_item) => item = inside_item,
-                                None =>
-                                    return Err(VMError::ErrString(format!("Indexing out of bounds of array `{name}`!")))
+                        item = match (item, index) {
+                            (Value::Array(inside_item), Value::Number(index)) => {
+                                let index = Self::position(index, inside_item.len())?;
+                                &mut inside_item[index]
                             }
-                        } else {
-                            return Err(VMError::ErrString(format!("Indexing out of bounds of array `{name}`!")))
-                        }
+                            (Value::Map(map), Value::String(key)) => {
+                                // Assigning to a key that is not in a map adds it, but an operation needs the value it already has
+                                if level + 1 == *depth && !*slice && *operator == Operator::Equal {
+                                    map.entry(key).or_insert(Value::Null)
+                                } else {
+                                    match map.get_mut(&key) {
+                                        Some(inside_item) => inside_item,
+                                        None => return Err(VMError::ErrString(format!("The key `{key}` does not exist in the map `{name}`!"))),
+                                    }
+                                }
+                            }
+                            (Value::Array(..), a) => return Err(VMError::ErrString(format!("Cannot index an array by type {}!", a.type_of()))),
+                            (Value::Map(..), a) => return Err(VMError::ErrString(format!("Cannot index a map by type {}!", a.type_of()))),
+                            (a, _) => return Err(VMError::ErrString(format!("Cannot index a value of type {} in `{name}`!", a.type_of()))),
+                        };
                     }
 
-                    match (&mut item, value) {
-                        (Value::Number(a), Value::Number(b)) => {
-                            match operator {
-                                Operator::Equal => *a = b,
-                                Operator::PlusEqual => *a += b,
-                                Operator::MinusEqual => *a -= b,
-                                Operator::MultiplyEqual => *a *= b,
-                                Operator::DivideEqual => {
-                                    if b == 0.0 {
-                                        return Err(VMError::ErrString(format!("Cannot divide a number by 0!")));
-                                    }
-                                    *a /= b
-                                },
-                                Operator::ModuloEqual => {
-                                    if b == 0.0 {
-                                        return Err(VMError::ErrString(format!("Cannot take the modulus of a number by 0!")));
-                                    }
-                                    *a %= b
-                                },
-                                Operator::ExponentEqual => *a = a.powf(b),
-                                Operator::BitOrEqual => *a = (*a as usize | (b as usize)) as f64,
-                                Operator::BitAndEqual => *a = (*a as usize & (b as usize)) as f64,
-                                Operator::BitXorEqual => *a = (*a as usize ^ (b as usize)) as f64,
-                                Operator::BitLeftShiftEqual => *a = ((*a as usize) << (b as usize)) as f64,
-                                Operator::BitRightShiftEqual => *a = ((*a as usize) >> (b as usize)) as f64,
-
-                                _ => unimplemented!()
+                    if *slice {
+                        let [start, end, step] = Self::pop_bounds(&mut self.stack)?;
+                        let Value::Array(array) = item else {
+                            return Err(VMError::ErrString(format!("Cannot assign to a slice of a value of type {}!", item.type_of())));
+                        };
+                        let positions = Self::slice_positions(array.len(), start, end, step)?;
+                        let value = match operator {
+                            Operator::Equal => value,
+                            // The operation is done with the slice, and the result is assigned back to it
+                            operator => {
+                                let current = Value::Array(positions.iter().map(|i| array[*i].clone()).collect());
+                                Self::binary(operator.without_assignment(), current, value)?
                             }
-                        },
+                        };
+                        let Value::Array(values) = value else {
+                            return Err(VMError::ErrString(format!("Cannot assign a value of type {} to a slice, only an array can be!", value.type_of())));
+                        };
 
-                        (Value::String(a), Value::String(b)) => {
-                            match operator {
-                                Operator::Equal => *a = b,
-                                Operator::PlusEqual => *a = format!("{a}{b}"),
-                                _ => return Err(VMError::ErrString(format!("Cannot perform operation `{operator}` on strings!")))
+                        // A slice with a step of 1 can be replaced by an array of any length, like in Python
+                        if step.unwrap_or(1.0) == 1.0 {
+                            let (start, _, _) = Self::slice_bounds(array.len(), start, end, step)?;
+                            let from = positions.first().copied().unwrap_or(start as usize);
+                            array.splice(from..(from + positions.len()), values);
+                        } else if positions.len() != values.len() {
+                            return Err(VMError::ErrString(format!(
+                                "Cannot assign an array of length {} to a slice of length {}!", values.len(), positions.len(),
+                            )));
+                        } else {
+                            for (position, value) in positions.into_iter().zip(values) {
+                                array[position] = value;
                             }
                         }
-
-                        (a, b) => {
-                            match operator {
-                                Operator::Equal => **a = b,
-                                _ => return Err(VMError::ErrString(format!("Cannot perform operation {operator} on incompatible types {} and {}!", a.type_of(), b.type_of())))
+                    } else {
+                        match (&mut item, value) {
+                            (Value::Number(a), Value::Number(b)) => {
+                                match operator {
+                                    Operator::Equal => *a = b,
+                                    Operator::PlusEqual => *a += b,
+                                    Operator::MinusEqual => *a -= b,
+                                    Operator::MultiplyEqual => *a *= b,
+                                    Operator::DivideEqual => {
+                                        if b == 0.0 {
+                                            return Err(VMError::ErrString(format!("Cannot divide a number by 0!")));
+                                        }
+                                        *a /= b
+                                    },
+                                    Operator::ModuloEqual => {
+                                        if b == 0.0 {
+                                            return Err(VMError::ErrString(format!("Cannot take the modulus of a number by 0!")));
+                                        }
+                                        *a %= b
+                                    },
+                                    Operator::ExponentEqual => *a = a.powf(b),
+                                    Operator::BitOrEqual => *a = (*a as usize | (b as usize)) as f64,
+                                    Operator::BitAndEqual => *a = (*a as usize & (b as usize)) as f64,
+                                    Operator::BitXorEqual => *a = (*a as usize ^ (b as usize)) as f64,
+                                    Operator::BitLeftShiftEqual => *a = ((*a as usize) << (b as usize)) as f64,
+                                    Operator::BitRightShiftEqual => *a = ((*a as usize) >> (b as usize)) as f64,
+    
+                                    _ => unimplemented!()
+                                }
+                            },
+    
+                            (Value::String(a), Value::String(b)) => {
+                                match operator {
+                                    Operator::Equal => *a = b,
+                                    Operator::PlusEqual => *a = format!("{a}{b}"),
+                                    _ => return Err(VMError::ErrString(format!("Cannot perform operation `{operator}` on strings!")))
+                                }
+                            }
+    
+                            (a, b) => {
+                                match operator {
+                                    Operator::Equal => **a = b,
+                                    _ => return Err(VMError::ErrString(format!("Cannot perform operation {operator} on incompatible types {} and {}!", a.type_of(), b.type_of())))
+                                }
                             }
                         }
                     }
@@ -578,6 +725,499 @@ impl<'a> VM<'a> {
         Ok(())
     }
 
+    /// Performs a binary operation on two values
+    fn binary(operator: Operator, lhs: Value, rhs: Value) -> Result<Value, VMError> {
+        // Values of any type are compared as a whole, rather than element by element
+        if matches!(operator, Operator::IsEqual | Operator::NotEqual) {
+            return Ok(Value::Number(f64::from((lhs == rhs) == (operator == Operator::IsEqual))));
+        }
+
+        match (lhs, rhs) {
+            (Value::Number(a), Value::Number(b)) => {
+                let res = match operator {
+                    Operator::Plus => a + b,
+                    Operator::Minus => a - b,
+                    Operator::Multiply => a * b,
+                    Operator::Modulo => {
+                        if b == 0.0 {
+                            return Err(VMError::ErrString(format!("Cannot take the modulus of a number by 0!")));
+                        }
+                        a % b
+                    },
+                    Operator::Divide => {
+                        if b == 0.0 {
+                            return Err(VMError::ErrString(format!("Cannot divide a number by zero!")));
+                        }
+                        a / b
+                    },
+                    Operator::Exponent => a.powf(b),
+                    Operator::BitAnd => (a as usize & b as usize) as f64,
+                    Operator::BitOr => (a as usize | b as usize) as f64,
+                    Operator::BitXor => (a as usize ^ b as usize) as f64,
+                    Operator::BitLeftShift => ((a as usize) << (b as usize)) as f64,
+                    Operator::BitRightShift => ((a as usize) >> (b as usize)) as f64,
+                    Operator::Less => f64::from(a < b),
+                    Operator::LessEqual => f64::from(a <= b),
+                    Operator::Greater => f64::from(a > b),
+                    Operator::GreaterEqual => f64::from(a >= b),
+                    _ => unimplemented!()
+                };
+                Ok(Value::Number(res))
+            }
+
+            (Value::String(a), Value::String(b)) => {
+                let res = match operator {
+                    // Strings are compared character by character
+                    Operator::Less => return Ok(Value::Number(f64::from(a < b))),
+                    Operator::LessEqual => return Ok(Value::Number(f64::from(a <= b))),
+                    Operator::Greater => return Ok(Value::Number(f64::from(a > b))),
+                    Operator::GreaterEqual => return Ok(Value::Number(f64::from(a >= b))),
+                    Operator::Plus => {
+                        let mut base = a;
+                        base.push_str(&b);
+                        base
+                    },
+                    _ => return Err(VMError::ErrString(format!("Cannot perform binary operation `{operator}` on strings!")))
+                };
+                Ok(Value::String(res))
+            }
+
+            // Repeating a string a number of times
+            (Value::String(string), Value::Number(count)) | (Value::Number(count), Value::String(string)) if operator == Operator::Multiply => {
+                if count < 0.0 || count.fract() != 0.0 {
+                    return Err(VMError::ErrString(format!("Cannot repeat a string {count} times, the count must be a whole, positive number!")));
+                }
+                Ok(Value::String(string.repeat(count as usize)))
+            }
+
+            // Arrays are worked on element by element. Like NumPy, the shapes of the arrays are lined up from their last
+            // dimension, so an array with fewer dimensions is used with every element of the other one
+            (Value::Array(a), Value::Array(b)) => {
+                let (a_shape, b_shape) = (matrix::shape(&a), matrix::shape(&b));
+                if a_shape.iter().rev().zip(b_shape.iter().rev()).any(|(a, b)| a != b) {
+                    return Err(VMError::ErrString(format!(
+                        "Cannot perform binary operation `{operator}` on arrays of different shapes: lhs {a_shape:?} and rhs {b_shape:?}!",
+                    )));
+                }
+                let result = if a_shape.len() > b_shape.len() {
+                    let b = Value::Array(b);
+                    a.into_iter().map(|a| Self::binary(operator, a, b.clone())).collect::<Result<_, _>>()?
+                } else if b_shape.len() > a_shape.len() {
+                    let a = Value::Array(a);
+                    b.into_iter().map(|b| Self::binary(operator, a.clone(), b)).collect::<Result<_, _>>()?
+                } else {
+                    a.into_iter().zip(b).map(|(a, b)| Self::binary(operator, a, b)).collect::<Result<_, _>>()?
+                };
+                Ok(Value::Array(result))
+            }
+
+            (Value::Array(a), b) => Ok(Value::Array(a.into_iter().map(|a| Self::binary(operator, a, b.clone())).collect::<Result<_, _>>()?)),
+
+            (a, Value::Array(b)) => Ok(Value::Array(b.into_iter().map(|b| Self::binary(operator, a.clone(), b)).collect::<Result<_, _>>()?)),
+
+            (a, b) => {
+                Err(
+                    VMError::ErrString(
+                        format!(
+                            "Cannot perform binary operation `{operator}` on {} types: lhs `{}` and rhs `{}`!",
+                            if a.type_of() != b.type_of() { "mismatched" } else { "incompatible" },
+                            a.type_of(), b.type_of()
+                        )
+                    )
+                )
+            }
+        }
+    }
+
+    /// Checks whether a value matches a pattern, adding the names that the pattern binds along with their values
+    fn match_pattern(pattern: &Pattern<'a>, value: &Value, bound: &mut Vec<(&'a str, Value)>) -> bool {
+        match (pattern, value) {
+            (Pattern::Wildcard, _) => true,
+            (Pattern::Binding { name }, value) => {
+                bound.push((name, value.clone()));
+                true
+            }
+            (Pattern::Literal { value: literal }, value) => literal == value,
+            (Pattern::Array { patterns }, Value::Array(values)) => {
+                patterns.len() == values.len() && patterns.iter().zip(values).all(|(pattern, value)| Self::match_pattern(pattern, value, bound))
+            }
+            (Pattern::Array { .. }, _) => false,
+            (Pattern::Type { pattern, type_name }, value) => {
+                value.type_of() == format!("{{{type_name}}}") && Self::match_pattern(pattern, value, bound)
+            }
+        }
+    }
+
+    /// Takes apart a value following a pattern of names and arrays, adding the names along with their values
+    fn destructure(pattern: &Pattern<'a>, value: &Value, bound: &mut Vec<(&'a str, Value)>) -> Result<(), VMError> {
+        match (pattern, value) {
+            (Pattern::Wildcard, _) => Ok(()),
+            (Pattern::Binding { name }, value) => {
+                bound.push((name, value.clone()));
+                Ok(())
+            }
+            (Pattern::Array { patterns }, Value::Array(values)) => {
+                if patterns.len() != values.len() {
+                    return Err(VMError::ErrString(format!(
+                        "Cannot take apart an array of length {} with the pattern `{pattern}`, which needs {} values!", values.len(), patterns.len(),
+                    )));
+                }
+                patterns.iter().zip(values).try_for_each(|(pattern, value)| Self::destructure(pattern, value, bound))
+            }
+            (Pattern::Array { .. }, value) => {
+                Err(VMError::ErrString(format!("Cannot take apart a value of type {} with the array pattern `{pattern}`!", value.type_of())))
+            }
+            (Pattern::Literal { .. } | Pattern::Type { .. }, _) => Err(VMError::InvalidBytecode),
+        }
+    }
+
+    /// Finds the position of an index within a value of the length. Negative indices count from the end, like in Python
+    fn position(number: f64, length: usize) -> Result<usize, VMError> {
+        if number.fract() != 0.0 || !number.is_finite() {
+            return Err(VMError::ErrString(format!("Cannot index by {number}, an index has to be a whole number!")));
+        }
+        let index = if number < 0.0 { number + length as f64 } else { number };
+        if index < 0.0 || index >= length as f64 {
+            return Err(VMError::ErrString(format!("The index {number} is out of bounds of a value of length {length}!")));
+        }
+        Ok(index as usize)
+    }
+
+    /// Takes the start, end and step of a slice off the stack, where a null value is a bound that was left out
+    fn pop_bounds(stack: &mut Vec<Value>) -> Result<[Option<f64>; 3], VMError> {
+        let mut bounds = [None; 3];
+        for bound in bounds.iter_mut().rev() {
+            *bound = match stack.pop() {
+                Some(Value::Null) => None,
+                Some(Value::Number(number)) if number.fract() == 0.0 && number.is_finite() => Some(number),
+                Some(Value::Number(number)) => return Err(VMError::ErrString(format!("Cannot slice by {number}, the bounds of a slice have to be whole numbers!"))),
+                Some(value) => return Err(VMError::ErrString(format!("The bounds of a slice have to be numbers, found a value of type {}!", value.type_of()))),
+                None => return Err(VMError::InvalidBytecode),
+            };
+        }
+        Ok(bounds)
+    }
+
+    /// The start, end and step of a slice of a value of the length, with the bounds that were left out filled in
+    /// and the ones out of range clamped the same way as Python
+    fn slice_bounds(length: usize, start: Option<f64>, end: Option<f64>, step: Option<f64>) -> Result<(f64, f64, f64), VMError> {
+        let step = step.unwrap_or(1.0);
+        if step == 0.0 {
+            return Err(VMError::ErrString("The step of a slice cannot be 0!".to_string()));
+        }
+        let length = length as f64;
+        // A negative step goes down to before the first element
+        let (lower, upper) = if step > 0.0 { (0.0, length) } else { (-1.0, length - 1.0) };
+        let clamp = |bound: f64| (if bound < 0.0 { bound + length } else { bound }).clamp(lower, upper);
+        let start = start.map(clamp).unwrap_or(if step > 0.0 { lower } else { upper });
+        let end = end.map(clamp).unwrap_or(if step > 0.0 { upper } else { lower });
+        Ok((start, end, step))
+    }
+
+    /// The positions of the elements in a slice, in order
+    fn slice_positions(length: usize, start: Option<f64>, end: Option<f64>, step: Option<f64>) -> Result<Vec<usize>, VMError> {
+        let (start, end, step) = Self::slice_bounds(length, start, end, step)?;
+        let mut positions = vec![];
+        let mut position = start;
+        while (step > 0.0 && position < end) || (step < 0.0 && position > end) {
+            positions.push(position as usize);
+            position += step;
+        }
+        Ok(positions)
+    }
+
+    /// Calls a function with the arguments that are at the top of the stack, leaving its result on the stack.
+    /// The arguments that the function was partially called with come before the ones on the stack
+    fn run_function(&mut self, function: &Function) -> Result<(), VMError> {
+        let parameters = self.parameters(function)?;
+        let orig_pc = self.pc;
+        let orig_symbols = self.symbols.clone();
+        let mut locals = parameters.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+
+        // A function of a module can use the other declarations of the module, wherever it is called from
+        if function.module != self.module {
+            if let Some(module) = function.module.as_ref().and_then(|module| self.modules.get(module)) {
+                self.symbols.extend(module.declarations.clone());
+            }
+        }
+
+        // A lambda is run with the values that it captured, which come before the arguments since those can have the same names
+        if let Some(Instruction::Lambda { captures }) = function.parameters.checked_sub(3).and_then(|address| self.instructions.get(address)) {
+            for (name, value) in captures.iter().zip(&function.captured) {
+                if let Some(value) = value {
+                    self.symbols.insert(name, value.clone());
+                    locals.push(name);
+                }
+            }
+        }
+        let orig_locals = self.locals.replace(locals);
+        let depth = self.bindings.len();
+
+        let result = self.run_body(function, parameters);
+
+        // The arguments and the names bound within the function are gone after it, even when it stopped with an error
+        self.bindings.truncate(depth);
+        self.pc = orig_pc;
+        self.symbols = orig_symbols;
+        self.locals = orig_locals;
+        result
+    }
+
+    /// Gives a function its arguments, including the default values of those that were not given, and runs its body
+    fn run_body(&mut self, function: &Function, parameters: Vec<Parameter<'a>>) -> Result<(), VMError> {
+        // The given arguments are set first, so that the default values can use them
+        for ((name, _), value) in parameters.iter().zip(&function.is_partial) {
+            if let Some(value) = value {
+                self.symbols.insert(name, value.clone());
+            }
+        }
+        // The rest argument is an empty array when there were no arguments left over for it
+        if function.is_variadic && !matches!(function.is_partial.last(), Some(Some(..))) {
+            let (name, _) = parameters.last().ok_or(VMError::InvalidBytecode)?;
+            self.symbols.insert(name, Value::Array(vec![]));
+        }
+        for (index, (name, default)) in parameters.into_iter().enumerate() {
+            if let (None | Some(None), Some(default)) = (function.is_partial.get(index), default) {
+                self.execute_range(default)?;
+                let value = self.stack.pop().ok_or(VMError::InvalidBytecode)?;
+                self.symbols.insert(name, value);
+            }
+        }
+
+        self.execute_range(function.instructions.clone())
+    }
+
+    /// Gives the names bound by the last pattern that matched their previous values
+    fn unbind(&mut self) {
+        for (name, value) in self.bindings.pop().into_iter().flatten() {
+            match value {
+                Some(value) => { self.symbols.insert(name, value); },
+                None => { self.symbols.remove(name); },
+            }
+        }
+    }
+
+    /// Marks a name that is declared as local to the function that is being run, if any
+    fn local(locals: &mut Option<Vec<&'a str>>, name: &'a str) {
+        if let Some(locals) = locals {
+            locals.push(name);
+        }
+    }
+
+    /// Executes the instructions of the body of a function, or of the default value of an argument
+    fn execute_range(&mut self, range: Range<usize>) -> Result<(), VMError> {
+        self.pc = range.start;
+        for _ in 0..range.len() {
+            self.execute_next()?;
+            // A stack overflow used to occur when a function declaration was used during initializaion of another 
+            // function like `let a _ = (let b _ = 1); a()();`, This would cause a stack overflow due to the function 
+            // escaping it's bounds and calling `a` again
+            if self.pc >= range.end { break; }
+        }
+        Ok(())
+    }
+
+    /// Reads the number of arguments and the length of a function that follow its declaration, skipping over its body
+    fn function_body(&mut self) -> Result<Function, VMError> {
+        let args = match self.instructions[self.pc] {
+            Instruction::UData { number } => number,
+            _ => return Err(VMError::InvalidBytecode),
+        };
+        self.pc += 1;
+
+        let end = match self.instructions[self.pc] {
+            Instruction::UData { number } => number,
+            _ => return Err(VMError::InvalidBytecode),
+        };
+        self.pc += 1;
+
+        // The body comes after the arguments and the instructions of their default values
+        let mut function = Function::new(args, self.pc, 0..0);
+        let mut fn_body_address = self.pc + args;
+        for (_, default) in self.parameters(&function)? {
+            fn_body_address += default.map_or(0, |default| default.len());
+        }
+        function.module = self.module.clone();
+        function.is_variadic = args > 0 && matches!(self.instructions.get(fn_body_address - 1), Some(Instruction::RestArgument { .. }));
+        function.instructions = fn_body_address..(self.pc + end);
+        self.pc += end;
+        Ok(function)
+    }
+
+    fn parameters(&self, function: &Function) -> Result<Vec<Parameter<'a>>, VMError> {
+        let mut parameters = Vec::with_capacity(function.arguments);
+        let mut address = function.parameters;
+        for _ in 0..function.arguments {
+            match self.instructions.get(address) {
+                Some(Instruction::ArgumentName { name } | Instruction::RestArgument { name }) => {
+                    parameters.push((*name, None));
+                    address += 1;
+                }
+                Some(Instruction::DefaultArgument { name, len }) => {
+                    parameters.push((*name, Some((address + 1)..(address + 1 + len))));
+                    address += 1 + len;
+                }
+                _ => return Err(VMError::InvalidBytecode),
+            }
+        }
+        Ok(parameters)
+    }
+
+    /// Gives a function the arguments at the top of the stack, the last of which are given by the names.
+    /// The other arguments take the first positions that have not been given yet, keeping the order they were given in,
+    /// and those that are left over are added to the rest argument of a variadic function
+    fn give_arguments(&mut self, function: &mut Function, len: usize, names: &[&str], name: Option<&str>) -> Result<(), String> {
+        let function_name = name.map_or("A function".to_string(), |name| format!("The function `{name}`"));
+        let parameters = self.parameters(function).map_err(|_| "The bytecode of a function appears to be invalid!".to_string())?;
+        let mut arguments = self.stack.split_off(self.stack.len().saturating_sub(len));
+        let named = arguments.split_off(arguments.len().saturating_sub(names.len()));
+
+        function.is_partial.resize(function.arguments, None);
+        let given = function.is_partial.iter().filter(|argument| argument.is_some()).count() + len;
+        let fixed = function.fixed_arguments();
+        for value in arguments {
+            let (arguments, rest) = function.is_partial.split_at_mut(fixed);
+            match (arguments.iter_mut().find(|argument| argument.is_none()), rest.first_mut()) {
+                (Some(argument), _) => *argument = Some(value),
+                (None, Some(Some(Value::Array(values)))) => values.push(value),
+                (None, Some(rest)) => *rest = Some(Value::Array(vec![value])),
+                (None, None) => return Err(format!("{function_name} was provided too many arguments! Expected: {}, got: {given} argument(s)", function.arguments)),
+            }
+        }
+        for (argument, value) in names.iter().zip(named) {
+            let Some(position) = parameters.iter().position(|(name, _)| name == argument) else {
+                return Err(format!("{function_name} does not have an argument named `{argument}`!"));
+            };
+            if position >= fixed {
+                return Err(format!("{function_name} cannot be given the rest argument `{argument}` by name!"));
+            }
+            if function.is_partial[position].is_some() {
+                return Err(format!("{function_name} was given the argument `{argument}` more than once!"));
+            }
+            function.is_partial[position] = Some(value);
+        }
+        Ok(())
+    }
+
+    /// Whether a function has been given all of the arguments that do not have a default value.
+    /// A variadic function does not wait for its rest argument, so it is called as soon as it has the others
+    fn is_ready(&self, function: &Function) -> Result<bool, VMError> {
+        Ok(self.parameters(function)?.iter().zip(&function.is_partial).take(function.fixed_arguments())
+            .all(|((_, default), value)| default.is_some() || value.is_some()))
+    }
+
+    /// Calls a function value with the arguments, which is used by the builtin functions that take functions.
+    /// Fewer arguments than the function takes result in a partial function, like any other call
+    pub fn call(&mut self, function: &Value, arguments: Vec<Value>) -> Result<Value, String> {
+        let mut function = match function {
+            Value::Function(function) => function.clone(),
+            value => return Err(format!("Expected a value of type {{Function}} but found a value of type {}!", value.type_of())),
+        };
+
+        let len = arguments.len();
+        self.stack.extend(arguments);
+        self.give_arguments(&mut function, len, &[], None)?;
+        let depth = self.stack.len();
+        match self.is_ready(&function).and_then(|ready| if ready { self.run_function(&function).map(|_| true) } else { Ok(false) }) {
+            Ok(true) => (),
+            Ok(false) => return Ok(Value::Function(function)),
+            Err(VMError::ErrString(message)) => return Err(message),
+            Err(VMError::InvalidBytecode) => return Err("The bytecode of a function appears to be invalid!".to_string()),
+        }
+        // The result is the only value that the function leaves on the stack
+        match self.stack.len() {
+            length if length == depth + 1 => Ok(self.stack.pop().unwrap()),
+            _ => Err("A function did not produce a value (Likely an internal error)!".to_string()),
+        }
+    }
+
+    /// Moves the functions of a module that was run elsewhere to its instructions at `start`, along with the modules it imports,
+    /// whose instructions are within its own
+    fn move_module(&mut self, module: &str, start: usize, len: usize) {
+        let from = self.modules[module].start;
+        if from == start {
+            return;
+        }
+        for module in self.modules.values_mut().filter(|other| (from..from + len).contains(&other.start)) {
+            module.start = module.start - from + start;
+            for value in module.declarations.values_mut() {
+                Self::move_functions(value, &[(from..from + len, start)]);
+            }
+        }
+    }
+
+    /// Moves the functions in a value whose instructions are in one of the ranges, so that the range starts at the position given with it
+    fn move_functions(value: &mut Value, moves: &[(Range<usize>, usize)]) {
+        match value {
+            Value::Function(function) | Value::PartialFunction(function, _) => {
+                if let Some((range, start)) = moves.iter().find(|(range, _)| range.contains(&function.parameters)) {
+                    function.parameters = function.parameters - range.start + start;
+                    function.instructions = (function.instructions.start - range.start + start)..(function.instructions.end - range.start + start);
+                }
+                for value in function.is_partial.iter_mut().chain(function.captured.iter_mut()).flatten() {
+                    Self::move_functions(value, moves);
+                }
+            }
+            Value::Array(values) => values.iter_mut().for_each(|value| Self::move_functions(value, moves)),
+            Value::Map(map) => map.values_mut().for_each(|value| Self::move_functions(value, moves)),
+            _ => (),
+        }
+        if let Value::PartialFunction(_, values) = value {
+            values.iter_mut().for_each(|value| Self::move_functions(value, moves));
+        }
+    }
+
+    /// Finds the instructions of the functions in a value, from the instruction that declares each one to the end of its body
+    fn function_code(&self, value: &Value, code: &mut Vec<Range<usize>>) {
+        match value {
+            Value::Function(function) | Value::PartialFunction(function, _) => {
+                if let Some(start) = function.parameters.checked_sub(3).filter(|_| function.instructions.end <= self.instructions.len()) {
+                    code.push(start..function.instructions.end);
+                }
+                for value in function.is_partial.iter().chain(function.captured.iter()).flatten() {
+                    self.function_code(value, code);
+                }
+            }
+            Value::Array(values) => values.iter().for_each(|value| self.function_code(value, code)),
+            Value::Map(map) => map.values().for_each(|value| self.function_code(value, code)),
+            _ => (),
+        }
+        if let Value::PartialFunction(_, values) = value {
+            values.iter().for_each(|value| self.function_code(value, code));
+        }
+    }
+
+    /// Copies the instructions of the functions in the symbols, such as the lambdas kept in variables, and moves the functions
+    /// to the copy as if it was at the start of the instructions. The repl runs each line with new instructions, so the copy
+    /// is put before them for the functions to still be called on the next lines
+    pub fn take_functions(&mut self) -> Vec<Instruction<'a>> {
+        let mut code = vec![];
+        for value in self.symbols.values() {
+            self.function_code(value, &mut code);
+        }
+        // A function within the instructions of another, like a lambda that a function returns, is copied along with it
+        code.sort_by_key(|range| (range.start, std::cmp::Reverse(range.end)));
+        let mut functions = vec![Instruction::Jump { offset: 0 }];
+        let mut moves: Vec<(Range<usize>, usize)> = vec![];
+        for range in code {
+            if moves.last().is_some_and(|(last, _)| range.end <= last.end) {
+                continue;
+            }
+            moves.push((range.clone(), functions.len()));
+            functions.extend_from_slice(&self.instructions[range]);
+        }
+        if moves.is_empty() {
+            return vec![];
+        }
+
+        // The copy is jumped over, since its instructions are only run when the functions are called
+        functions[0] = Instruction::Jump { offset: functions.len() - 1 };
+        for value in self.symbols.values_mut() {
+            Self::move_functions(value, &moves);
+        }
+        functions
+    }
+
     pub fn get_symbols(self) -> HashMap<&'a str, Value> {
         self.symbols
     }